@@ -40,6 +40,24 @@ fn bench_taxes(c: &mut Criterion) {
     });
 }
 
+fn bench_compiled_tax_plan(c: &mut Criterion) {
+    let vu = BigDecimal::from_str("100.0").unwrap();
+    let qty = BigDecimal::from_str("1.0").unwrap();
+
+    let mut taxer = baggins::tax::TaxComputer::new();
+    let _ = taxer.add_tax_from_f64(18.0, Stage::OverTaxable, TaxMode::Percentual);
+    let _ = taxer.add_tax_from_f64(10.0, Stage::OverTaxable, TaxMode::Percentual);
+    let _ = taxer.add_tax_from_f64(0.5, Stage::OverTaxable, TaxMode::AmountUnit);
+
+    let plan = taxer.compile();
+
+    c.bench_function("bench_compiled_tax_plan", |b| {
+        b.iter(|| {
+            let _ = plan.apply(black_box(&vu), black_box(&qty));
+        });
+    });
+}
+
 
 fn bench_baggins(c: &mut Criterion) {
     let mut cl = DetailCalculator::new();
@@ -75,6 +93,41 @@ fn bench_baggins(c: &mut Criterion) {
     });
 }
 
+fn precision_growth_calculator(scale_cap: Option<i64>) -> DetailCalculator {
+    let mut cl = DetailCalculator::new();
+    cl.set_scale_cap(scale_cap);
+
+    let _ = cl.add_discount_from_str("7.77", discount::Mode::Percentual);
+
+    let _ = cl.add_tax_from_str("18.333", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    let _ = cl.add_tax_from_str("3.1415", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    let _ = cl.add_tax_from_str("11.111", tax::Stage::OverTax, tax::Mode::Percentual);
+    let _ = cl.add_tax_from_str("2.7182", tax::Stage::OverTax, tax::Mode::Percentual);
+    let _ = cl.add_tax_from_str("0.577", tax::Stage::OverTaxIgnorable, tax::Mode::Percentual);
+
+    cl
+}
+
+fn bench_precision_growth(c: &mut Criterion) {
+    let vu = BigDecimal::from_str("99.99").unwrap();
+    let qty = BigDecimal::from_str("7.0").unwrap();
+
+    let mut uncapped = precision_growth_calculator(None);
+    let mut capped = precision_growth_calculator(Some(16));
+
+    c.bench_function("bench_precision_growth_uncapped", |b| {
+        b.iter(|| {
+            let _ = uncapped.compute(black_box(vu.clone()), black_box(qty.clone()), None);
+        });
+    });
+
+    c.bench_function("bench_precision_growth_scale_cap_16", |b| {
+        b.iter(|| {
+            let _ = capped.compute(black_box(vu.clone()), black_box(qty.clone()), None);
+        });
+    });
+}
+
 // fn bench_compute(c: &mut Criterion) {
 //     let vu = BigDecimal::from_str("100.0").unwrap();
 //     let qty = BigDecimal::from_str("1.0").unwrap();
@@ -103,5 +156,12 @@ fn bench_baggins(c: &mut Criterion) {
 //     });
 // }
 
-criterion_group!(benches, bench_discount, bench_taxes, bench_baggins);
+criterion_group!(
+    benches,
+    bench_discount,
+    bench_taxes,
+    bench_compiled_tax_plan,
+    bench_baggins,
+    bench_precision_growth
+);
 criterion_main!(benches);
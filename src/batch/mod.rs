@@ -0,0 +1,93 @@
+//! batch
+//!
+//! `batch` module provides ways to compute whole files of detail lines at once.
+//! Requires the `csv` feature.
+//!
+use std::{io, str::FromStr};
+
+use bigdecimal::BigDecimal;
+
+use crate::{BagginsError, BagginsResult, Calculator, DetailCalculator};
+
+impl DetailCalculator {
+    /// reads the `unit_value,qty` rows of a csv with headers, computes each row with the
+    /// registered discounts and taxes, and writes a `unit_value,qty,net,tax,brute` row for each,
+    /// with the values after discounts.
+    /// Could return [BagginsError::InvalidDecimalValue] if a row has an invalid value, and
+    /// [BagginsError::NegativeQty] [BagginsError::Other] if a row can't be read, computed or written
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use baggins::{Calculator, DetailCalculator, tax};
+    ///
+    /// let mut c = DetailCalculator::new();
+    /// let _ = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    ///
+    /// let mut output = Vec::new();
+    /// c.process_csv("unit_value,qty\n100,2\n".as_bytes(), &mut output).unwrap();
+    ///
+    /// assert_eq!(
+    ///     String::from_utf8(output).unwrap(),
+    ///     "unit_value,qty,net,tax,brute\n100,2,200,36,236\n"
+    /// );
+    /// ```
+    pub fn process_csv<R: io::Read, W: io::Write>(
+        &mut self,
+        reader: R,
+        writer: W,
+    ) -> BagginsResult<()> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(reader);
+        let mut writer = csv::Writer::from_writer(writer);
+
+        writer
+            .write_record(["unit_value", "qty", "net", "tax", "brute"])
+            .map_err(|err| BagginsError::Other(format!("writing csv header {}", err)))?;
+
+        for (index, record) in reader.records().enumerate() {
+            let row = index + 1;
+            let record = record
+                .map_err(|err| BagginsError::Other(format!("reading csv row {} {}", row, err)))?;
+
+            let unit_value = parse_field(&record, 0, "unit_value", row)?;
+            let qty = parse_field(&record, 1, "qty", row)?;
+
+            let calc = self.compute(unit_value.clone(), qty.clone(), None)?;
+            let values = calc.with_discount_values();
+
+            writer
+                .write_record([
+                    unit_value.to_string(),
+                    qty.to_string(),
+                    values.net.to_string(),
+                    values.tax.to_string(),
+                    values.brute.to_string(),
+                ])
+                .map_err(|err| BagginsError::Other(format!("writing csv row {} {}", row, err)))?;
+        }
+
+        writer
+            .flush()
+            .map_err(|err| BagginsError::Other(format!("writing csv {}", err)))
+    }
+}
+
+fn parse_field(
+    record: &csv::StringRecord,
+    position: usize,
+    name: &str,
+    row: usize,
+) -> BagginsResult<BigDecimal> {
+    let field = record
+        .get(position)
+        .ok_or_else(|| BagginsError::Other(format!("missing {} at csv row {}", name, row)))?;
+
+    BigDecimal::from_str(field).map_err(|err| {
+        BagginsError::InvalidDecimalValue(format!(
+            "invalid {} {} at csv row {} {}",
+            name, field, row, err
+        ))
+    })
+}
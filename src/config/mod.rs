@@ -0,0 +1,379 @@
+//! config
+//!
+//! `config` module provides a serializable representation of the discounts and taxes
+//! registered in a [DetailCalculator], so a calculator can be persisted and rebuilt.
+//!
+use bigdecimal::BigDecimal;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    discount::{self, Discounter, Stacking},
+    tax::{self, Stage, Stager, TaxBase, Taxer},
+    BagginsError, BagginsResult, DetailCalculator, RoundingTarget, SurchargeMode,
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+/// a registered discount
+pub struct DiscountConfig {
+    /// the discount mode
+    pub mode: discount::Mode,
+    /// the discount value
+    pub value: BigDecimal,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+/// the cumulated taxes of a [tax::Stage]
+pub struct StageConfig {
+    /// cumulated percentual taxes
+    pub percentual: BigDecimal,
+    /// cumulated amount line taxes
+    pub amount_line: BigDecimal,
+    /// cumulated amount unit taxes
+    pub amount_unit: BigDecimal,
+    /// percentual taxes applied up to an assessable cap, as pairs of percent and cap
+    #[serde(default)]
+    pub capped_percentuals: Vec<(BigDecimal, BigDecimal)>,
+    /// cumulated percent of tax taxes, only used by the [tax::Stage::OverTax] stage
+    #[serde(default = "crate::zero")]
+    pub percent_of_tax: BigDecimal,
+    /// the scale the taxes of the stage are rounded to, see [tax::TaxComputer::set_stage_scale]
+    #[serde(default)]
+    pub scale: Option<u32>,
+}
+
+impl Default for StageConfig {
+    fn default() -> Self {
+        Self {
+            percentual: crate::zero(),
+            amount_line: crate::zero(),
+            amount_unit: crate::zero(),
+            capped_percentuals: Vec::new(),
+            percent_of_tax: crate::zero(),
+            scale: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+/// the serializable configuration of a [DetailCalculator]
+///
+/// # Example
+///
+/// ```
+/// use baggins::{Calculator, DetailCalculator, config::PricingConfig, discount, tax};
+///
+/// let mut c = DetailCalculator::new();
+/// let _ = c.add_discount_from_str("10.0", discount::Mode::Percentual);
+/// let _ = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+///
+/// let config = PricingConfig::from(&c);
+/// let rebuilt = DetailCalculator::from_config(&config).unwrap();
+///
+/// assert_eq!(PricingConfig::from(&rebuilt), config);
+/// ```
+pub struct PricingConfig {
+    /// the registered discounts in the order they were added
+    pub discounts: Vec<DiscountConfig>,
+    /// how the discounts are combined
+    pub stacking: Stacking,
+    /// the override unit price, see [DetailCalculator::set_override_unit_price]
+    pub override_unit_price: Option<BigDecimal>,
+    /// the increment discounts are rounded to, see [discount::DiscountComputer::set_discount_increment]
+    pub discount_increment: Option<BigDecimal>,
+    /// the max percent of the gross the combined discounts can take, see [discount::DiscountComputer::set_max_total_discount_pct]
+    pub max_total_discount_pct: Option<BigDecimal>,
+    /// the min percent of the gross discounted, see [discount::DiscountComputer::set_min_discount_pct]
+    pub min_discount_pct: Option<BigDecimal>,
+    /// taxes of the [tax::Stage::OverTaxable] stage
+    pub over_taxable: StageConfig,
+    /// taxes of the [tax::Stage::OverTax] stage
+    pub over_tax: StageConfig,
+    /// taxes of the [tax::Stage::OverTaxIgnorable] stage
+    pub over_tax_ignorable: StageConfig,
+    /// taxes of the [tax::Stage::OverTaxable] stage calculated over the [tax::TaxBase::Gross]
+    pub gross_over_taxable: StageConfig,
+    /// taxes of the [tax::Stage::OverTax] stage calculated over the [tax::TaxBase::Gross]
+    pub gross_over_tax: StageConfig,
+    /// taxes of the [tax::Stage::OverTaxIgnorable] stage calculated over the [tax::TaxBase::Gross]
+    pub gross_over_tax_ignorable: StageConfig,
+    /// the scale cap of the calculator, see [DetailCalculator::set_scale_cap]
+    pub scale_cap: Option<i64>,
+    /// which values are rounded, see [DetailCalculator::set_rounding]
+    pub rounding: RoundingTarget,
+    /// the scale values are rounded to
+    pub rounding_scale: i64,
+    /// the threshold under which values snap to zero, see [DetailCalculator::set_epsilon_snap]
+    pub epsilon_snap: Option<BigDecimal>,
+    /// the max scale of the inputs, see [DetailCalculator::set_strict_input_scale]
+    pub strict_input_scale: Option<u32>,
+    /// the scale percentages are rounded to, see [DetailCalculator::set_percent_display_scale]
+    pub percent_display_scale: Option<u32>,
+    /// the discount tiers, see [DetailCalculator::set_discount_tiers]
+    pub discount_tiers: discount::DiscountTiers,
+    /// true if the divisions are deferred, see [DetailCalculator::set_exact_rational]
+    pub exact_rational: bool,
+    /// the first and last dates of the tax holiday, see [DetailCalculator::set_tax_holiday]
+    pub tax_holiday: Option<(NaiveDate, NaiveDate)>,
+    /// cumulated amount line surcharges
+    pub surcharge_line: BigDecimal,
+    /// cumulated amount unit surcharges
+    pub surcharge_unit: BigDecimal,
+}
+
+impl Default for PricingConfig {
+    /// the configuration of a new [DetailCalculator]
+    fn default() -> Self {
+        Self::from(&DetailCalculator::new())
+    }
+}
+
+fn stage_config(stage: impl Stager) -> StageConfig {
+    StageConfig {
+        percentual: stage.percent(),
+        amount_line: stage.amount_line(),
+        amount_unit: stage.amount_by_qty(),
+        capped_percentuals: stage.capped_percentuals(),
+        percent_of_tax: stage.percent_of_tax(),
+        scale: stage.scale(),
+    }
+}
+
+impl From<&DetailCalculator> for PricingConfig {
+    fn from(calculator: &DetailCalculator) -> Self {
+        Self {
+            discounts: calculator
+                .discount_handler
+                .discounts()
+                .iter()
+                .map(|(mode, value)| DiscountConfig {
+                    mode: mode.clone(),
+                    value: value.clone(),
+                })
+                .collect(),
+            stacking: calculator.discount_handler.stacking(),
+            override_unit_price: calculator.discount_handler.override_unit_price().cloned(),
+            discount_increment: calculator.discount_handler.discount_increment().cloned(),
+            max_total_discount_pct: calculator
+                .discount_handler
+                .max_total_discount_pct()
+                .cloned(),
+            min_discount_pct: calculator.discount_handler.min_discount_pct().cloned(),
+            over_taxable: stage_config(calculator.tax_handler.over_taxables()),
+            over_tax: stage_config(calculator.tax_handler.over_taxes()),
+            over_tax_ignorable: stage_config(calculator.tax_handler.over_tax_ignorables()),
+            gross_over_taxable: stage_config(calculator.gross_tax_handler.over_taxables()),
+            gross_over_tax: stage_config(calculator.gross_tax_handler.over_taxes()),
+            gross_over_tax_ignorable: stage_config(
+                calculator.gross_tax_handler.over_tax_ignorables(),
+            ),
+            scale_cap: calculator.scale_cap,
+            rounding: calculator.rounding,
+            rounding_scale: calculator.rounding_scale,
+            epsilon_snap: calculator.epsilon_snap.clone(),
+            strict_input_scale: calculator.strict_input_scale,
+            percent_display_scale: calculator.percent_display_scale,
+            discount_tiers: calculator.discount_tiers.clone(),
+            exact_rational: calculator.exact_rational,
+            tax_holiday: calculator.tax_holiday(),
+            surcharge_line: calculator.surcharge_line.clone(),
+            surcharge_unit: calculator.surcharge_unit.clone(),
+        }
+    }
+}
+
+/// returns the configuration with the discounts and taxes that can be registered in any order
+/// sorted, so equivalent configurations are equal. Stacked discounts are cumulated by mode, as
+/// the calculator does, while the order of the discounts is kept for [Stacking::FirstMatch].
+fn canonical(mut config: PricingConfig) -> PricingConfig {
+    match config.stacking {
+        Stacking::StackAll => {
+            let modes = [
+                discount::Mode::Percentual,
+                discount::Mode::AmountLine,
+                discount::Mode::AmountUnit,
+            ];
+
+            config.discounts = modes
+                .into_iter()
+                .map(|mode| DiscountConfig {
+                    value: config
+                        .discounts
+                        .iter()
+                        .filter(|discount| discount.mode == mode)
+                        .map(|discount| &discount.value)
+                        .sum(),
+                    mode,
+                })
+                .filter(|discount| discount.value != crate::zero())
+                .collect();
+        }
+        Stacking::BestSingle => config.discounts.sort_by(|a, b| {
+            (a.mode.clone() as u8)
+                .cmp(&(b.mode.clone() as u8))
+                .then_with(|| a.value.cmp(&b.value))
+        }),
+        Stacking::FirstMatch => {}
+    }
+
+    for stage in [
+        &mut config.over_taxable,
+        &mut config.over_tax,
+        &mut config.over_tax_ignorable,
+        &mut config.gross_over_taxable,
+        &mut config.gross_over_tax,
+        &mut config.gross_over_tax_ignorable,
+    ] {
+        stage.capped_percentuals.sort();
+    }
+
+    config
+}
+
+impl DetailCalculator {
+    /// returns true if both calculators are configured identically: the same cumulated discounts
+    /// and taxes by mode and stage and the same settings, no matter the order the discounts and
+    /// taxes were added in. Useful to detect configuration drift.
+    pub fn config_eq(&self, other: &DetailCalculator) -> bool {
+        canonical(PricingConfig::from(self)) == canonical(PricingConfig::from(other))
+    }
+
+    /// Creates a new [`DetailCalculator`] with the discounts and taxes of the specified [PricingConfig].
+    /// Could return [BagginsError::Discount] [BagginsError::Tax] if any discount or tax is not valid,
+    /// or [BagginsError::Other] if a surcharge is not valid
+    pub fn from_config(config: &PricingConfig) -> BagginsResult<Self> {
+        let mut calculator = Self::new();
+        calculator.set_scale_cap(config.scale_cap);
+        calculator.set_rounding(config.rounding, config.rounding_scale);
+        calculator.set_epsilon_snap(config.epsilon_snap.clone());
+        calculator.set_strict_input_scale(config.strict_input_scale);
+        calculator.set_percent_display_scale(config.percent_display_scale);
+        calculator.set_discount_tiers(config.discount_tiers.clone());
+        calculator.set_exact_rational(config.exact_rational);
+        calculator.discount_handler.set_stacking(config.stacking);
+
+        if let Some(range) = config.tax_holiday {
+            if let Some(err) = calculator.set_tax_holiday(range) {
+                return Err(BagginsError::Tax(err));
+            }
+        }
+
+        if let Some(err) = calculator.set_override_unit_price(config.override_unit_price.clone()) {
+            return Err(BagginsError::Discount(err));
+        }
+
+        if let Some(err) = calculator
+            .discount_handler
+            .set_discount_increment(config.discount_increment.clone())
+        {
+            return Err(BagginsError::Discount(err));
+        }
+
+        if let Some(err) = calculator
+            .discount_handler
+            .set_max_total_discount_pct(config.max_total_discount_pct.clone())
+        {
+            return Err(BagginsError::Discount(err));
+        }
+
+        if let Some(err) = calculator
+            .discount_handler
+            .set_min_discount_pct(config.min_discount_pct.clone())
+        {
+            return Err(BagginsError::Discount(err));
+        }
+
+        let surcharges = [
+            (SurchargeMode::AmountLine, &config.surcharge_line),
+            (SurchargeMode::AmountUnit, &config.surcharge_unit),
+        ];
+
+        for (mode, value) in surcharges {
+            if let Some(err) = calculator.add_surcharge(value.clone(), mode) {
+                return Err(err);
+            }
+        }
+
+        for discount in &config.discounts {
+            if let Some(err) = calculator
+                .discount_handler
+                .add_discount(discount.value.clone(), discount.mode.clone())
+            {
+                return Err(BagginsError::Discount(err));
+            }
+        }
+
+        let stages = [
+            (
+                TaxBase::DiscountedNet,
+                Stage::OverTaxable,
+                &config.over_taxable,
+            ),
+            (TaxBase::DiscountedNet, Stage::OverTax, &config.over_tax),
+            (
+                TaxBase::DiscountedNet,
+                Stage::OverTaxIgnorable,
+                &config.over_tax_ignorable,
+            ),
+            (
+                TaxBase::Gross,
+                Stage::OverTaxable,
+                &config.gross_over_taxable,
+            ),
+            (TaxBase::Gross, Stage::OverTax, &config.gross_over_tax),
+            (
+                TaxBase::Gross,
+                Stage::OverTaxIgnorable,
+                &config.gross_over_tax_ignorable,
+            ),
+        ];
+
+        for (base, stage, stage_config) in stages {
+            let taxes = [
+                (tax::Mode::Percentual, &stage_config.percentual),
+                (tax::Mode::AmountLine, &stage_config.amount_line),
+                (tax::Mode::AmountUnit, &stage_config.amount_unit),
+            ];
+
+            for (mode, value) in taxes {
+                if let Some(err) =
+                    calculator.add_tax_with_base(value.clone(), stage.clone(), mode, base)
+                {
+                    return Err(BagginsError::Tax(err));
+                }
+            }
+
+            if stage == Stage::OverTax {
+                if let Some(err) = calculator.add_tax_with_base(
+                    stage_config.percent_of_tax.clone(),
+                    stage.clone(),
+                    tax::Mode::PercentOfTax,
+                    base,
+                ) {
+                    return Err(BagginsError::Tax(err));
+                }
+            }
+
+            let handler = match base {
+                TaxBase::DiscountedNet => &mut calculator.tax_handler,
+                TaxBase::Gross => &mut calculator.gross_tax_handler,
+            };
+
+            if let Some(scale) = stage_config.scale {
+                handler.set_stage_scale(stage.clone(), scale);
+            }
+
+            for (percent, cap) in &stage_config.capped_percentuals {
+                if let Some(err) = handler.add_tax_with_assessable_cap(
+                    percent.clone(),
+                    stage.clone(),
+                    Some(cap.clone()),
+                ) {
+                    return Err(BagginsError::Tax(err));
+                }
+            }
+        }
+
+        Ok(calculator)
+    }
+}
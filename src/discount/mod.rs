@@ -2,14 +2,15 @@
 //!
 //! `discount` module provides ways to calculate discounts.
 //!
-use std::{fmt, str::FromStr};
+use std::{collections::BTreeMap, fmt, str::FromStr};
 
-use bigdecimal::{BigDecimal, FromPrimitive};
+use bigdecimal::{BigDecimal, FromPrimitive, RoundingMode};
+use serde::{Deserialize, Serialize};
 
 use crate::hundred;
 
 // Different types of discounts are represented here we use the mode identificator to identify them
-#[derive(PartialEq)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Mode {
     /// It's a discount applied as a percentage over a value as when someone says *a discount of 10%*
     Percentual,
@@ -49,7 +50,87 @@ impl Mode {
     }
 }
 
-#[derive(Debug)]
+/// How the registered discounts are combined when a line qualifies for several of them
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum Stacking {
+    /// every registered discount is applied. This is the default
+    #[default]
+    StackAll,
+
+    /// each registered discount is evaluated independently and only the one
+    /// yielding the largest reduction is applied
+    BestSingle,
+
+    /// only the first registered discount is applied
+    FirstMatch,
+}
+
+/// Which of two alternative discounts is applied, see [DiscountComputer::best_discount]
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum BetterOrWorse {
+    /// the greater discount is applied, as in "10% or $15 off, whichever is greater"
+    Better,
+
+    /// the lesser discount is applied
+    Worse,
+}
+
+/// A discount operation applied over the running base of a line, see
+/// [crate::DetailCalculator::compute_sequenced]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum DiscountStep {
+    /// subtracts a fixed amount from the running base
+    Amount(BigDecimal),
+
+    /// subtracts a percent of the running base
+    Percent(BigDecimal),
+}
+
+/// A matrix of percentual discounts by tier key, as the Bronze, Silver and Gold tiers of a
+/// membership, see [crate::DetailCalculator::compute_for_tier]
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct DiscountTiers {
+    tiers: BTreeMap<String, BigDecimal>,
+}
+
+impl DiscountTiers {
+    /// returns an empty matrix, without any tier registered
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// registers the percentual discount of the specified tier, replacing any previous one.
+    /// Can return [DiscountError::NegativeValue] [DiscountError::OverMaxDiscount] wrapped in [Option]
+    pub fn add_tier<S: Into<String>>(
+        &mut self,
+        tier: S,
+        percent: BigDecimal,
+    ) -> Option<DiscountError<String>> {
+        if percent < crate::zero() {
+            return Some(DiscountError::NegativeValue(format!(
+                "negative tier discount {}",
+                percent
+            )));
+        }
+
+        if percent > hundred() {
+            return Some(DiscountError::OverMaxDiscount(format!(
+                "tier discount {} over 100%",
+                percent
+            )));
+        }
+
+        self.tiers.insert(tier.into(), percent);
+        None
+    }
+
+    /// returns the percentual discount of the specified tier, if registered
+    pub fn rate(&self, tier: &str) -> Option<&BigDecimal> {
+        self.tiers.get(tier)
+    }
+}
+
+#[derive(Debug, PartialEq)]
 /// Possible errors of the discount processing
 pub enum DiscountError<S: Into<String>> {
     /// a negative value is not allowed, How do you discount 10% of -10?, or the -10% of 10
@@ -84,6 +165,19 @@ impl<S: Into<String> + Clone> fmt::Display for DiscountError<S> {
     }
 }
 
+impl<S: Into<String> + Clone + fmt::Debug> std::error::Error for DiscountError<S> {}
+
+/// returns the percent of the gross line value taken by the discount value, or zero for a zero
+/// gross. It is the cumulated percentual discount returned by [Discounter::compute] and the
+/// total discount percent of a [crate::Calculation]
+pub fn discount_percent(discount_value: &BigDecimal, gross: &BigDecimal) -> BigDecimal {
+    if *gross == crate::zero() {
+        crate::zero()
+    } else {
+        discount_value * crate::hundred() / gross
+    }
+}
+
 /// Represents a thing able to calculates discounts
 pub trait Discounter {
     /// adds a f64 value as a discount of the specified mode. Using f64 values may cause some precission loss
@@ -150,7 +244,8 @@ pub trait Discounter {
     /// Removes the registered discounts over the discounted value received.
     /// When successful returns a tuple of [BigDecimal] with the undiscounted value, the removed discount value,
     /// and the percentual discount removed.
-    /// Can return [DiscountError::NegativeValue]
+    /// Can return [DiscountError::NegativeValue] [DiscountError::OverMaxDiscount], and [DiscountError::Other]
+    /// when the recovered discountable would be lower than the discounted value
     fn un_discount(
         &self,
         discounted: BigDecimal,
@@ -182,6 +277,45 @@ pub trait Discounter {
     fn ratio(&self, discounted: BigDecimal, discount: BigDecimal) -> BigDecimal {
         hundred() * &discount / (&discounted + &discount)
     }
+
+    /// adds a [BigDecimal] value as a discount of the specified mode.
+    /// Same as [Discounter::add_discount] but returns a [Result] so it can be used with `?`
+    fn try_add_discount(
+        &mut self,
+        discount: BigDecimal,
+        discount_mode: Mode,
+    ) -> Result<(), DiscountError<String>> {
+        match self.add_discount(discount, discount_mode) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// adds a f64 value as a discount of the specified mode.
+    /// Same as [Discounter::add_discount_from_f64] but returns a [Result] so it can be used with `?`
+    fn try_add_discount_from_f64(
+        &mut self,
+        discount: f64,
+        discount_mode: Mode,
+    ) -> Result<(), DiscountError<String>> {
+        match self.add_discount_from_f64(discount, discount_mode) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// adds a string value as a discount of the specified mode.
+    /// Same as [Discounter::add_discount_from_str] but returns a [Result] so it can be used with `?`
+    fn try_add_discount_from_str<S: Into<String>>(
+        &mut self,
+        discount: S,
+        discount_mode: Mode,
+    ) -> Result<(), DiscountError<String>> {
+        match self.add_discount_from_str(discount, discount_mode) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
 }
 
 /// calculates discounts
@@ -237,10 +371,17 @@ pub trait Discounter {
 /// }
 ///```
 ///
+#[allow(clippy::needless_doctest_main)]
 pub struct DiscountComputer {
     percentual: BigDecimal,
     amount_line: BigDecimal,
     amount_unit: BigDecimal,
+    discounts: Vec<(Mode, BigDecimal)>,
+    stacking: Stacking,
+    override_unit_price: Option<BigDecimal>,
+    discount_increment: Option<BigDecimal>,
+    max_total_discount_pct: Option<BigDecimal>,
+    min_discount_pct: Option<BigDecimal>,
 }
 
 impl DiscountComputer {
@@ -249,6 +390,393 @@ impl DiscountComputer {
             percentual: crate::zero(),
             amount_line: crate::zero(),
             amount_unit: crate::zero(),
+            discounts: Vec::new(),
+            stacking: Stacking::default(),
+            override_unit_price: None,
+            discount_increment: None,
+            max_total_discount_pct: None,
+            min_discount_pct: None,
+        }
+    }
+
+    /// sets how the registered discounts are combined by [Discounter::compute].
+    /// un_discount always removes all the registered discounts stacked.
+    pub fn set_stacking(&mut self, stacking: Stacking) {
+        self.stacking = stacking;
+    }
+
+    /// returns how the registered discounts are combined
+    pub fn stacking(&self) -> Stacking {
+        self.stacking
+    }
+
+    /// sets a new unit price for the item, so the discount is the difference between the unit value
+    /// and the override price for each unit. While set, the override replaces the registered discounts.
+    /// `None` removes the override. [Discounter::compute] fails if the override is over the unit value.
+    /// Could return [DiscountError::NegativeValue]
+    pub fn set_override_unit_price(
+        &mut self,
+        override_price: Option<BigDecimal>,
+    ) -> Option<DiscountError<String>> {
+        if let Some(price) = &override_price {
+            if *price < crate::zero() {
+                return Some(DiscountError::NegativeValue(format!(
+                    "negative override price {}",
+                    price
+                )));
+            }
+        }
+
+        self.override_unit_price = override_price;
+
+        None
+    }
+
+    /// returns the override unit price, if any
+    pub fn override_unit_price(&self) -> Option<&BigDecimal> {
+        self.override_unit_price.as_ref()
+    }
+
+    /// sets an increment the discount value is rounded to, like 0.05 to round a discount of
+    /// 22.26 to 22.25. `None` keeps the discount value as it is.
+    /// Could return [DiscountError::NegativeValue] if the increment is not over zero
+    pub fn set_discount_increment(
+        &mut self,
+        increment: Option<BigDecimal>,
+    ) -> Option<DiscountError<String>> {
+        if let Some(increment) = &increment {
+            if *increment <= crate::zero() {
+                return Some(DiscountError::NegativeValue(format!(
+                    "discount increment must be over zero {}",
+                    increment
+                )));
+            }
+        }
+
+        self.discount_increment = increment;
+
+        None
+    }
+
+    /// returns the increment the discount value is rounded to, if any
+    pub fn discount_increment(&self) -> Option<&BigDecimal> {
+        self.discount_increment.as_ref()
+    }
+
+    /// sets the max percent of the gross line value the combined discounts of every mode can take.
+    /// [Discounter::compute] fails with [DiscountError::OverMaxDiscount] over it. `None` removes the cap.
+    /// Could return [DiscountError::NegativeValue] [DiscountError::OverMaxDiscount]
+    pub fn set_max_total_discount_pct(
+        &mut self,
+        max: Option<BigDecimal>,
+    ) -> Option<DiscountError<String>> {
+        if let Some(max) = &max {
+            if *max < crate::zero() {
+                return Some(DiscountError::NegativeValue(format!(
+                    "negative max total discount percent {}",
+                    max
+                )));
+            }
+
+            if *max > crate::hundred() {
+                return Some(DiscountError::OverMaxDiscount(format!(
+                    "max total discount percent over 100 {}",
+                    max
+                )));
+            }
+        }
+
+        self.max_total_discount_pct = max;
+
+        None
+    }
+
+    /// returns the max percent of the gross line value the combined discounts can take, if any
+    pub fn max_total_discount_pct(&self) -> Option<&BigDecimal> {
+        self.max_total_discount_pct.as_ref()
+    }
+
+    /// sets the min percent of the gross line value discounted, like a loyalty guarantee of always
+    /// at least 5% off. When the registered discounts give less, the discount value is raised up to
+    /// it. `None` removes the floor.
+    /// Could return [DiscountError::NegativeValue] [DiscountError::OverMaxDiscount]
+    pub fn set_min_discount_pct(
+        &mut self,
+        min: Option<BigDecimal>,
+    ) -> Option<DiscountError<String>> {
+        if let Some(min) = &min {
+            if *min < crate::zero() {
+                return Some(DiscountError::NegativeValue(format!(
+                    "negative min discount percent {}",
+                    min
+                )));
+            }
+
+            if *min > crate::hundred() {
+                return Some(DiscountError::OverMaxDiscount(format!(
+                    "min discount percent over 100 {}",
+                    min
+                )));
+            }
+        }
+
+        self.min_discount_pct = min;
+
+        None
+    }
+
+    /// returns the min percent of the gross line value discounted, if any
+    pub fn min_discount_pct(&self) -> Option<&BigDecimal> {
+        self.min_discount_pct.as_ref()
+    }
+
+    /// returns the registered discounts individually, in the order they were added
+    pub fn discounts(&self) -> &[(Mode, BigDecimal)] {
+        &self.discounts
+    }
+
+    /// returns false if applying the registered discounts to the specified gross line value and
+    /// quantity produces a negative net. Useful to validate a discounts configuration before
+    /// using it, instead of failing at checkout.
+    ///
+    /// Negative `sample_gross` or `qty` are not viable.
+    pub fn is_viable(&self, sample_gross: BigDecimal, qty: BigDecimal) -> bool {
+        if sample_gross < crate::zero() || qty < crate::zero() {
+            return false;
+        }
+
+        let unit_value = if qty == crate::zero() {
+            crate::zero()
+        } else {
+            &sample_gross / &qty
+        };
+
+        self.discount_value(&unit_value, &qty) <= sample_gross
+    }
+
+    /// returns the greater or the lesser, as preferred, of a percentual discount and an amount line
+    /// discount over the line, as the coupons of "10% or $15 off, whichever is greater". Neither
+    /// discount is over the line value. The registered discounts are not considered.
+    /// Can return [DiscountError::NegativeValue] [DiscountError::OverMaxDiscount]
+    pub fn best_discount(
+        &self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+        pct: BigDecimal,
+        amount: BigDecimal,
+        prefer: BetterOrWorse,
+    ) -> Result<BigDecimal, DiscountError<String>> {
+        for (name, value) in [
+            ("unit_value", &unit_value),
+            ("qty", &qty),
+            ("pct", &pct),
+            ("amount", &amount),
+        ] {
+            if *value < crate::zero() {
+                return Err(DiscountError::NegativeValue(format!(
+                    "negative <{}> {}",
+                    name, value
+                )));
+            }
+        }
+
+        if pct > crate::hundred() {
+            return Err(DiscountError::OverMaxDiscount(format!(
+                "percentual discount {} over 100%",
+                pct
+            )));
+        }
+
+        let gross = &unit_value * &qty;
+        let by_percent = &gross * &pct / crate::hundred();
+        let by_amount = amount.min(gross);
+
+        Ok(match prefer {
+            BetterOrWorse::Better => by_percent.max(by_amount),
+            BetterOrWorse::Worse => by_percent.min(by_amount),
+        })
+    }
+
+    /// removes the registered discounts, like [Discounter::un_discount], from a discounted value
+    /// given as the specified numerator and denominator, returning the numerator and denominator
+    /// of the undiscounted value without dividing them, see
+    /// [crate::DetailCalculator::set_exact_rational].
+    /// Can return [DiscountError::NegativeValue] [DiscountError::OverMaxDiscount] [DiscountError::Other]
+    pub fn un_discount_ratio(
+        &self,
+        numerator: BigDecimal,
+        denominator: BigDecimal,
+        qty: BigDecimal,
+    ) -> Result<(BigDecimal, BigDecimal), DiscountError<String>> {
+        if qty < crate::zero() {
+            return Err(DiscountError::NegativeValue(format!(
+                "negative <qty> {}",
+                qty
+            )));
+        }
+
+        if denominator == crate::zero() {
+            return Err(DiscountError::Other(
+                "the denominator of the discounted value can't be zero".to_string(),
+            ));
+        }
+
+        if self.percentual == crate::hundred() {
+            return Err(DiscountError::OverMaxDiscount(
+                "a 100% discount cannot be removed".to_string(),
+            ));
+        }
+
+        let (numerator, denominator) = if denominator < crate::zero() {
+            (-numerator, -denominator)
+        } else {
+            (numerator, denominator)
+        };
+
+        if numerator < crate::zero() {
+            return Err(DiscountError::NegativeValue(format!(
+                "negative <discounted> {}",
+                &numerator / &denominator
+            )));
+        }
+
+        let amounts = &qty * &self.amount_unit + &self.amount_line;
+        let discountable_numerator = (&numerator + amounts * &denominator) * crate::hundred();
+        let discountable_denominator = &denominator * (crate::hundred() - &self.percentual);
+
+        // compares discountable_numerator / discountable_denominator with numerator / denominator
+        // without dividing, the denominator is positive
+        let lower = if discountable_denominator > crate::zero() {
+            &discountable_numerator * &denominator < &numerator * &discountable_denominator
+        } else {
+            &discountable_numerator * &denominator > &numerator * &discountable_denominator
+        };
+
+        if lower {
+            return Err(DiscountError::Other(format!(
+                "recovered discountable {} is lower than the discounted {} with a cumulated percentual of {}%",
+                &discountable_numerator / &discountable_denominator,
+                &numerator / &denominator,
+                self.percentual
+            )));
+        }
+
+        Ok((discountable_numerator, discountable_denominator))
+    }
+
+    fn single_discount_value(
+        mode: &Mode,
+        discount: &BigDecimal,
+        unit_value: &BigDecimal,
+        qty: &BigDecimal,
+    ) -> BigDecimal {
+        match mode {
+            Mode::Percentual => unit_value * qty * discount / crate::hundred(),
+            Mode::AmountLine => discount.clone(),
+            Mode::AmountUnit => discount * qty,
+        }
+    }
+
+    /// validates the discount value of a line against the registered limits: the override unit
+    /// price can't be over the unit value and the discount can't be over the max total discount
+    /// percent of the line, if any. [Discounter::compute] and every compute variant of
+    /// [crate::DetailCalculator] call it, whatever the discount comes from.
+    /// Can return [DiscountError::OverMaxDiscount] [DiscountError::Other]
+    pub fn check_discount(
+        &self,
+        discount_value: &BigDecimal,
+        unit_value: &BigDecimal,
+        qty: &BigDecimal,
+    ) -> Result<(), DiscountError<String>> {
+        if let Some(price) = &self.override_unit_price {
+            if price > unit_value {
+                return Err(DiscountError::Other(format!(
+                    "override price {} over the unit value {}",
+                    price, unit_value
+                )));
+            }
+        }
+
+        if let Some(max_pct) = &self.max_total_discount_pct {
+            let gross = unit_value * qty;
+            if gross > crate::zero() {
+                let discount_pct = discount_value * crate::hundred() / &gross;
+                if discount_pct > *max_pct {
+                    return Err(DiscountError::OverMaxDiscount(format!(
+                        "total discount percent {} max_total_discount_pct {}",
+                        discount_pct, max_pct
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// returns the value of the registered discounts for the specified unit value and quantity,
+    /// combined according to the [Stacking] policy, without any validation or cap.
+    /// When an override unit price is set, returns the override discount instead, or zero if
+    /// the override is over the unit value. The value is raised up to the min discount percent and
+    /// then rounded to the nearest multiple of the discount increment, if any. When the rounding
+    /// leaves it under the min discount percent, it is rounded up to the next multiple instead, so
+    /// the floor is always guaranteed
+    pub fn discount_value(&self, unit_value: &BigDecimal, qty: &BigDecimal) -> BigDecimal {
+        let mut discount_value = self.unrounded_discount_value(unit_value, qty);
+        let floor = self
+            .min_discount_pct
+            .as_ref()
+            .map(|min_pct| unit_value * qty * min_pct / crate::hundred());
+
+        if let Some(floor) = &floor {
+            if discount_value < *floor {
+                discount_value = floor.clone();
+            }
+        }
+
+        match &self.discount_increment {
+            Some(increment) => {
+                let steps = (&discount_value / increment).round(0);
+
+                match &floor {
+                    Some(floor) if &steps * increment < *floor => {
+                        (floor / increment).with_scale_round(0, RoundingMode::Ceiling) * increment
+                    }
+                    _ => steps * increment,
+                }
+            }
+            None => discount_value,
+        }
+    }
+
+    fn unrounded_discount_value(&self, unit_value: &BigDecimal, qty: &BigDecimal) -> BigDecimal {
+        if let Some(price) = &self.override_unit_price {
+            return if price < unit_value {
+                (unit_value - price) * qty
+            } else {
+                crate::zero()
+            };
+        }
+
+        match self.stacking {
+            Stacking::StackAll => {
+                unit_value * qty * &self.percentual / crate::hundred()
+                    + &self.amount_unit * qty
+                    + &self.amount_line
+            }
+            Stacking::BestSingle => self
+                .discounts
+                .iter()
+                .map(|(mode, discount)| {
+                    Self::single_discount_value(mode, discount, unit_value, qty)
+                })
+                .max()
+                .unwrap_or(crate::zero()),
+            Stacking::FirstMatch => match self.discounts.first() {
+                Some((mode, discount)) => {
+                    Self::single_discount_value(mode, discount, unit_value, qty)
+                }
+                None => crate::zero(),
+            },
         }
     }
 }
@@ -277,22 +805,10 @@ impl Discounter for DiscountComputer {
             )));
         }
 
-        match discount_mode {
-            Mode::Percentual => {
-                self.percentual =
-                    &self.percentual + BigDecimal::from_f64(discount).unwrap_or(crate::zero())
-            }
-            Mode::AmountLine => {
-                self.amount_line =
-                    &self.amount_line + BigDecimal::from_f64(discount).unwrap_or(crate::zero())
-            }
-            Mode::AmountUnit => {
-                self.amount_unit =
-                    &self.amount_unit + BigDecimal::from_f64(discount).unwrap_or(crate::zero())
-            }
-        }
-
-        None
+        self.add_discount(
+            BigDecimal::from_f64(discount).unwrap_or(crate::zero()),
+            discount_mode,
+        )
     }
 
     fn add_discount_from_str<S: Into<String>>(
@@ -301,6 +817,18 @@ impl Discounter for DiscountComputer {
         discount_mode: Mode,
     ) -> Option<DiscountError<String>> {
         let d = discount.into();
+
+        let d = match crate::strip_percent(&d) {
+            Some(stripped) if discount_mode == Mode::Percentual => stripped.to_string(),
+            Some(_) => {
+                return Some(DiscountError::InvalidDecimal(format!(
+                    "discount {} percent symbol is only allowed for percentual discounts",
+                    d
+                )))
+            }
+            None => d,
+        };
+
         match BigDecimal::from_str(&d) {
             Ok(discount) => self.add_discount(discount.clone(), discount_mode),
             Err(err) => Some(DiscountError::InvalidDecimal(format!(
@@ -330,11 +858,13 @@ impl Discounter for DiscountComputer {
         }
 
         match discount_mode {
-            Mode::Percentual => self.percentual = &self.percentual + discount,
-            Mode::AmountLine => self.amount_line = &self.amount_line + discount,
-            Mode::AmountUnit => self.amount_unit = &self.amount_unit + discount,
+            Mode::Percentual => self.percentual = &self.percentual + &discount,
+            Mode::AmountLine => self.amount_line = &self.amount_line + &discount,
+            Mode::AmountUnit => self.amount_unit = &self.amount_unit + &discount,
         }
 
+        self.discounts.push((discount_mode, discount));
+
         None
     }
 
@@ -382,9 +912,9 @@ impl Discounter for DiscountComputer {
             )));
         }
 
-        let discount_value = &unit_value * &qty * &self.percentual / crate::hundred()
-            + &self.amount_unit * &qty
-            + &self.amount_line;
+        let discount_value = self.discount_value(&unit_value, &qty);
+
+        self.check_discount(&discount_value, &unit_value, &qty)?;
 
         if discount_value > max_discount_allowed {
             return Err(DiscountError::OverMaxDiscount(format!(
@@ -393,22 +923,17 @@ impl Discounter for DiscountComputer {
             )));
         }
 
-        let percentual_discount = (&unit_value * &qty - &discount_value) / crate::hundred();
+        let gross = &unit_value * &qty;
 
-        if percentual_discount > crate::hundred() {
-            return Err(DiscountError::OverMaxDiscount(format!(
-                "percentual_discount {}",
-                percentual_discount
-            )));
-        }
-
-        if percentual_discount < crate::zero() {
+        if discount_value > gross {
             return Err(DiscountError::NegativeValue(format!(
-                "percentual_discount {}",
-                percentual_discount
+                "discount_value {} over the line value {} leaves a negative net",
+                discount_value, gross
             )));
         }
 
+        let percentual_discount = discount_percent(&discount_value, &gross);
+
         Ok((discount_value, percentual_discount))
     }
 
@@ -456,15 +981,24 @@ impl Discounter for DiscountComputer {
             )));
         }
 
-        let percentual = if self.percentual > crate::zero() {
-            self.percentual.clone()
-        } else {
-            crate::one()
-        };
+        if self.percentual == crate::hundred() {
+            return Err(DiscountError::OverMaxDiscount(
+                "a 100% discount cannot be removed".to_string(),
+            ));
+        }
+
+        let discountable = (&discounted + &qty * &self.amount_unit + &self.amount_line)
+            * crate::hundred()
+            / (crate::hundred() - &self.percentual);
+
+        if discountable < discounted {
+            return Err(DiscountError::Other(format!(
+                "recovered discountable {} is lower than the discounted {} with a cumulated percentual of {}%",
+                discountable, discounted, self.percentual
+            )));
+        }
 
-        let discountable = (&discounted + &self.amount_line) / &percentual * crate::hundred()
-            + &qty * &self.amount_unit;
-        let percentual_discount = (&discountable - &discounted) * crate::hundred() / &discountable;
+        let percentual_discount = discount_percent(&(&discountable - &discounted), &discountable);
 
         Ok((
             discountable.clone(),
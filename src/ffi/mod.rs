@@ -0,0 +1,71 @@
+//! ffi
+//!
+//! `ffi` module provides monomorphic `&str` based functions wrapping [DetailCalculator],
+//! easier to expose through a C FFI or bindings like PyO3 or wasm-bindgen than the generic
+//! methods of [crate::Calculator]. Configurations and results are exchanged as JSON.
+//!
+use std::str::FromStr;
+
+use bigdecimal::BigDecimal;
+
+use crate::{config::PricingConfig, Calculator, DetailCalculator};
+
+/// returns the JSON of an empty [PricingConfig], without discounts nor taxes,
+/// to be used as a template by the callers
+pub fn default_config() -> String {
+    serde_json::to_string(&PricingConfig::default()).unwrap_or_default()
+}
+
+/// computes a line with the discounts and taxes of the specified JSON [PricingConfig]
+/// returning the resulting [crate::Calculation] as JSON.
+/// The errors are returned as their message.
+///
+/// # Example
+///
+/// ```
+/// use baggins::ffi;
+///
+/// let calc = ffi::compute_line("100.0", "2.0", None, &ffi::default_config()).unwrap();
+///
+/// assert!(calc.contains("with_discount_values"));
+/// ```
+pub fn compute_line(
+    unit_value: &str,
+    qty: &str,
+    max_discount_allowed: Option<&str>,
+    config: &str,
+) -> Result<String, String> {
+    let config: PricingConfig = match serde_json::from_str(config) {
+        Ok(config) => config,
+        Err(err) => return Err(format!("parsing config {}", err)),
+    };
+
+    let mut calculator = match DetailCalculator::from_config(&config) {
+        Ok(calculator) => calculator,
+        Err(err) => return Err(err.to_string()),
+    };
+
+    let max_discount_allowed = match max_discount_allowed {
+        Some(max_discount_allowed) => match BigDecimal::from_str(max_discount_allowed) {
+            Ok(max_discount_allowed) => Some(max_discount_allowed),
+            Err(err) => {
+                return Err(format!(
+                    "parsing max_discount_allowed {} {}",
+                    max_discount_allowed, err
+                ))
+            }
+        },
+        None => None,
+    };
+
+    match BigDecimal::from_str(unit_value) {
+        Ok(unit_value) => match BigDecimal::from_str(qty) {
+            Ok(qty) => match calculator.compute(unit_value, qty, max_discount_allowed) {
+                Ok(calc) => serde_json::to_string(&calc).map_err(|err| err.to_string()),
+                Err(err) => Err(err.to_string()),
+            },
+            Err(err) => Err(format!("parsing qty {} {}", qty, err)),
+        },
+        Err(err) => Err(format!("parsing unit_value {} {}", unit_value, err)),
+    }
+}
@@ -12,13 +12,18 @@
 //! El foco está en la facilidad de uso y en aprender Rust, por lo que hay muchas oportunidades de mejora.
 //!
 //!
-use bigdecimal::{BigDecimal, FromPrimitive, Zero};
+use bigdecimal::{BigDecimal, FromPrimitive, RoundingMode, ToPrimitive, Zero};
 use discount::Discounter;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::{fmt, str::FromStr};
 use tax::Taxer;
 
+#[cfg(feature = "csv")]
+pub mod batch;
+pub mod config;
 pub mod discount;
+pub mod ffi;
+pub mod prelude;
 pub mod tax;
 
 /// handy utility to get 100.0 as BigDecimal
@@ -41,7 +46,48 @@ pub fn zero() -> BigDecimal {
     BigDecimal::zero()
 }
 
-#[derive(Debug)]
+/// returns zero if the absolute value is lower than epsilon, otherwise the value
+fn snap(value: &BigDecimal, epsilon: &BigDecimal) -> BigDecimal {
+    if value.abs() < *epsilon {
+        zero()
+    } else {
+        value.clone()
+    }
+}
+
+/// returns the breakdown with each stage settled, as rounded or snapped. When the breakdown adds
+/// up to the tax, the difference between the settled tax and the settled stages is kept in the
+/// over taxable stage, so the breakdown still adds up to the tax. A breakdown trimmed by a tax
+/// cap is only settled stage by stage.
+fn settle_breakdown<F>(
+    breakdown: &tax::TaxBreakdown,
+    tax: &BigDecimal,
+    settled_tax: &BigDecimal,
+    settle: F,
+) -> tax::TaxBreakdown
+where
+    F: Fn(&BigDecimal) -> BigDecimal,
+{
+    let mut settled = tax::TaxBreakdown {
+        over_taxable: settle(&breakdown.over_taxable),
+        over_tax: settle(&breakdown.over_tax),
+        over_tax_ignorable: settle(&breakdown.over_tax_ignorable),
+    };
+
+    if breakdown.total() == *tax {
+        settled.over_taxable += settled_tax - settled.total();
+    }
+
+    settled
+}
+
+/// returns the value without its trailing percent symbol, as in "10%" or "10 %",
+/// or [None] if the value has no percent symbol
+pub(crate) fn strip_percent(value: &str) -> Option<&str> {
+    value.trim().strip_suffix('%').map(str::trim_end)
+}
+
+#[derive(Debug, PartialEq)]
 /// The error type for baggins operations - El tipo de error para operaciones de baggins
 ///
 /// Everything in life can end in a huge mistake, and the operations that baggins performs
@@ -81,10 +127,19 @@ pub enum BagginsError<S: Into<String>> {
     /// Error for not being able to convert a value to [BigDecimal]
     InvalidDecimalValue(S),
 
+    /// Error of the tax handler, keeping its original variant
+    Tax(tax::TaxError<String>),
+
+    /// Error of the discount handler, keeping its original variant
+    Discount(discount::DiscountError<String>),
+
     /// Any other unspecified error
     Other(S),
 }
 
+/// the [Result] returned by the fallible operations of the crate
+pub type BagginsResult<T> = Result<T, BagginsError<String>>;
+
 impl<S: Into<String> + Clone> fmt::Display for BagginsError<S> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -94,11 +149,25 @@ impl<S: Into<String> + Clone> fmt::Display for BagginsError<S> {
             BagginsError::InvalidDecimalValue(msg) => {
                 write!(f, " Invalid decimal value {}", msg.clone().into())
             }
+            BagginsError::Tax(err) => write!(f, "{}", err),
+            BagginsError::Discount(err) => write!(f, "{}", err),
             BagginsError::Other(msg) => write!(f, " Error {}", msg.clone().into()),
         }
     }
 }
 
+impl From<tax::TaxError<String>> for BagginsError<String> {
+    fn from(err: tax::TaxError<String>) -> Self {
+        BagginsError::Tax(err)
+    }
+}
+
+impl From<discount::DiscountError<String>> for BagginsError<String> {
+    fn from(err: discount::DiscountError<String>) -> Self {
+        BagginsError::Discount(err)
+    }
+}
+
 #[derive(Debug, Serialize)]
 /// will contain the result of the computing of the specified subtotal
 pub struct CalculationWithDiscount {
@@ -112,7 +181,8 @@ pub struct CalculationWithDiscount {
     pub discount_value: BigDecimal,
     /// stores the cumulated discount value
     pub discount_brute_value: BigDecimal,
-    /// stores the total discount applied as a percentage
+    /// stores the total discount applied as a percentage of the net without discount, see
+    /// [discount::discount_percent]
     pub total_discount_percent: BigDecimal,
     /// stores the unit value with discounts applied
     pub unit_value: BigDecimal,
@@ -152,6 +222,36 @@ impl CalculationWithDiscount {
             unit_value: self.unit_value.clone(), 
         }
     }
+
+    /// returns a copy with every value whose absolute value is lower than epsilon set to zero.
+    /// The brute is the snapped net plus the snapped tax, so it still adds up.
+    pub fn snap(&self, epsilon: &BigDecimal) -> Self {
+        let net = snap(&self.net, epsilon);
+        let tax = snap(&self.tax, epsilon);
+
+        Self {
+            brute: &net + &tax,
+            net,
+            tax,
+            discount_value: snap(&self.discount_value, epsilon),
+            discount_brute_value: snap(&self.discount_brute_value, epsilon),
+            total_discount_percent: snap(&self.total_discount_percent, epsilon),
+            unit_value: snap(&self.unit_value, epsilon),
+        }
+    }
+
+    /// returns a copy with the monetary values multiplied by the exchange rate, keeping the percentages
+    pub fn convert(&self, rate: &BigDecimal) -> Self {
+        Self {
+            net: &self.net * rate,
+            brute: &self.brute * rate,
+            tax: &self.tax * rate,
+            discount_value: &self.discount_value * rate,
+            discount_brute_value: &self.discount_brute_value * rate,
+            total_discount_percent: self.total_discount_percent.clone(),
+            unit_value: &self.unit_value * rate,
+        }
+    }
 }
 
 impl Default for CalculationWithDiscount {
@@ -220,6 +320,30 @@ impl CalculationWithoutDiscount {
             unit_value: self.unit_value.clone(), 
         }
     }
+
+    /// returns a copy with every value whose absolute value is lower than epsilon set to zero.
+    /// The brute is the snapped net plus the snapped tax, so it still adds up.
+    pub fn snap(&self, epsilon: &BigDecimal) -> Self {
+        let net = snap(&self.net, epsilon);
+        let tax = snap(&self.tax, epsilon);
+
+        Self {
+            brute: &net + &tax,
+            net,
+            tax,
+            unit_value: snap(&self.unit_value, epsilon),
+        }
+    }
+
+    /// returns a copy with the monetary values multiplied by the exchange rate
+    pub fn convert(&self, rate: &BigDecimal) -> Self {
+        Self {
+            net: &self.net * rate,
+            brute: &self.brute * rate,
+            tax: &self.tax * rate,
+            unit_value: &self.unit_value * rate,
+        }
+    }
 }
 
 impl Default for CalculationWithoutDiscount {
@@ -247,6 +371,8 @@ impl fmt::Display for CalculationWithoutDiscount {
 pub struct Calculation {
     without_discount_values: CalculationWithoutDiscount,
     with_discount_values: CalculationWithDiscount,
+    /// the taxes with discount by stage, serialized as a `tax_breakdown` object next to the values
+    tax_breakdown: tax::TaxBreakdown,
 }
 
 
@@ -258,6 +384,50 @@ impl Calculation {
         Self {
             without_discount_values,
             with_discount_values,
+            tax_breakdown: tax::TaxBreakdown::default(),
+        }
+    }
+
+    /// returns the values calculated without applying discounts
+    pub fn without_discount_values(&self) -> &CalculationWithoutDiscount {
+        &self.without_discount_values
+    }
+
+    /// returns the values calculated applying the registered discounts
+    pub fn with_discount_values(&self) -> &CalculationWithDiscount {
+        &self.with_discount_values
+    }
+
+    /// returns the taxes of the values with discount by [tax::Stage], before the tax caps and
+    /// the rounding. Empty for a calculation created with [Calculation::new]
+    pub fn tax_breakdown(&self) -> &tax::TaxBreakdown {
+        &self.tax_breakdown
+    }
+
+    /// returns a copy with the monetary values multiplied by the exchange rate, keeping the percentages
+    pub fn convert(&self, rate: &BigDecimal) -> Self {
+        Self {
+            without_discount_values: self.without_discount_values.convert(rate),
+            with_discount_values: self.with_discount_values.convert(rate),
+            tax_breakdown: tax::TaxBreakdown {
+                over_taxable: &self.tax_breakdown.over_taxable * rate,
+                over_tax: &self.tax_breakdown.over_tax * rate,
+                over_tax_ignorable: &self.tax_breakdown.over_tax_ignorable * rate,
+            },
+        }
+    }
+
+    /// returns the signed differences of the values with discount from this calculation to the
+    /// other, as in `other - self`, so editing a line can be audited field by field
+    pub fn diff(&self, other: &Calculation) -> CalculationDiff {
+        let before = &self.with_discount_values;
+        let after = &other.with_discount_values;
+
+        CalculationDiff {
+            net: &after.net - &before.net,
+            tax: &after.tax - &before.tax,
+            brute: &after.brute - &before.brute,
+            discount_value: &after.discount_value - &before.discount_value,
         }
     }
 }
@@ -272,203 +442,3140 @@ impl fmt::Display for Calculation {
     }
 }
 
-// A thing able to calculate sales values
-pub trait Calculator {
-    /// adds a [BigDecimal] discount value of the specified [discount::Mode] to [Calculator].
-    fn add_discount(
-        &mut self,
-        discount: BigDecimal,
-        discount_mode: discount::Mode,
-    ) -> Option<discount::DiscountError<String>>;
+#[derive(Debug, Serialize, Default, Clone, PartialEq)]
+/// will contain the signed differences between two [Calculation], see [Calculation::diff]
+pub struct CalculationDiff {
+    /// stores the difference of the nets with discount
+    pub net: BigDecimal,
+    /// stores the difference of the taxes
+    pub tax: BigDecimal,
+    /// stores the difference of the brutes
+    pub brute: BigDecimal,
+    /// stores the difference of the discount values
+    pub discount_value: BigDecimal,
+}
 
-    /// adds a [f64] discount value of the specified [discount::Mode]
-    /// to [Calculator] so expect some precision loss
-    fn add_discount_from_f64(
-        &mut self,
-        discount: f64,
-        discount_mode: discount::Mode,
-    ) -> Option<discount::DiscountError<String>>;
+impl fmt::Display for CalculationDiff {
+    /// shows only the fields that changed, as in `net +10, brute +11.8`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fields = [
+            ("net", &self.net),
+            ("tax", &self.tax),
+            ("brute", &self.brute),
+            ("discount value", &self.discount_value),
+        ];
+
+        let changed: Vec<String> = fields
+            .iter()
+            .filter(|(_, delta)| !delta.is_zero())
+            .map(|(name, delta)| {
+                if **delta > zero() {
+                    format!("{} +{}", name, delta)
+                } else {
+                    format!("{} {}", name, delta)
+                }
+            })
+            .collect();
 
-    /// adds an [`Into<String>`] discount value of the specified [discount::Mode]
-    /// to [`Calculator`]
-    fn add_discount_from_str<S: Into<String>>(
-        &mut self,
-        discount: S,
-        discount_mode: discount::Mode,
-    ) -> Option<discount::DiscountError<String>>;
+        write!(f, "{}", changed.join(", "))
+    }
+}
 
-    /// adds a tax to the specified [tax::Stage] in [Calculator] from a [f64] value
-    /// of the specified [tax::Mode] so expect some precision loss
-    fn add_tax_from_f64(
-        &mut self,
-        tax: f64,
-        stage: tax::Stage,
-        tax_mode: tax::Mode,
-    ) -> Option<tax::TaxError<String>>;
+/// returns the value rounded to the specified scale as an integer of minor units, so 100.00 at
+/// scale 2 is 10000. Returns [None] if the value doesn't fit in an [i64]
+pub fn to_minor_units(value: &BigDecimal, scale: u32) -> Option<i64> {
+    let scale = i64::from(scale);
+    (value.round(scale) * BigDecimal::new(1.into(), -scale)).to_i64()
+}
 
-    /// adds a tax to the specified [tax::Stage] in [Calculator] from a [BigDecimal]
-    fn add_tax(
-        &mut self,
-        tax: BigDecimal,
-        stage: tax::Stage,
-        tax_mode: tax::Mode,
-    ) -> Option<tax::TaxError<String>>;
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+/// the values of a [CalculationWithDiscount] as integer minor units
+pub struct CalculationWithDiscountMinor {
+    /// net in minor units
+    pub net: i64,
+    /// brute in minor units
+    pub brute: i64,
+    /// tax in minor units
+    pub tax: i64,
+    /// discount value in minor units
+    pub discount_value: i64,
+    /// discount brute value in minor units
+    pub discount_brute_value: i64,
+    /// the percent in minor units of the same scale, so 12.5% at scale 2 is 1250
+    pub total_discount_percent: i64,
+    /// unit value in minor units
+    pub unit_value: i64,
+}
 
-    /// adds a tax to the specified [tax::Stage] in [Calculator] from a [String]
-    fn add_tax_from_str<S: Into<String>>(
-        &mut self,
-        tax: S,
-        stage: tax::Stage,
-        tax_mode: tax::Mode,
-    ) -> Option<tax::TaxError<String>>;
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+/// the values of a [CalculationWithoutDiscount] as integer minor units
+pub struct CalculationWithoutDiscountMinor {
+    /// net in minor units
+    pub net: i64,
+    /// brute in minor units
+    pub brute: i64,
+    /// tax in minor units
+    pub tax: i64,
+    /// unit value in minor units
+    pub unit_value: i64,
+}
 
-    /// calculates and produces a [Calculation] from a [BigDecimal] brute value
-    /// and a quantity of the same type
-    fn compute_from_brute(
-        &mut self,
-        brute: BigDecimal,
-        qty: BigDecimal,
-        max_discount_allowed: Option<BigDecimal>,
-    ) -> Result<Calculation, BagginsError<String>>;
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+/// a [Calculation] with its values as integer minor units, see [DetailCalculator::compute_minor]
+pub struct CalculationMinor {
+    /// values calculated without applying discounts
+    pub without_discount_values: CalculationWithoutDiscountMinor,
+    /// values calculated applying the registered discounts
+    pub with_discount_values: CalculationWithDiscountMinor,
+}
 
-    /// calculates and produces a [Calculation] from a [f64] brute subtotal value
-    /// and a quantity of the same type. Use of [f64] may cause precission loss
-    fn compute_from_brute_f64(
-        &mut self,
-        brute: f64,
-        qty: f64,
-        max_discount_allowed: Option<f64>,
-    ) -> Result<Calculation, BagginsError<String>>;
+impl Calculation {
+    /// returns the calculation with its values rounded to the specified scale as integer minor units.
+    /// Could return [BagginsError::InvalidDecimalValue] if a value doesn't fit in an [i64]
+    pub fn to_minor(&self, scale: u32) -> BagginsResult<CalculationMinor> {
+        let minor = |name: &str, value: &BigDecimal| -> BagginsResult<i64> {
+            to_minor_units(value, scale).ok_or_else(|| {
+                BagginsError::InvalidDecimalValue(format!(
+                    "<{}> {} overflows minor units at scale {}",
+                    name, value, scale
+                ))
+            })
+        };
+
+        let wd = &self.with_discount_values;
+        let wod = &self.without_discount_values;
+
+        Ok(CalculationMinor {
+            without_discount_values: CalculationWithoutDiscountMinor {
+                net: minor("net", &wod.net)?,
+                brute: minor("brute", &wod.brute)?,
+                tax: minor("tax", &wod.tax)?,
+                unit_value: minor("unit_value", &wod.unit_value)?,
+            },
+            with_discount_values: CalculationWithDiscountMinor {
+                net: minor("net", &wd.net)?,
+                brute: minor("brute", &wd.brute)?,
+                tax: minor("tax", &wd.tax)?,
+                discount_value: minor("discount_value", &wd.discount_value)?,
+                discount_brute_value: minor("discount_brute_value", &wd.discount_brute_value)?,
+                total_discount_percent: minor(
+                    "total_discount_percent",
+                    &wd.total_discount_percent,
+                )?,
+                unit_value: minor("unit_value", &wd.unit_value)?,
+            },
+        })
+    }
+}
 
-    /// calculates and produces a [Calculation] from a [String] brute value
-    /// and a quantity of the same type
-    fn compute_from_brute_str<S: Into<String>>(
-        &mut self,
-        brute: S,
-        qty: S,
-        max_discount_allowed: Option<S>,
-    ) -> Result<Calculation, BagginsError<String>>;
+/// returns the adjustment needed to reconcile the taxes of an invoice rounded line by line with
+/// the tax of the invoice rounded from its totals.
+///
+/// `sum(round(tax)) - round(sum(tax))` using the tax with discount of every line, rounded to the
+/// specified scale. A positive value means the lines charge more tax than the invoice total.
+pub fn reconcile_tax(lines: &[Calculation], scale: u32) -> BigDecimal {
+    let scale = i64::from(scale);
+    let mut rounded_lines = zero();
+    let mut total = zero();
+
+    for line in lines {
+        rounded_lines += line.with_discount_values.tax.round(scale);
+        total += &line.with_discount_values.tax;
+    }
 
-    /// calculates and produces a [Calculation] from a [String] unit value
-    /// and a quantity of the same type
-    fn compute_from_str<S: Into<String>>(
-        &mut self,
-        unit_value: S,
-        qty: S,
-        max_discount_allowed: Option<S>,
-    ) -> Result<Calculation, BagginsError<String>>;
+    rounded_lines - total.round(scale)
+}
 
-    /// calculates and produces a [Calculation] from a [f64] unit value
-    /// and a quantity of the same type. Use of [f64] may cause precission loss
-    fn compute_from_f64(
-        &mut self,
-        unit_value: f64,
-        qty: f64,
-        max_discount_allowed: Option<f64>,
-    ) -> Result<Calculation, BagginsError<String>>;
+/// splits a [Calculation] into `n` installments, dividing the nets, the taxes and the discount
+/// value in `n` parts rounded down to `scale`. The rounding residue is assigned to the first
+/// installment, so the parts of each value sum exactly to the original. The tax of each
+/// installment is the sum of its tax breakdown stages when the breakdown adds up to the tax, and
+/// its brute is its net plus its tax, so every installment adds up. Unit values and the discount
+/// percent are kept as they are. Could return [BagginsError::Other] if `n` is zero
+pub fn split_installments(
+    calc: &Calculation,
+    n: u32,
+    scale: u32,
+) -> BagginsResult<Vec<Calculation>> {
+    if n == 0 {
+        return Err(BagginsError::Other(
+            "can't split a calculation in zero installments".to_string(),
+        ));
+    }
 
-    /// calculates and produces a [Calculation] from a [BigDecimal] unit value
-    /// and a quantity of the same type
-    /// Receives an [Option<BigDecimal>] to use as a maximum discount value. If [None] the max discount value will be the
-    /// equivalent to the 100% of the calculated brute subtotal. If [Some] will be validated and used the passed value.
-    /// If the unwrapped [BigDecimal] is negative or greater than 100, a Bigdecimal with the 100 value will be used
-    /// as maximum allowed discount.
-    fn compute(
-        &mut self,
-        unit_value: BigDecimal,
-        qty: BigDecimal,
-        max_discount_allowed: Option<BigDecimal>,
-    ) -> Result<Calculation, BagginsError<String>>;
+    let split = |value: &BigDecimal| -> Vec<BigDecimal> {
+        let part =
+            (value / BigDecimal::from(n)).with_scale_round(i64::from(scale), RoundingMode::Down);
+        let mut parts = vec![value - &part * BigDecimal::from(n - 1)];
+        parts.resize(n as usize, part);
+        parts
+    };
+
+    let wd = &calc.with_discount_values;
+    let wod = &calc.without_discount_values;
+
+    let net = split(&wd.net);
+    let discount_value = split(&wd.discount_value);
+    let net_wod = split(&wod.net);
+    let tax_wod = split(&wod.tax);
+    let over_taxable = split(&calc.tax_breakdown.over_taxable);
+    let over_tax = split(&calc.tax_breakdown.over_tax);
+    let over_tax_ignorable = split(&calc.tax_breakdown.over_tax_ignorable);
+
+    let tax = if calc.tax_breakdown.total() == wd.tax {
+        (0..n as usize)
+            .map(|i| &over_taxable[i] + &over_tax[i] + &over_tax_ignorable[i])
+            .collect()
+    } else {
+        split(&wd.tax)
+    };
+
+    let installments = (0..n as usize)
+        .map(|i| {
+            let brute = &net[i] + &tax[i];
+            let brute_wod = &net_wod[i] + &tax_wod[i];
+
+            Calculation {
+                with_discount_values: CalculationWithDiscount {
+                    net: net[i].clone(),
+                    discount_brute_value: &brute - &brute_wod,
+                    brute,
+                    tax: tax[i].clone(),
+                    discount_value: discount_value[i].clone(),
+                    total_discount_percent: wd.total_discount_percent.clone(),
+                    unit_value: wd.unit_value.clone(),
+                },
+                without_discount_values: CalculationWithoutDiscount {
+                    net: net_wod[i].clone(),
+                    brute: brute_wod,
+                    tax: tax_wod[i].clone(),
+                    unit_value: wod.unit_value.clone(),
+                },
+                tax_breakdown: tax::TaxBreakdown {
+                    over_taxable: over_taxable[i].clone(),
+                    over_tax: over_tax[i].clone(),
+                    over_tax_ignorable: over_tax_ignorable[i].clone(),
+                },
+            }
+        })
+        .collect();
 
-    /// an utility to calculate a tax directly
-    ///
-    /// # Params
-    ///
-    /// taxable [BigDecimal] value to tax
-    ///
-    /// qty     [BigDecimal] quantity being sold (some tax needs to know this value to be calculated)
-    ///
-    /// value   [BigDecimal] percent or amount to apply as tax
-    ///
-    /// mode    [tax::Mode]  wheter the tax is percentual, amount by unit or amount by line
-    ///
-    fn line_tax(
-        &mut self,
-        taxable: BigDecimal,
-        qty: BigDecimal,
-        value: BigDecimal,
-        mode: tax::Mode,
-    ) -> Result<BigDecimal, tax::TaxError<String>>;
+    Ok(installments)
+}
 
-    /// an utility to calculate a tax directly using [String]s as entry.
-    /// Converts values to BigDecimal.
-    ///
-    /// # Params
-    ///
-    /// taxable [String] value to tax
-    ///
-    /// qty     [String] quantity being sold (some tax needs to know this value to be calculated)
-    ///
-    /// value   [String] percent or amount to apply as tax
-    ///
-    /// mode    [tax::Mode]  wheter the tax is percentual, amount by unit or amount by line
-    ///
-    fn line_tax_from_str<S: Into<String>>(
-        &mut self,
-        taxable: S,
-        qty: S,
-        value: S,
-        mode: tax::Mode,
-    ) -> Result<BigDecimal, tax::TaxError<String>>;
+/// returns the amount discount of each line net so the invoice discount is exactly the
+/// `target_pct` of the invoice total rounded to `scale`. Each line discount is rounded down to
+/// `scale` and the rounding residue is assigned to the last line, so the discounts sum exactly
+/// to the rounded invoice discount, which per line rounding alone could miss by a cent.
+/// Could return [BagginsError::Other] if a line net is negative, the target percent is out of
+/// the range 0..=100 or the residue doesn't fit in the last line
+pub fn discounts_for_exact_percent(
+    line_nets: &[BigDecimal],
+    target_pct: BigDecimal,
+    scale: u32,
+) -> BagginsResult<Vec<BigDecimal>> {
+    let scale = i64::from(scale);
+
+    if target_pct < zero() || target_pct > hundred() {
+        return Err(BagginsError::Other(format!(
+            "target percent {} out of the range 0..=100",
+            target_pct
+        )));
+    }
 
-    /// an utility to calculate a tax directly using [f64]s as entry. Some precission could be loss.
-    /// Converts values to BigDecimal.
-    ///
-    /// # Params
-    ///
-    /// taxable [f64] value to tax
-    ///
-    /// qty     [f64] quantity being sold (some tax needs to know this value to be calculated)
-    ///
-    /// value   [f64] percent or amount to apply as tax
-    ///
-    /// mode    [tax::Mode]  wheter the tax is percentual, amount by unit or amount by line
-    ///
-    fn line_tax_from_f64(
-        &mut self,
-        taxable: f64,
-        qty: f64,
-        value: f64,
-        mode: tax::Mode,
-    ) -> Result<BigDecimal, tax::TaxError<String>>;
-}
+    if let Some(net) = line_nets.iter().find(|net| **net < zero()) {
+        return Err(BagginsError::Other(format!("negative line net {}", net)));
+    }
 
-pub struct DetailCalculator {
-    tax_handler: tax::TaxComputer,
-    discount_handler: discount::DiscountComputer,
-}
+    let total = line_nets.iter().fold(zero(), |total, net| total + net);
+    let total_discount = (&total * &target_pct / hundred()).round(scale);
 
-impl DetailCalculator {
-    /// Creates a new [`DetailCalculator`].
-    pub fn new() -> Self {
-        Self {
-            tax_handler: tax::TaxComputer::default(),
-            discount_handler: discount::DiscountComputer::default(),
+    let mut discounts: Vec<BigDecimal> = line_nets
+        .iter()
+        .map(|net| (net * &target_pct / hundred()).with_scale_round(scale, RoundingMode::Down))
+        .collect();
+
+    let allocated = discounts
+        .iter()
+        .fold(zero(), |allocated, discount| allocated + discount);
+
+    if let Some(last) = discounts.last_mut() {
+        *last += &total_discount - allocated;
+
+        if *last > line_nets[line_nets.len() - 1] {
+            return Err(BagginsError::Other(format!(
+                "the residue of the discount {} doesn't fit in the last line",
+                total_discount
+            )));
         }
     }
-}
 
-impl Default for DetailCalculator {
-    fn default() -> Self {
-        Self::new()
-    }
+    Ok(discounts)
 }
 
-impl Calculator for DetailCalculator {
-    fn add_discount(
-        &mut self,
-        discount: BigDecimal,
-        discount_mode: discount::Mode,
+/// renders the lines of an invoice as an aligned text table with a row for each line
+/// and a totals row, showing net, discount, tax and brute rounded to the specified scale.
+/// Scales out of the range 0..=128 are clamped to 128.
+///
+/// # Example
+///
+/// ```
+/// use baggins::{Calculator, DetailCalculator};
+///
+/// let mut c = DetailCalculator::new();
+/// let line = c.compute_from_str("10.0", "2.0", None).unwrap();
+///
+/// let table = baggins::render_invoice(&[line], 2);
+/// assert!(table.contains("TOTAL"));
+/// ```
+pub fn render_invoice(lines: &[Calculation], scale: i64) -> String {
+    let scale = if !(0..=128).contains(&scale) {
+        128
+    } else {
+        scale
+    };
+    let header = ["#", "NET", "DISCOUNT", "TAX", "BRUTE"];
+    let cell = |value: &BigDecimal| format!("{}", value.round(scale).with_scale(scale));
+
+    let mut rows: Vec<[String; 5]> = Vec::new();
+    let mut totals = [zero(), zero(), zero(), zero()];
+
+    for (index, line) in lines.iter().enumerate() {
+        let values = [
+            &line.with_discount_values.net,
+            &line.with_discount_values.discount_value,
+            &line.with_discount_values.tax,
+            &line.with_discount_values.brute,
+        ];
+
+        for (total, value) in totals.iter_mut().zip(values) {
+            *total += value;
+        }
+
+        rows.push([
+            format!("{}", index + 1),
+            cell(values[0]),
+            cell(values[1]),
+            cell(values[2]),
+            cell(values[3]),
+        ]);
+    }
+
+    if !lines.is_empty() {
+        rows.push([
+            "TOTAL".to_string(),
+            cell(&totals[0]),
+            cell(&totals[1]),
+            cell(&totals[2]),
+            cell(&totals[3]),
+        ]);
+    }
+
+    let mut widths = header.map(|title| title.len());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let render_row = |cells: [&str; 5]| -> String {
+        let mut line = String::new();
+        for (i, cell) in cells.iter().enumerate() {
+            if i == 0 {
+                line.push_str(&format!("{:<width$}", cell, width = widths[i]));
+            } else {
+                line.push_str(&format!(" | {:>width$}", cell, width = widths[i]));
+            }
+        }
+        line.push('\n');
+        line
+    };
+
+    let mut table = render_row(header);
+    let separator_len = widths.iter().sum::<usize>() + 3 * (widths.len() - 1);
+    table.push_str(&"-".repeat(separator_len));
+    table.push('\n');
+
+    for (index, row) in rows.iter().enumerate() {
+        if index + 1 == rows.len() && !lines.is_empty() {
+            table.push_str(&"-".repeat(separator_len));
+            table.push('\n');
+        }
+        table.push_str(&render_row([&row[0], &row[1], &row[2], &row[3], &row[4]]));
+    }
+
+    table
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+/// How the taxes of a line must be reported
+pub enum LineTaxStatus {
+    /// a line taxed at the registered rates
+    Standard,
+
+    /// a taxable line taxed at a zero rate. Reported with its base and a zero tax
+    ZeroRated,
+
+    /// a line out of the scope of the tax. Reported apart as exempt base
+    Exempt,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+/// a row of a [tax_summary]
+pub struct TaxSummaryRow {
+    /// the status of the lines grouped in this row
+    pub status: LineTaxStatus,
+    /// stores the cumulated net of the lines
+    pub base: BigDecimal,
+    /// stores the cumulated tax of the lines. Always zero for zero rated and exempt lines
+    pub tax: BigDecimal,
+    /// the number of lines grouped in this row
+    pub lines: usize,
+}
+
+/// groups the lines of an invoice by their [LineTaxStatus], returning a row for each status
+/// present, in the order Standard, ZeroRated, Exempt.
+///
+/// Zero rated lines are reported with their base and a zero tax, and exempt lines are reported
+/// in their own row, so they can be filled in the distinct boxes tax returns require.
+pub fn tax_summary(lines: &[(LineTaxStatus, Calculation)]) -> Vec<TaxSummaryRow> {
+    let statuses = [
+        LineTaxStatus::Standard,
+        LineTaxStatus::ZeroRated,
+        LineTaxStatus::Exempt,
+    ];
+
+    let mut rows = Vec::new();
+
+    for status in statuses {
+        let mut row = TaxSummaryRow {
+            status,
+            base: zero(),
+            tax: zero(),
+            lines: 0,
+        };
+
+        for (line_status, calc) in lines {
+            if *line_status != status {
+                continue;
+            }
+
+            row.base += &calc.with_discount_values.net;
+            if status == LineTaxStatus::Standard {
+                row.tax += &calc.with_discount_values.tax;
+            }
+            row.lines += 1;
+        }
+
+        if row.lines > 0 {
+            rows.push(row);
+        }
+    }
+
+    rows
+}
+
+#[derive(Debug, Serialize, Default, Clone, PartialEq)]
+/// the taxes of a batch of lines by [tax::Stage], see [stage_totals]
+pub struct StageTotals {
+    /// stores the cumulated taxes of the [tax::Stage::OverTaxable] stage
+    pub over_taxable: BigDecimal,
+    /// stores the cumulated taxes of the [tax::Stage::OverTax] stage
+    pub over_tax: BigDecimal,
+    /// stores the cumulated taxes of the [tax::Stage::OverTaxIgnorable] stage
+    pub over_tax_ignorable: BigDecimal,
+}
+
+/// sums the [Calculation::tax_breakdown] of every line by [tax::Stage], so each stage can be
+/// posted to its own ledger account
+pub fn stage_totals(calcs: &[Calculation]) -> StageTotals {
+    calcs
+        .iter()
+        .fold(StageTotals::default(), |mut totals, calc| {
+            totals.over_taxable += &calc.tax_breakdown.over_taxable;
+            totals.over_tax += &calc.tax_breakdown.over_tax;
+            totals.over_tax_ignorable += &calc.tax_breakdown.over_tax_ignorable;
+            totals
+        })
+}
+
+/// a shipping charge taxed independently from the line it is sent with.
+///
+/// Shipping is usually taxed separately and sometimes at a different rate than the goods,
+/// so it carries its own [tax::TaxComputer].
+pub struct ShippingCharge {
+    /// the shipping amount before taxes
+    pub amount: BigDecimal,
+    /// the taxes applied to the shipping amount
+    pub taxes: tax::TaxComputer,
+}
+
+impl ShippingCharge {
+    /// Creates a new [`ShippingCharge`].
+    pub fn new(amount: BigDecimal, taxes: tax::TaxComputer) -> Self {
+        Self { amount, taxes }
+    }
+}
+
+#[derive(Debug, Serialize, Default)]
+/// will contain the result of the computing of a [ShippingCharge]
+pub struct ShippingCalculation {
+    /// stores the shipping amount
+    pub net: BigDecimal,
+    /// stores the net plus taxes
+    pub brute: BigDecimal,
+    /// stores the cumulated tax calculated over the shipping amount
+    pub tax: BigDecimal,
+}
+
+impl fmt::Display for ShippingCalculation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "net {}, brute {}, tax {})",
+            self.net, self.brute, self.tax,
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+/// the cash rounding of a currency without small coins, like the swiss and swedish rounding of
+/// the cash total to the nearest 0.05 or 0.10. Only the final brute is rounded, see
+/// [DetailCalculator::compute_cash]
+pub struct CashRounding {
+    /// the increment the brute is rounded to
+    pub increment: BigDecimal,
+}
+
+impl CashRounding {
+    /// Creates a new [`CashRounding`].
+    pub fn new(increment: BigDecimal) -> Self {
+        Self { increment }
+    }
+
+    /// returns the value rounded to the nearest multiple of the increment, halves away from zero.
+    /// Unlike the halves to even roundings of the rest of the crate, cash rounding rules round the
+    /// halves up, as a swiss total of 118.025 paid in cash is 118.05.
+    /// Could return [BagginsError::InvalidDecimalValue] if the increment is not over zero
+    pub fn round(&self, value: &BigDecimal) -> BagginsResult<BigDecimal> {
+        if self.increment <= zero() {
+            return Err(BagginsError::InvalidDecimalValue(format!(
+                "cash rounding increment must be over zero {}",
+                self.increment
+            )));
+        }
+
+        Ok((value / &self.increment).with_scale_round(0, RoundingMode::HalfUp) * &self.increment)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+/// a price break table with the unit price by minimum quantity, as 1 to 9 units at $10 and 10
+/// or more at $9, see [DetailCalculator::compute_with_breaks]
+pub struct PriceBreaks {
+    breaks: std::collections::BTreeMap<BigDecimal, BigDecimal>,
+}
+
+impl PriceBreaks {
+    /// Creates a new empty [`PriceBreaks`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// registers the unit price from the specified minimum quantity, replacing any previous one.
+    /// The unit price can't grow with the quantity, so it must not be over the price of a lower
+    /// break nor under the price of a higher one.
+    /// Could return [BagginsError::NegativeQty] [BagginsError::InvalidDecimalValue] [BagginsError::Other]
+    pub fn add_break(
+        &mut self,
+        min_qty: BigDecimal,
+        unit_price: BigDecimal,
+    ) -> Option<BagginsError<String>> {
+        if min_qty < zero() {
+            return Some(BagginsError::NegativeQty(format!(
+                "negative price break quantity {}",
+                min_qty
+            )));
+        }
+
+        if unit_price < zero() {
+            return Some(BagginsError::InvalidDecimalValue(format!(
+                "negative price break unit price {}",
+                unit_price
+            )));
+        }
+
+        let lower = self.breaks.range(..&min_qty).next_back();
+        let higher = self
+            .breaks
+            .range((
+                std::ops::Bound::Excluded(&min_qty),
+                std::ops::Bound::Unbounded,
+            ))
+            .next();
+
+        if let Some((qty, price)) = lower {
+            if unit_price > *price {
+                return Some(BagginsError::Other(format!(
+                    "unit price {} from qty {} over the unit price {} from the lower qty {}",
+                    unit_price, min_qty, price, qty
+                )));
+            }
+        }
+
+        if let Some((qty, price)) = higher {
+            if unit_price < *price {
+                return Some(BagginsError::Other(format!(
+                    "unit price {} from qty {} under the unit price {} from the higher qty {}",
+                    unit_price, min_qty, price, qty
+                )));
+            }
+        }
+
+        self.breaks.insert(min_qty, unit_price);
+        None
+    }
+
+    /// returns the unit price of the highest break whose minimum quantity is not over the
+    /// specified quantity, if any
+    pub fn unit_price(&self, qty: &BigDecimal) -> Option<&BigDecimal> {
+        self.breaks
+            .range(..=qty)
+            .next_back()
+            .map(|(_, price)| price)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+/// the import costs added to a line to get its landed cost, see [DetailCalculator::landed_cost]
+pub struct LandedCostInput {
+    /// the customs duty of the line
+    pub duty: BigDecimal,
+    /// the freight allocated to the line
+    pub freight: BigDecimal,
+}
+
+impl LandedCostInput {
+    /// Creates a new [`LandedCostInput`].
+    pub fn new(duty: BigDecimal, freight: BigDecimal) -> Self {
+        Self { duty, freight }
+    }
+}
+
+/// a service charge of an invoice, like the tip of a restaurant, taxed independently from the
+/// goods, so it carries its own [tax::TaxComputer].
+pub struct ServiceCharge {
+    /// the service charge amount before taxes
+    pub amount: BigDecimal,
+    /// the taxes applied to the service charge amount
+    pub tax: tax::TaxComputer,
+}
+
+impl ServiceCharge {
+    /// Creates a new [`ServiceCharge`].
+    pub fn new(amount: BigDecimal, tax: tax::TaxComputer) -> Self {
+        Self { amount, tax }
+    }
+}
+
+#[derive(Debug, Serialize, Default)]
+/// will contain the result of the computing of an invoice with a [ServiceCharge], keeping the
+/// goods and the service charge apart
+pub struct ServiceInvoice {
+    /// stores the calculation of each line of goods
+    pub lines: Vec<Calculation>,
+    /// stores the cumulated values of the goods
+    pub goods: InvoiceTotals,
+    /// stores the service charge amount
+    pub service_net: BigDecimal,
+    /// stores the tax calculated over the service charge amount
+    pub service_tax: BigDecimal,
+    /// stores the goods and service nets plus their taxes
+    pub brute: BigDecimal,
+}
+
+/// a gratuity of an invoice, like a tip. Depending on the region a gratuity may be taxed with
+/// the same taxes as the goods or not taxed at all.
+pub struct Gratuity {
+    /// the gratuity amount before taxes
+    pub amount: BigDecimal,
+    /// true if the registered taxes apply to the gratuity
+    pub taxable: bool,
+}
+
+impl Gratuity {
+    /// Creates a new [`Gratuity`].
+    pub fn new(amount: BigDecimal, taxable: bool) -> Self {
+        Self { amount, taxable }
+    }
+}
+
+#[derive(Debug, Serialize, Default)]
+/// will contain the result of the computing of an invoice with a [Gratuity], keeping the goods
+/// and the gratuity apart
+pub struct GratuityInvoice {
+    /// stores the calculation of each line of goods
+    pub lines: Vec<Calculation>,
+    /// stores the cumulated values of the goods
+    pub goods: InvoiceTotals,
+    /// stores the gratuity amount
+    pub gratuity_net: BigDecimal,
+    /// stores the tax calculated over the gratuity amount, zero if the gratuity is not taxable
+    pub gratuity_tax: BigDecimal,
+    /// stores the goods and gratuity nets plus their taxes
+    pub brute: BigDecimal,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+/// a line of a transaction, see [DetailCalculator::build_receipt]
+pub struct LineInput {
+    /// the unit value of the line
+    pub unit_value: BigDecimal,
+    /// the quantity of the line
+    pub qty: BigDecimal,
+    /// the max discount allowed for the line
+    pub max_discount_allowed: Option<BigDecimal>,
+    /// how the taxes of the line must be reported
+    pub tax_status: LineTaxStatus,
+}
+
+impl LineInput {
+    /// Creates a new [`LineInput`] without a max discount and taxed at the registered rates.
+    pub fn new(unit_value: BigDecimal, qty: BigDecimal) -> Self {
+        Self {
+            unit_value,
+            qty,
+            max_discount_allowed: None,
+            tax_status: LineTaxStatus::Standard,
+        }
+    }
+}
+
+/// the error of a line of a transaction along with the index of the line, see
+/// [DetailCalculator::compute_batch_collect]
+pub type LineError = (usize, BagginsError<String>);
+
+#[derive(Debug, Serialize, Default, Clone, PartialEq)]
+/// the grand totals of a transaction
+pub struct InvoiceTotals {
+    /// stores the cumulated net of the lines, with discounts
+    pub net: BigDecimal,
+    /// stores the cumulated tax of the lines
+    pub tax: BigDecimal,
+    /// stores the cumulated brute of the lines
+    pub brute: BigDecimal,
+    /// stores the cumulated discount of the lines
+    pub discount_value: BigDecimal,
+}
+
+impl InvoiceTotals {
+    /// cumulates the values with discount of the specified line
+    fn add(&mut self, calc: &Calculation) {
+        self.net += &calc.with_discount_values.net;
+        self.tax += &calc.with_discount_values.tax;
+        self.brute += &calc.with_discount_values.brute;
+        self.discount_value += &calc.with_discount_values.discount_value;
+    }
+}
+
+/// returns the [InvoiceTotals] of the specified lines, summing their values with discount
+pub fn sum_calculations(calcs: &[Calculation]) -> InvoiceTotals {
+    let mut totals = InvoiceTotals::default();
+
+    for calc in calcs {
+        totals.add(calc);
+    }
+
+    totals
+}
+
+#[derive(Debug, Serialize, Default)]
+/// will contain a full transaction ready to be serialized for a receipt, see
+/// [DetailCalculator::build_receipt]
+pub struct Receipt {
+    /// stores the calculation of each line
+    pub lines: Vec<Calculation>,
+    /// stores the taxes of the lines grouped by [LineTaxStatus], see [tax_summary]
+    pub tax_summary: Vec<TaxSummaryRow>,
+    /// stores the grand totals of the lines
+    pub totals: InvoiceTotals,
+}
+
+/// returns the blended tax rate of an invoice as a percentage, weighting the tax of each
+/// [Calculation] by its net instead of averaging their rates.
+///
+/// `sum(tax) * 100 / sum(net)` using the values with discount of every line.
+/// If the sum of the nets is zero, zero is returned.
+pub fn blended_rate(calcs: &[Calculation]) -> BigDecimal {
+    let mut tax = zero();
+    let mut net = zero();
+
+    for calc in calcs {
+        tax += &calc.with_discount_values.tax;
+        net += &calc.with_discount_values.net;
+    }
+
+    if net == zero() {
+        return zero();
+    }
+
+    tax * hundred() / net
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+/// Which values of a [Calculation] are rounded by [DetailCalculator]
+pub enum RoundingTarget {
+    /// values keep their full precision. This is the default
+    #[default]
+    Unrounded,
+
+    /// every value is rounded independently, so net + tax could differ from brute
+    Independent,
+
+    /// only brute is rounded, tax is derived from it as `round(brute * rate / (1 + rate))`
+    /// and net as `brute - tax`, so net + tax is exactly the rounded brute
+    Brute,
+}
+
+/// rounds net + tax to the specified scale and back solves net and tax from it,
+/// returning (net, tax, brute)
+fn round_brute(
+    net: &BigDecimal,
+    tax: &BigDecimal,
+    scale: i64,
+) -> (BigDecimal, BigDecimal, BigDecimal) {
+    let brute = net + tax;
+
+    if brute == zero() {
+        return (zero(), zero(), zero());
+    }
+
+    let rounded_brute = brute.round(scale);
+    // rate / (1 + rate) == tax / brute
+    let rounded_tax = (&rounded_brute * tax / &brute).round(scale);
+
+    (&rounded_brute - &rounded_tax, rounded_tax, rounded_brute)
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// Different types of surcharges, which increase the net of a line before taxes
+pub enum SurchargeMode {
+    /// a surcharge applied as an amount over the entirety of the line without consider quantity,
+    /// as a handling fee or a small order fee
+    AmountLine,
+
+    /// a surcharge applied as an amount by each unit being sold
+    AmountUnit,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+/// non fatal findings over a computed [Calculation] that usually point to a misconfiguration,
+/// see [DetailCalculator::compute_checked]
+pub enum Warning {
+    /// the tax is over the net with discount, like with a tax of 200%
+    TaxExceedsNet,
+    /// the discount value is over half of the net before discounts
+    DiscountExceedsHalfNet,
+    /// a percentual discount under 1% is registered, like 0.1, which may have been meant as a
+    /// fraction of 10% instead of a percent
+    SuspiciousFractionalPercent,
+}
+
+#[derive(Debug, Serialize, Default, Clone, Copy, PartialEq)]
+/// reports which guards were activated by [DetailCalculator::compute_with_flags]
+pub struct ComputeFlags {
+    /// the discount value was over the max discount allowed and it was capped
+    pub discount_capped: bool,
+    /// the discount value was over the line value and it was clamped to leave net in zero
+    pub net_clamped: bool,
+    /// some value was rounded by the scale cap
+    pub scale_capped: bool,
+}
+
+impl ComputeFlags {
+    /// returns true if any guard was activated
+    pub fn any(&self) -> bool {
+        self.discount_capped || self.net_clamped || self.scale_capped
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+/// reports how much of the requested discount was applied by [DetailCalculator::compute_with_clip]
+pub struct DiscountClipInfo {
+    /// the discount value of the registered discounts
+    pub requested: BigDecimal,
+    /// the discount value applied after the caps
+    pub applied: BigDecimal,
+    /// the discount value trimmed by the caps, requested minus applied
+    pub clipped: BigDecimal,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+/// the account a [LedgerEntry] is posted to
+pub enum LedgerAccount {
+    /// the line value before discounts
+    Sales,
+    /// the discounts given, posted as a negative amount
+    Discount,
+    /// the taxes charged
+    Tax,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+/// a signed posting of a [Calculation], see [DetailCalculator::to_ledger_entries]
+pub struct LedgerEntry {
+    /// the account of the posting
+    pub account: LedgerAccount,
+    /// the signed amount of the posting
+    pub amount: BigDecimal,
+}
+
+// A thing able to calculate sales values
+pub trait Calculator {
+    /// adds a [BigDecimal] discount value of the specified [discount::Mode] to [Calculator].
+    fn add_discount(
+        &mut self,
+        discount: BigDecimal,
+        discount_mode: discount::Mode,
+    ) -> Option<discount::DiscountError<String>>;
+
+    /// adds a [f64] discount value of the specified [discount::Mode]
+    /// to [Calculator] so expect some precision loss
+    fn add_discount_from_f64(
+        &mut self,
+        discount: f64,
+        discount_mode: discount::Mode,
+    ) -> Option<discount::DiscountError<String>>;
+
+    /// adds an [`Into<String>`] discount value of the specified [discount::Mode]
+    /// to [`Calculator`]
+    fn add_discount_from_str<S: Into<String>>(
+        &mut self,
+        discount: S,
+        discount_mode: discount::Mode,
+    ) -> Option<discount::DiscountError<String>>;
+
+    /// adds a tax to the specified [tax::Stage] in [Calculator] from a [f64] value
+    /// of the specified [tax::Mode] so expect some precision loss
+    fn add_tax_from_f64(
+        &mut self,
+        tax: f64,
+        stage: tax::Stage,
+        tax_mode: tax::Mode,
+    ) -> Option<tax::TaxError<String>>;
+
+    /// adds a tax to the specified [tax::Stage] in [Calculator] from a [BigDecimal]
+    fn add_tax(
+        &mut self,
+        tax: BigDecimal,
+        stage: tax::Stage,
+        tax_mode: tax::Mode,
+    ) -> Option<tax::TaxError<String>>;
+
+    /// adds a tax to the specified [tax::Stage] in [Calculator] from a [String]
+    fn add_tax_from_str<S: Into<String>>(
+        &mut self,
+        tax: S,
+        stage: tax::Stage,
+        tax_mode: tax::Mode,
+    ) -> Option<tax::TaxError<String>>;
+
+    /// adds a [BigDecimal] discount value of the specified [discount::Mode] to [Calculator].
+    /// Same as [Calculator::add_discount] but returns a [Result] so it can be used with `?`
+    fn try_add_discount(
+        &mut self,
+        discount: BigDecimal,
+        discount_mode: discount::Mode,
+    ) -> Result<(), discount::DiscountError<String>> {
+        match self.add_discount(discount, discount_mode) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// adds a [f64] discount value of the specified [discount::Mode] to [Calculator].
+    /// Same as [Calculator::add_discount_from_f64] but returns a [Result] so it can be used with `?`
+    fn try_add_discount_from_f64(
+        &mut self,
+        discount: f64,
+        discount_mode: discount::Mode,
+    ) -> Result<(), discount::DiscountError<String>> {
+        match self.add_discount_from_f64(discount, discount_mode) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// adds an [`Into<String>`] discount value of the specified [discount::Mode] to [Calculator].
+    /// Same as [Calculator::add_discount_from_str] but returns a [Result] so it can be used with `?`
+    fn try_add_discount_from_str<S: Into<String>>(
+        &mut self,
+        discount: S,
+        discount_mode: discount::Mode,
+    ) -> Result<(), discount::DiscountError<String>> {
+        match self.add_discount_from_str(discount, discount_mode) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// adds a tax to the specified [tax::Stage] in [Calculator] from a [BigDecimal].
+    /// Same as [Calculator::add_tax] but returns a [Result] so it can be used with `?`
+    fn try_add_tax(
+        &mut self,
+        tax: BigDecimal,
+        stage: tax::Stage,
+        tax_mode: tax::Mode,
+    ) -> Result<(), tax::TaxError<String>> {
+        match self.add_tax(tax, stage, tax_mode) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// adds a tax to the specified [tax::Stage] in [Calculator] from a [f64].
+    /// Same as [Calculator::add_tax_from_f64] but returns a [Result] so it can be used with `?`
+    fn try_add_tax_from_f64(
+        &mut self,
+        tax: f64,
+        stage: tax::Stage,
+        tax_mode: tax::Mode,
+    ) -> Result<(), tax::TaxError<String>> {
+        match self.add_tax_from_f64(tax, stage, tax_mode) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// adds a tax to the specified [tax::Stage] in [Calculator] from a [String].
+    /// Same as [Calculator::add_tax_from_str] but returns a [Result] so it can be used with `?`
+    fn try_add_tax_from_str<S: Into<String>>(
+        &mut self,
+        tax: S,
+        stage: tax::Stage,
+        tax_mode: tax::Mode,
+    ) -> Result<(), tax::TaxError<String>> {
+        match self.add_tax_from_str(tax, stage, tax_mode) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// calculates and produces a [Calculation] from a [BigDecimal] brute value
+    /// and a quantity of the same type
+    /// Could return [BagginsError::NegativeQty] [BagginsError::InvalidDecimalValue] [BagginsError::Tax]
+    /// [BagginsError::Discount] [BagginsError::Other]
+    fn compute_from_brute(
+        &mut self,
+        brute: BigDecimal,
+        qty: BigDecimal,
+        max_discount_allowed: Option<BigDecimal>,
+    ) -> BagginsResult<Calculation>;
+
+    /// calculates and produces a [Calculation] from a [f64] brute subtotal value
+    /// and a quantity of the same type. Use of [f64] may cause precission loss
+    fn compute_from_brute_f64(
+        &mut self,
+        brute: f64,
+        qty: f64,
+        max_discount_allowed: Option<f64>,
+    ) -> BagginsResult<Calculation>;
+
+    /// calculates and produces a [Calculation] from a [String] brute value
+    /// and a quantity of the same type
+    fn compute_from_brute_str<S: Into<String>>(
+        &mut self,
+        brute: S,
+        qty: S,
+        max_discount_allowed: Option<S>,
+    ) -> BagginsResult<Calculation>;
+
+    /// calculates and produces a [Calculation] from a [String] unit value
+    /// and a quantity of the same type
+    fn compute_from_str<S: Into<String>>(
+        &mut self,
+        unit_value: S,
+        qty: S,
+        max_discount_allowed: Option<S>,
+    ) -> BagginsResult<Calculation>;
+
+    /// calculates and produces a [Calculation] from a [f64] unit value
+    /// and a quantity of the same type. Use of [f64] may cause precission loss
+    fn compute_from_f64(
+        &mut self,
+        unit_value: f64,
+        qty: f64,
+        max_discount_allowed: Option<f64>,
+    ) -> BagginsResult<Calculation>;
+
+    /// calculates and produces a [Calculation] from a [BigDecimal] unit value
+    /// and a quantity of the same type
+    /// Receives an [Option<BigDecimal>] to use as a maximum discount value. If [None] the max discount value will be the
+    /// equivalent to the 100% of the calculated brute subtotal. If [Some] will be validated and used the passed value.
+    /// If the unwrapped [BigDecimal] is negative or greater than 100, a Bigdecimal with the 100 value will be used
+    /// as maximum allowed discount.
+    /// Could return [BagginsError::NegativeQty] [BagginsError::InvalidDecimalValue] [BagginsError::Tax]
+    /// [BagginsError::Discount] [BagginsError::Other]
+    fn compute(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+        max_discount_allowed: Option<BigDecimal>,
+    ) -> BagginsResult<Calculation>;
+
+    /// calculates and produces a [Calculation] from an integer unit value expressed in minor units
+    /// at the specified scale, and an integer quantity. So (10000, 2, 2) is a unit value of 100.00
+    /// and a quantity of 2. The optional max discount is expressed in minor units too.
+    fn compute_from_i64(
+        &mut self,
+        unit_minor: i64,
+        qty: i64,
+        scale: u32,
+        max_discount_allowed: Option<i64>,
+    ) -> BagginsResult<Calculation> {
+        let scale = i64::from(scale);
+
+        self.compute(
+            BigDecimal::new(unit_minor.into(), scale),
+            BigDecimal::from(qty),
+            max_discount_allowed.map(|max| BigDecimal::new(max.into(), scale)),
+        )
+    }
+
+    /// an utility to calculate a tax directly
+    ///
+    /// # Params
+    ///
+    /// taxable [BigDecimal] value to tax
+    ///
+    /// qty     [BigDecimal] quantity being sold (some tax needs to know this value to be calculated)
+    ///
+    /// value   [BigDecimal] percent or amount to apply as tax
+    ///
+    /// mode    [tax::Mode]  wheter the tax is percentual, amount by unit or amount by line
+    ///
+    fn line_tax(
+        &mut self,
+        taxable: BigDecimal,
+        qty: BigDecimal,
+        value: BigDecimal,
+        mode: tax::Mode,
+    ) -> Result<BigDecimal, tax::TaxError<String>>;
+
+    /// an utility to calculate a tax directly using [String]s as entry.
+    /// Converts values to BigDecimal.
+    ///
+    /// # Params
+    ///
+    /// taxable [String] value to tax
+    ///
+    /// qty     [String] quantity being sold (some tax needs to know this value to be calculated)
+    ///
+    /// value   [String] percent or amount to apply as tax
+    ///
+    /// mode    [tax::Mode]  wheter the tax is percentual, amount by unit or amount by line
+    ///
+    fn line_tax_from_str<S: Into<String>>(
+        &mut self,
+        taxable: S,
+        qty: S,
+        value: S,
+        mode: tax::Mode,
+    ) -> Result<BigDecimal, tax::TaxError<String>>;
+
+    /// an utility to calculate a tax directly using [f64]s as entry. Some precission could be loss.
+    /// Converts values to BigDecimal.
+    ///
+    /// # Params
+    ///
+    /// taxable [f64] value to tax
+    ///
+    /// qty     [f64] quantity being sold (some tax needs to know this value to be calculated)
+    ///
+    /// value   [f64] percent or amount to apply as tax
+    ///
+    /// mode    [tax::Mode]  wheter the tax is percentual, amount by unit or amount by line
+    ///
+    fn line_tax_from_f64(
+        &mut self,
+        taxable: f64,
+        qty: f64,
+        value: f64,
+        mode: tax::Mode,
+    ) -> Result<BigDecimal, tax::TaxError<String>>;
+}
+
+pub struct DetailCalculator {
+    tax_handler: tax::TaxComputer,
+    gross_tax_handler: tax::TaxComputer,
+    discount_handler: discount::DiscountComputer,
+    scale_cap: Option<i64>,
+    rounding: RoundingTarget,
+    rounding_scale: i64,
+    epsilon_snap: Option<BigDecimal>,
+    strict_input_scale: Option<u32>,
+    percent_display_scale: Option<u32>,
+    discount_tiers: discount::DiscountTiers,
+    surcharge_line: BigDecimal,
+    surcharge_unit: BigDecimal,
+    exact_rational: bool,
+    precision_log: Option<Vec<usize>>,
+}
+
+impl DetailCalculator {
+    /// Creates a new [`DetailCalculator`].
+    pub fn new() -> Self {
+        Self {
+            tax_handler: tax::TaxComputer::default(),
+            gross_tax_handler: tax::TaxComputer::default(),
+            discount_handler: discount::DiscountComputer::default(),
+            scale_cap: None,
+            rounding: RoundingTarget::default(),
+            rounding_scale: 2,
+            epsilon_snap: None,
+            strict_input_scale: None,
+            percent_display_scale: None,
+            discount_tiers: discount::DiscountTiers::default(),
+            surcharge_line: zero(),
+            surcharge_unit: zero(),
+            exact_rational: false,
+            precision_log: None,
+        }
+    }
+
+    /// adds a tax to the specified [tax::Stage] choosing over which [tax::TaxBase] it is calculated.
+    /// Taxes added with [Calculator::add_tax] are calculated over the [tax::TaxBase::DiscountedNet].
+    ///
+    /// The inverse operations, like [DetailCalculator::original_from_final] and
+    /// [Calculator::compute_from_brute], only consider the [tax::TaxBase::DiscountedNet] taxes.
+    /// Could return [tax::TaxError::NegativeValue] wrapped in [Option]
+    pub fn add_tax_with_base(
+        &mut self,
+        tax: BigDecimal,
+        stage: tax::Stage,
+        tax_mode: tax::Mode,
+        base: tax::TaxBase,
+    ) -> Option<tax::TaxError<String>> {
+        match base {
+            tax::TaxBase::DiscountedNet => self.tax_handler.add_tax(tax, stage, tax_mode),
+            tax::TaxBase::Gross => self.gross_tax_handler.add_tax(tax, stage, tax_mode),
+        }
+    }
+
+    /// adds a percentual tax to the specified [tax::Stage] applied only up to the maximum assessable
+    /// value of the line, see [tax::TaxComputer::add_tax_with_assessable_cap]. Once added,
+    /// [Calculator::compute_from_brute] and [DetailCalculator::original_from_final] fail.
+    /// Could return [tax::TaxError::NegativeValue] wrapped in [Option]
+    pub fn add_tax_with_assessable_cap(
+        &mut self,
+        percent: BigDecimal,
+        stage: tax::Stage,
+        assessable_cap: Option<BigDecimal>,
+    ) -> Option<tax::TaxError<String>> {
+        self.tax_handler
+            .add_tax_with_assessable_cap(percent, stage, assessable_cap)
+    }
+
+    /// adds a surcharge of the specified [SurchargeMode], which increases the net before taxes.
+    /// Unlike discounts, surcharges are applied to the values with and without discount.
+    /// Could return [BagginsError::Other] wrapped in [Option] if the amount is negative
+    pub fn add_surcharge(
+        &mut self,
+        amount: BigDecimal,
+        mode: SurchargeMode,
+    ) -> Option<BagginsError<String>> {
+        if amount < zero() {
+            return Some(BagginsError::Other(format!(
+                "negative surcharge {}",
+                amount
+            )));
+        }
+
+        match mode {
+            SurchargeMode::AmountLine => self.surcharge_line = &self.surcharge_line + amount,
+            SurchargeMode::AmountUnit => self.surcharge_unit = &self.surcharge_unit + amount,
+        }
+
+        None
+    }
+
+    /// returns the cumulated surcharge of the specified [SurchargeMode]
+    pub fn surcharge(&self, mode: SurchargeMode) -> BigDecimal {
+        match mode {
+            SurchargeMode::AmountLine => self.surcharge_line.clone(),
+            SurchargeMode::AmountUnit => self.surcharge_unit.clone(),
+        }
+    }
+
+    /// computes a [Calculation] for each of the specified quantities with the same unit value,
+    /// returning each quantity paired with its [Calculation]. Useful to build quantity break tables.
+    /// Stops at the first quantity failing, returning its [BagginsError]
+    pub fn compute_quantity_table(
+        &mut self,
+        unit_value: BigDecimal,
+        quantities: &[BigDecimal],
+        max: Option<BigDecimal>,
+    ) -> BagginsResult<Vec<(BigDecimal, Calculation)>> {
+        let mut table = Vec::with_capacity(quantities.len());
+
+        for qty in quantities {
+            let calc = self.compute(unit_value.clone(), qty.clone(), max.clone())?;
+            table.push((qty.clone(), calc));
+        }
+
+        Ok(table)
+    }
+
+    /// validates the brute of the line never decreases as the quantity grows from 1 to `max_qty`,
+    /// so a larger quantity is never cheaper in total than a smaller one. Useful to catch
+    /// misconfigured volume pricing before it reaches a store.
+    /// Could return [BagginsError::NegativeQty] [BagginsError::Other] at the first quantity
+    /// whose brute is lower than the brute of the previous quantity
+    pub fn validate_monotonic_brute(
+        &mut self,
+        unit_value: BigDecimal,
+        max_qty: u32,
+        max: Option<BigDecimal>,
+    ) -> BagginsResult<()> {
+        let mut previous: Option<BigDecimal> = None;
+
+        for qty in 1..=max_qty {
+            let calc = self.compute(unit_value.clone(), BigDecimal::from(qty), max.clone())?;
+            let brute = calc.with_discount_values.brute;
+
+            if let Some(previous) = &previous {
+                if brute < *previous {
+                    return Err(BagginsError::Other(format!(
+                        "the brute {} of qty {} is lower than the brute {} of qty {}",
+                        brute,
+                        qty,
+                        previous,
+                        qty - 1
+                    )));
+                }
+            }
+
+            previous = Some(brute);
+        }
+
+        Ok(())
+    }
+
+    /// returns the equation used to compute the line with the registered values substituted, as in
+    /// `net = 100 × 2 − (100 × 2 × 10/100 + 1 × 2) ; tax = net × 16/100 ; brute = net + tax`.
+    /// Meant as a learning aid, it describes the stacked discounts and the
+    /// [tax::TaxBase::DiscountedNet] taxes only. Terms with zero values are left out.
+    pub fn explain_equation(&self, unit_value: BigDecimal, qty: BigDecimal) -> String {
+        use tax::Stager;
+
+        let line = format!("{} × {}", unit_value, qty);
+        let amount_terms = |percent_base: &str, percent: BigDecimal, unit, amount_line| {
+            let mut terms = Vec::new();
+            if percent != zero() {
+                terms.push(format!("{} × {}/100", percent_base, percent));
+            }
+            if unit != zero() {
+                terms.push(format!("{} × {}", unit, qty));
+            }
+            if amount_line != zero() {
+                terms.push(format!("{}", amount_line));
+            }
+            terms
+        };
+
+        let mut percentual = zero();
+        let mut amount_unit = zero();
+        let mut amount_line = zero();
+        for (mode, value) in self.discount_handler.discounts() {
+            match mode {
+                discount::Mode::Percentual => percentual += value,
+                discount::Mode::AmountUnit => amount_unit += value,
+                discount::Mode::AmountLine => amount_line += value,
+            }
+        }
+
+        let mut net = line.clone();
+        let surcharge = amount_terms(
+            "",
+            zero(),
+            self.surcharge_unit.clone(),
+            self.surcharge_line.clone(),
+        );
+        if !surcharge.is_empty() {
+            net = format!("{} + ({})", net, surcharge.join(" + "));
+        }
+        let discount = amount_terms(&line, percentual, amount_unit, amount_line);
+        if !discount.is_empty() {
+            net = format!("{} − ({})", net, discount.join(" + "));
+        }
+
+        let stage = self.tax_handler.over_taxables();
+        let over_taxable = amount_terms(
+            "net",
+            stage.percent(),
+            stage.amount_by_qty(),
+            stage.amount_line(),
+        );
+        let over_tax_base = if over_taxable.is_empty() {
+            "net".to_string()
+        } else {
+            format!("(net + ({}) × {})", over_taxable.join(" + "), qty)
+        };
+        let stage = self.tax_handler.over_taxes();
+        let mut over_tax = amount_terms(
+            &over_tax_base,
+            stage.percent(),
+            stage.amount_by_qty(),
+            stage.amount_line(),
+        );
+        if stage.percent_of_tax() != zero() && !over_taxable.is_empty() {
+            over_tax.push(format!(
+                "({}) × {}/100",
+                over_taxable.join(" + "),
+                stage.percent_of_tax()
+            ));
+        }
+        let stage = self.tax_handler.over_tax_ignorables();
+        let over_tax_ignorable = amount_terms(
+            "net",
+            stage.percent(),
+            stage.amount_by_qty(),
+            stage.amount_line(),
+        );
+
+        let taxes = [over_taxable, over_tax, over_tax_ignorable].concat();
+        let tax = if taxes.is_empty() {
+            "0".to_string()
+        } else {
+            taxes.join(" + ")
+        };
+
+        format!("net = {} ; tax = {} ; brute = net + tax", net, tax)
+    }
+
+    /// decomposes a [Calculation] in signed postings: a positive sales entry with the line value
+    /// before discounts, a negative discount entry, and a positive tax entry. The entries always
+    /// sum to the brute of the calculation.
+    pub fn to_ledger_entries(&self, calc: &Calculation) -> Vec<LedgerEntry> {
+        let gross = &calc.without_discount_values.net;
+        let net = &calc.with_discount_values.net;
+        let brute = &calc.with_discount_values.brute;
+
+        vec![
+            LedgerEntry {
+                account: LedgerAccount::Sales,
+                amount: gross.clone(),
+            },
+            LedgerEntry {
+                account: LedgerAccount::Discount,
+                amount: net - gross,
+            },
+            LedgerEntry {
+                account: LedgerAccount::Tax,
+                amount: brute - net,
+            },
+        ]
+    }
+
+    /// returns the brute difference between discounting then taxing, the way [Calculator::compute]
+    /// works, and taxing then discounting, where the registered discounts are applied over the tax
+    /// inclusive value. Returns zero when the order doesn't matter, like with percentual only
+    /// discounts and taxes, and non zero when amounts make the result depend on the order.
+    /// Could return [BagginsError::NegativeQty] [BagginsError::Other]
+    pub fn order_sensitivity(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+    ) -> BagginsResult<BigDecimal> {
+        if qty == zero() {
+            return Ok(zero());
+        }
+
+        let discount_then_tax = self.compute(unit_value, qty.clone(), None)?;
+
+        let gross = &discount_then_tax.without_discount_values.brute;
+        let discount = self.discount_handler.discount_value(&(gross / &qty), &qty);
+        let tax_then_discount = gross - discount;
+
+        Ok(&discount_then_tax.with_discount_values.brute - tax_then_discount)
+    }
+
+    /// splits the tax of the [Calculation] between buyer and seller by the agreed buyer share,
+    /// returning (buyer_tax, seller_tax). The seller tax is rounded down to cents and the buyer
+    /// takes the residue, so both sum exactly to the tax.
+    /// Could return [BagginsError::Other] if the share is out of the range 0..=100
+    pub fn split_tax(
+        &self,
+        calc: &Calculation,
+        buyer_share_pct: BigDecimal,
+    ) -> BagginsResult<(BigDecimal, BigDecimal)> {
+        if buyer_share_pct < zero() || buyer_share_pct > hundred() {
+            return Err(BagginsError::Other(format!(
+                "buyer share {}% out of the range 0 to 100",
+                buyer_share_pct
+            )));
+        }
+
+        let tax = &calc.with_discount_values.tax;
+        let seller_tax = (tax * (hundred() - buyer_share_pct) / hundred())
+            .with_scale_round(2, RoundingMode::Down);
+
+        Ok((tax - &seller_tax, seller_tax))
+    }
+
+    /// returns true when the effective discount percent of the [Calculation], the discount over the
+    /// line value before discounts, exceeds the threshold. Useful to flag loss leaders and pricing
+    /// errors. A line with no value is never a deep discount.
+    pub fn is_deep_discount(&self, calc: &Calculation, threshold_pct: BigDecimal) -> bool {
+        let gross = &calc.without_discount_values.net;
+
+        if *gross == zero() {
+            return false;
+        }
+
+        discount::discount_percent(&(gross - &calc.with_discount_values.net), gross) > threshold_pct
+    }
+
+    /// returns the all in factor of the [Calculation], the brute over the net before discounts,
+    /// condensing the combined effect of discounts and taxes in a single figure. A factor of 1.062
+    /// means the customer pays 6.2% over the list net. A line with no value has a zero factor.
+    pub fn all_in_factor(&self, calc: &Calculation) -> BigDecimal {
+        let gross = &calc.without_discount_values.net;
+
+        if *gross == zero() {
+            return zero();
+        }
+
+        &calc.with_discount_values.brute / gross
+    }
+
+    /// returns the contribution of the line to the rounding adjustment of the invoice tax, the tax
+    /// with discount rounded to `scale` minus the unrounded tax. The contributions of the lines sum
+    /// to [reconcile_tax] when the unrounded invoice tax has no digits beyond `scale`.
+    pub fn line_rounding_contribution(&self, calc: &Calculation, scale: u32) -> BigDecimal {
+        let tax = &calc.with_discount_values.tax;
+
+        tax.round(i64::from(scale)) - tax
+    }
+
+    /// returns how much the brute of the line grows when one more unit is added, as in
+    /// `compute(.., qty + 1).brute - compute(.., qty).brute`, with the registered discounts and taxes.
+    /// Useful for upsell prompts like "add one more for just $X".
+    /// Could return [BagginsError::NegativeQty] [BagginsError::Other]
+    pub fn marginal_unit(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+        max: Option<BigDecimal>,
+    ) -> BagginsResult<BigDecimal> {
+        let current = self.compute(unit_value.clone(), qty.clone(), max.clone())?;
+        let next = self.compute(unit_value, qty + one(), max)?;
+
+        Ok(next.with_discount_values.brute - current.with_discount_values.brute)
+    }
+
+    /// returns the line value, before discounts and taxes, a customer can afford with the specified
+    /// tax inclusive budget, so computing that line gives a brute equal to the budget.
+    /// Same as [DetailCalculator::original_from_final]. Could return [BagginsError::Other]
+    pub fn affordable_net(
+        &self,
+        budget_brute: BigDecimal,
+        qty: BigDecimal,
+    ) -> BagginsResult<BigDecimal> {
+        if budget_brute < zero() {
+            return Err(BagginsError::Other(format!(
+                "negative budget_brute {}",
+                budget_brute
+            )));
+        }
+
+        self.original_from_final(budget_brute, qty)
+    }
+
+    /// returns the original line value, before discounts and taxes, of the specified tax inclusive
+    /// final value, removing the registered taxes with [tax::Taxer::un_tax], the surcharges, and
+    /// then the registered discounts with [discount::Discounter::un_discount].
+    /// Could return [BagginsError::Tax] [BagginsError::Discount]
+    pub fn original_from_final(
+        &self,
+        final_inclusive: BigDecimal,
+        qty: BigDecimal,
+    ) -> BagginsResult<BigDecimal> {
+        match self.tax_handler.un_tax(final_inclusive.clone(), qty.clone()) {
+            Ok(un_taxed) => match self
+                .discount_handler
+                .un_discount(&un_taxed - self.surcharge_value(&qty), qty)
+            {
+                Ok(un_discounted) => Ok(un_discounted.0),
+                Err(err) => Err(BagginsError::Discount(err)),
+            },
+            Err(err) => Err(BagginsError::Tax(err)),
+        }
+    }
+
+    /// returns the line net before discounts of the specified brute, removing the registered taxes
+    /// with [tax::Taxer::un_tax] and adding back the known discount, for records that keep the
+    /// brute and the discount but not the unit value.
+    /// Could return [BagginsError::NegativeQty] [BagginsError::InvalidDecimalValue] [BagginsError::Tax]
+    pub fn net_from_brute_and_discount(
+        &self,
+        brute: BigDecimal,
+        qty: BigDecimal,
+        known_discount: BigDecimal,
+    ) -> BagginsResult<BigDecimal> {
+        if qty < zero() {
+            return Err(BagginsError::NegativeQty(format!("negative qty {}", qty)));
+        }
+
+        if known_discount < zero() {
+            return Err(BagginsError::InvalidDecimalValue(format!(
+                "negative known discount {}",
+                known_discount
+            )));
+        }
+
+        match self.tax_handler.un_tax(brute.clone(), qty) {
+            Ok(un_taxed) => Ok(un_taxed + known_discount),
+            Err(err) => Err(BagginsError::Tax(err)),
+        }
+    }
+
+    fn surcharge_value(&self, qty: &BigDecimal) -> BigDecimal {
+        &self.surcharge_unit * qty + &self.surcharge_line
+    }
+
+    /// sets the maximum scale kept by the intermediate values of [Calculator::compute].
+    ///
+    /// Divisions and chained percentual taxes can make the fractional part of a [BigDecimal]
+    /// grow without bound, which gets slower with every operation. When [Some] the intermediate
+    /// values are rounded to the specified scale. Values out of the range 0..=128 are clamped to 128.
+    /// [None] (the default) keeps the full precision.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use baggins::DetailCalculator;
+    ///
+    /// let mut c = DetailCalculator::new();
+    /// c.set_scale_cap(Some(16));
+    /// ```
+    pub fn set_scale_cap(&mut self, scale: Option<i64>) {
+        self.scale_cap = scale.map(|scale| if !(0..=128).contains(&scale) { 128 } else { scale });
+    }
+
+    /// returns the scale cap used by [Calculator::compute] if any
+    pub fn scale_cap(&self) -> Option<i64> {
+        self.scale_cap
+    }
+
+    /// when enabled, [Calculator::compute_from_brute] keeps the value recovered from the brute as a
+    /// numerator and a denominator while removing the taxes, the surcharges, the discounts and the
+    /// quantity, and divides only once at the end, rounded to the scale cap if any. Otherwise each
+    /// step divides, and the precision lost by every non terminating division accumulates.
+    /// Only [Calculator::compute_from_brute] is affected, [Calculator::compute] and the taxes
+    /// keep dividing as usual.
+    pub fn set_exact_rational(&mut self, exact_rational: bool) {
+        self.exact_rational = exact_rational;
+    }
+
+    /// returns true if the divisions of [Calculator::compute_from_brute] are deferred
+    pub fn exact_rational(&self) -> bool {
+        self.exact_rational
+    }
+
+    /// returns the unit value of the specified brute dividing only once, see
+    /// [DetailCalculator::set_exact_rational]
+    fn rational_unit_value_from_brute(
+        &self,
+        brute: &BigDecimal,
+        qty: &BigDecimal,
+    ) -> BagginsResult<BigDecimal> {
+        if *qty == zero() {
+            return Err(BagginsError::Other(
+                "qty can't be zero to recover the unit value from the brute".to_string(),
+            ));
+        }
+
+        let (numerator, denominator) = self
+            .tax_handler
+            .un_tax_ratio(brute.clone(), qty.clone())
+            .map_err(BagginsError::Tax)?;
+
+        let numerator = numerator - self.surcharge_value(qty) * &denominator;
+
+        let (numerator, denominator) = self
+            .discount_handler
+            .un_discount_ratio(numerator, denominator, qty.clone())
+            .map_err(BagginsError::Discount)?;
+
+        let unit_value = numerator / (denominator * qty);
+
+        Ok(match self.scale_cap {
+            Some(scale) => unit_value.round(scale),
+            None => unit_value,
+        })
+    }
+
+    /// sets which values of the [Calculation] are rounded by [Calculator::compute]
+    /// and the scale they are rounded to. Scales out of the range 0..=128 are clamped to 128.
+    pub fn set_rounding(&mut self, target: RoundingTarget, scale: i64) {
+        self.rounding = target;
+        self.rounding_scale = if !(0..=128).contains(&scale) {
+            128
+        } else {
+            scale
+        };
+    }
+
+    /// returns the [RoundingTarget] and the scale used by [Calculator::compute]
+    pub fn rounding(&self) -> (RoundingTarget, i64) {
+        (self.rounding, self.rounding_scale)
+    }
+
+    /// sets a new unit price for the item, the discount becomes the difference between the unit
+    /// value and the override price for each unit, replacing the registered discounts.
+    /// See [discount::DiscountComputer::set_override_unit_price]
+    pub fn set_override_unit_price(
+        &mut self,
+        override_price: Option<BigDecimal>,
+    ) -> Option<discount::DiscountError<String>> {
+        self.discount_handler
+            .set_override_unit_price(override_price)
+    }
+
+    /// sets the threshold under which the values computed by [Calculator::compute] snap to zero,
+    /// so residues like 0.0000000001 are reported as zero. `None` disables the snap.
+    /// The snap is applied after the rounding, see [DetailCalculator::set_rounding].
+    pub fn set_epsilon_snap(&mut self, epsilon: Option<BigDecimal>) {
+        self.epsilon_snap = epsilon.map(|epsilon| epsilon.abs());
+    }
+
+    /// returns the threshold under which computed values snap to zero, if any
+    pub fn epsilon_snap(&self) -> Option<&BigDecimal> {
+        self.epsilon_snap.as_ref()
+    }
+
+    /// sets the max scale accepted for the unit value, quantity and max discount received by
+    /// [Calculator::compute] and [DetailCalculator::compute_with_flags], and for the brute received
+    /// by [Calculator::compute_from_brute]. Inputs with more decimals, ignoring trailing zeros, fail
+    /// with [BagginsError::InvalidDecimalValue]. `None` accepts any scale.
+    pub fn set_strict_input_scale(&mut self, scale: Option<u32>) {
+        self.strict_input_scale = scale;
+    }
+
+    /// returns the max scale accepted for the inputs, if any
+    pub fn strict_input_scale(&self) -> Option<u32> {
+        self.strict_input_scale
+    }
+
+    /// validates the inputs have at most the strict input scale, if any
+    fn check_input_scale(&self, inputs: &[(&str, Option<&BigDecimal>)]) -> BagginsResult<()> {
+        let max_scale = match self.strict_input_scale {
+            Some(scale) => i64::from(scale),
+            None => return Ok(()),
+        };
+
+        for (name, value) in inputs {
+            if let Some(value) = value {
+                let (_, scale) = value.normalized().as_bigint_and_exponent();
+                if scale > max_scale {
+                    return Err(BagginsError::InvalidDecimalValue(format!(
+                        "<{}> {} has more than {} decimals",
+                        name, value, max_scale
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// same as [Calculator::compute] without the strict input scale check, for the unit values
+    /// derived by the calculator itself, like the one recovered by [Calculator::compute_from_brute]
+    fn compute_unchecked(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+        max_discount_allowed: Option<BigDecimal>,
+    ) -> BagginsResult<Calculation> {
+        match self
+            .discount_handler
+            .compute(unit_value.clone(), qty.clone(), max_discount_allowed)
+        {
+            Ok(discount) => self.calculate(
+                unit_value,
+                qty,
+                discount.0,
+                &mut ComputeFlags::default(),
+            ),
+            Err(err) => Err(BagginsError::Discount(err)),
+        }
+    }
+
+    /// sets the scale the percentages of the [Calculation], like the total discount percent, are
+    /// rounded to by [Calculator::compute], leaving the monetary values untouched. `None` keeps
+    /// the percentages at full precision.
+    pub fn set_percent_display_scale(&mut self, scale: Option<u32>) {
+        self.percent_display_scale = scale;
+    }
+
+    /// returns the scale the percentages are rounded to, if any
+    pub fn percent_display_scale(&self) -> Option<u32> {
+        self.percent_display_scale
+    }
+
+    /// sets the discount tiers used by [DetailCalculator::compute_for_tier]
+    pub fn set_discount_tiers(&mut self, tiers: discount::DiscountTiers) {
+        self.discount_tiers = tiers;
+    }
+
+    /// returns the discount tiers used by [DetailCalculator::compute_for_tier]
+    pub fn discount_tiers(&self) -> &discount::DiscountTiers {
+        &self.discount_tiers
+    }
+
+    /// rounds the percentages of the calculation to the percent display scale, if any
+    fn apply_percent_display_scale(&self, mut calc: Calculation) -> Calculation {
+        if let Some(scale) = self.percent_display_scale {
+            calc.with_discount_values.total_discount_percent = calc
+                .with_discount_values
+                .total_discount_percent
+                .round(i64::from(scale));
+        }
+
+        calc
+    }
+
+    /// sets to zero the values of the calculation under the epsilon snap, if any
+    fn apply_snap(&self, calc: Calculation) -> Calculation {
+        match &self.epsilon_snap {
+            Some(epsilon) => {
+                let without_discount_values = calc.without_discount_values.snap(epsilon);
+                let mut with_discount_values = calc.with_discount_values.snap(epsilon);
+                with_discount_values.discount_brute_value =
+                    &with_discount_values.brute - &without_discount_values.brute;
+
+                Calculation {
+                    tax_breakdown: settle_breakdown(
+                        &calc.tax_breakdown,
+                        &calc.with_discount_values.tax,
+                        &with_discount_values.tax,
+                        |value| snap(value, epsilon),
+                    ),
+                    without_discount_values,
+                    with_discount_values,
+                }
+            }
+            None => calc,
+        }
+    }
+
+    /// rounds the calculation according to the [RoundingTarget]
+    fn apply_rounding(&self, calc: Calculation) -> Calculation {
+        let scale = self.rounding_scale;
+        let breakdown = calc.tax_breakdown.clone();
+        let tax = calc.with_discount_values.tax.clone();
+
+        let rounded = match self.rounding {
+            RoundingTarget::Unrounded => return calc,
+            RoundingTarget::Independent => Calculation {
+                without_discount_values: calc.without_discount_values.round(scale),
+                with_discount_values: calc.with_discount_values.round(scale),
+                tax_breakdown: calc.tax_breakdown,
+            },
+            RoundingTarget::Brute => {
+                let (net, tax, brute) = round_brute(
+                    &calc.with_discount_values.net,
+                    &calc.with_discount_values.tax,
+                    scale,
+                );
+                let (net_wd, tax_wd, brute_wd) = round_brute(
+                    &calc.without_discount_values.net,
+                    &calc.without_discount_values.tax,
+                    scale,
+                );
+
+                Calculation {
+                    with_discount_values: CalculationWithDiscount {
+                        discount_brute_value: &brute - &brute_wd,
+                        net,
+                        tax,
+                        brute,
+                        ..calc.with_discount_values
+                    },
+                    without_discount_values: CalculationWithoutDiscount {
+                        net: net_wd,
+                        tax: tax_wd,
+                        brute: brute_wd,
+                        ..calc.without_discount_values
+                    },
+                    tax_breakdown: calc.tax_breakdown,
+                }
+            }
+        };
+
+        Calculation {
+            tax_breakdown: settle_breakdown(
+                &breakdown,
+                &tax,
+                &rounded.with_discount_values.tax,
+                |value| value.round(scale),
+            ),
+            ..rounded
+        }
+    }
+
+    /// computes the [Calculation] for an already calculated discount value, applying
+    /// the registered taxes over the discounted unit value. The total discount percent is the
+    /// [discount::discount_percent] of the line value without discount, whatever the discount
+    /// comes from.
+    fn calculate(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+        discount_value: BigDecimal,
+        flags: &mut ComputeFlags,
+    ) -> BagginsResult<Calculation> {
+        let net_without_discount = &unit_value * &qty + self.surcharge_value(&qty);
+        let net = &net_without_discount - &discount_value;
+        let total_discount_percent =
+            discount::discount_percent(&discount_value, &net_without_discount);
+
+        self.tax_nets(
+            net_without_discount,
+            net,
+            qty,
+            discount_value,
+            total_discount_percent,
+            flags,
+        )
+    }
+
+    /// computes the [Calculation] applying the registered taxes over the already calculated
+    /// line nets without and with discount
+    fn tax_nets(
+        &mut self,
+        net_without_discount: BigDecimal,
+        net: BigDecimal,
+        qty: BigDecimal,
+        discount_value: BigDecimal,
+        total_discount_percent: BigDecimal,
+        flags: &mut ComputeFlags,
+    ) -> BagginsResult<Calculation> {
+        let discounted_uv = &net / &qty;
+
+        match self.tax_breakdown_by_base(&net, &net_without_discount, &qty) {
+            Ok((tax, tax_breakdown)) => {
+                if let Some(log) = &mut self.precision_log {
+                    log.extend(
+                        [
+                            &net_without_discount,
+                            &discount_value,
+                            &total_discount_percent,
+                            &net,
+                            &discounted_uv,
+                            &tax_breakdown.over_taxable,
+                            &tax_breakdown.over_tax,
+                            &tax_breakdown.over_tax_ignorable,
+                            &tax,
+                            &(&net + &tax),
+                        ]
+                        .iter()
+                        .map(|value| value.digits() as usize),
+                    );
+                }
+
+                let discounted_uv = self.cap(discounted_uv, flags);
+                match self.taxes_by_base(&net_without_discount, &net_without_discount, &qty) {
+                    Ok(tax_without_discount) => {
+                        let tax = self.cap(tax, flags);
+                        let tax_without_discount = self.cap(tax_without_discount, flags);
+                        let brute_without_discount = &net_without_discount + &tax_without_discount;
+                        let brute = &net + &tax;
+
+                        let calc = Calculation {
+                            without_discount_values: CalculationWithoutDiscount {
+                                brute: brute_without_discount.clone(),
+                                unit_value: self.cap(&net_without_discount / &qty, flags),
+                                net: net_without_discount,
+                                tax: tax_without_discount,
+                            },
+                            with_discount_values: CalculationWithDiscount {
+                                discount_brute_value: &brute - &brute_without_discount,
+                                brute,
+                                unit_value: discounted_uv,
+                                net,
+                                tax,
+                                discount_value,
+                                total_discount_percent,
+                            },
+                            tax_breakdown,
+                        };
+
+                        let calc = self.apply_snap(self.apply_rounding(calc));
+                        Ok(self.apply_percent_display_scale(calc))
+                    }
+                    Err(err) => Err(BagginsError::Tax(err)),
+                }
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// returns the credit of a return reversing the original [Calculation] exactly, with its net,
+    /// tax, brute, discounts and tax breakdown negated, so the refunded tax is the tax charged and
+    /// not a recomputed one. The unit values and the discount percent are kept as they are.
+    pub fn reverse_from_calculation(&self, original: &Calculation) -> Calculation {
+        let without_discount = &original.without_discount_values;
+        let with_discount = &original.with_discount_values;
+        let breakdown = &original.tax_breakdown;
+
+        Calculation {
+            without_discount_values: CalculationWithoutDiscount {
+                net: -&without_discount.net,
+                brute: -&without_discount.brute,
+                tax: -&without_discount.tax,
+                unit_value: without_discount.unit_value.clone(),
+            },
+            with_discount_values: CalculationWithDiscount {
+                net: -&with_discount.net,
+                brute: -&with_discount.brute,
+                tax: -&with_discount.tax,
+                discount_value: -&with_discount.discount_value,
+                discount_brute_value: -&with_discount.discount_brute_value,
+                total_discount_percent: with_discount.total_discount_percent.clone(),
+                unit_value: with_discount.unit_value.clone(),
+            },
+            tax_breakdown: tax::TaxBreakdown {
+                over_taxable: -&breakdown.over_taxable,
+                over_tax: -&breakdown.over_tax,
+                over_tax_ignorable: -&breakdown.over_tax_ignorable,
+            },
+        }
+    }
+
+    /// recomputes only the taxes of a prior [Calculation] with the registered taxes, reusing its
+    /// nets and discount as they are. Useful when only the taxes changed since the prior compute.
+    /// Could return [BagginsError::NegativeQty] [BagginsError::Other]
+    pub fn recompute_tax(
+        &mut self,
+        prior: &Calculation,
+        qty: BigDecimal,
+    ) -> BagginsResult<Calculation> {
+        if qty <= zero() {
+            return Err(BagginsError::NegativeQty(format!(
+                "qty must be over zero to recompute taxes {}",
+                qty
+            )));
+        }
+
+        self.tax_nets(
+            prior.without_discount_values.net.clone(),
+            prior.with_discount_values.net.clone(),
+            qty,
+            prior.with_discount_values.discount_value.clone(),
+            prior.with_discount_values.total_discount_percent.clone(),
+            &mut ComputeFlags::default(),
+        )
+    }
+
+    /// same as [Calculator::compute] but instead of failing when a guard is reached the
+    /// value is clamped, and the returned [ComputeFlags] reports which guards were activated.
+    ///
+    /// - a discount value over `max_discount_allowed` is reduced to `max_discount_allowed`, where
+    ///   [Calculator::compute] fails with [discount::DiscountError::OverMaxDiscount]
+    /// - a discount value over the line value is reduced so net becomes zero
+    /// - values rounded by the scale cap (see [DetailCalculator::set_scale_cap]) are reported
+    ///
+    /// The discount limits of [discount::DiscountComputer::check_discount] are not clamped, a
+    /// discount over them fails as in [Calculator::compute].
+    /// Could return [BagginsError::NegativeQty] [BagginsError::Discount] [BagginsError::Other]
+    pub fn compute_with_flags(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+        max_discount_allowed: Option<BigDecimal>,
+    ) -> BagginsResult<(Calculation, ComputeFlags)> {
+        self.check_input_scale(&[
+            ("unit_value", Some(&unit_value)),
+            ("qty", Some(&qty)),
+            ("max_discount_allowed", max_discount_allowed.as_ref()),
+        ])?;
+
+        if qty < zero() {
+            return Err(BagginsError::NegativeQty(format!("qty {}", qty)));
+        }
+
+        if unit_value < zero() {
+            return Err(BagginsError::Other(format!(
+                "negative unit_value {}",
+                unit_value
+            )));
+        }
+
+        let max_discount_allowed = max_discount_allowed.unwrap_or(hundred());
+
+        if max_discount_allowed < zero() {
+            return Err(BagginsError::Other(format!(
+                "negative max_discount_allowed {}",
+                max_discount_allowed
+            )));
+        }
+
+        let mut flags = ComputeFlags::default();
+        let gross = &unit_value * &qty + self.surcharge_value(&qty);
+        let mut discount_value = self.discount_handler.discount_value(&unit_value, &qty);
+
+        self.discount_handler
+            .check_discount(&discount_value, &unit_value, &qty)
+            .map_err(BagginsError::Discount)?;
+
+        if discount_value > max_discount_allowed {
+            discount_value = max_discount_allowed;
+            flags.discount_capped = true;
+        }
+
+        if discount_value > gross {
+            discount_value = gross.clone();
+            flags.net_clamped = true;
+        }
+
+
+        match self.calculate(
+            unit_value,
+            qty,
+            discount_value,
+            &mut flags,
+        ) {
+            Ok(calc) => Ok((calc, flags)),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// same as [DetailCalculator::compute_with_flags] but reports how much of the requested
+    /// discount was trimmed by `max_discount_allowed` or by the line value in a [DiscountClipInfo].
+    /// Could return [BagginsError::NegativeQty] [BagginsError::Discount] [BagginsError::Other]
+    pub fn compute_with_clip(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+        max_discount_allowed: Option<BigDecimal>,
+    ) -> BagginsResult<(Calculation, DiscountClipInfo)> {
+        let requested = self.discount_handler.discount_value(&unit_value, &qty);
+        let (calc, _) = self.compute_with_flags(unit_value, qty, max_discount_allowed)?;
+        let applied = calc.with_discount_values.discount_value.clone();
+
+        Ok((
+            calc,
+            DiscountClipInfo {
+                clipped: &requested - &applied,
+                requested,
+                applied,
+            },
+        ))
+    }
+
+    /// computes a [Calculation] applying the specified discount steps in order over the running
+    /// base of the line, instead of the registered discounts. So subtracting $10 and then 10% from
+    /// a line of $100 discounts $19, the 10% is taken from the remaining $90.
+    ///
+    /// `max_discount_allowed` caps the total discount, [None] allows a discount up to the line value.
+    /// Could return [BagginsError::NegativeQty] [BagginsError::Discount] [BagginsError::Other]
+    pub fn compute_sequenced(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+        steps: &[discount::DiscountStep],
+        max_discount_allowed: Option<BigDecimal>,
+    ) -> BagginsResult<Calculation> {
+        self.check_input_scale(&[
+            ("unit_value", Some(&unit_value)),
+            ("qty", Some(&qty)),
+            ("max_discount_allowed", max_discount_allowed.as_ref()),
+        ])?;
+
+        if qty < zero() {
+            return Err(BagginsError::NegativeQty(format!("qty {}", qty)));
+        }
+
+        if unit_value < zero() {
+            return Err(BagginsError::Other(format!(
+                "negative unit_value {}",
+                unit_value
+            )));
+        }
+
+        let gross = &unit_value * &qty + self.surcharge_value(&qty);
+        let mut base = gross.clone();
+
+        for step in steps {
+            let reduction = match step {
+                discount::DiscountStep::Amount(amount) => amount.clone(),
+                discount::DiscountStep::Percent(percent) => {
+                    if *percent > hundred() {
+                        return Err(BagginsError::Other(format!(
+                            "percentual discount step over 100% {}",
+                            percent
+                        )));
+                    }
+                    &base * percent / hundred()
+                }
+            };
+
+            if reduction < zero() {
+                return Err(BagginsError::Other(format!(
+                    "negative discount step {:?}",
+                    step
+                )));
+            }
+
+            base -= reduction;
+
+            if base < zero() {
+                return Err(BagginsError::Other(format!(
+                    "discount step {:?} leaves a negative net {}",
+                    step, base
+                )));
+            }
+        }
+
+        let discount_value = &gross - &base;
+
+        self.discount_handler
+            .check_discount(&discount_value, &unit_value, &qty)
+            .map_err(BagginsError::Discount)?;
+
+        if let Some(max) = max_discount_allowed {
+            if discount_value > max {
+                return Err(BagginsError::Other(format!(
+                    "discount_value {} over max_discount_allowed {}",
+                    discount_value, max
+                )));
+            }
+        }
+
+
+        self.calculate(
+            unit_value,
+            qty,
+            discount_value,
+            &mut ComputeFlags::default(),
+        )
+    }
+
+    /// computes a [Calculation] with the discount value returned by `discount_fn`, instead of the
+    /// registered discounts. `discount_fn` receives the gross line value and the quantity, so any
+    /// custom discount logic, like loyalty curves, can be plugged in.
+    ///
+    /// `max_discount_allowed` caps the discount, [None] allows a discount up to the line value.
+    /// Could return [BagginsError::NegativeQty] [BagginsError::Discount] [BagginsError::Other]
+    pub fn compute_with<F>(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+        discount_fn: F,
+        max_discount_allowed: Option<BigDecimal>,
+    ) -> BagginsResult<Calculation>
+    where
+        F: Fn(&BigDecimal, &BigDecimal) -> BigDecimal,
+    {
+        self.check_input_scale(&[
+            ("unit_value", Some(&unit_value)),
+            ("qty", Some(&qty)),
+            ("max_discount_allowed", max_discount_allowed.as_ref()),
+        ])?;
+
+        if qty < zero() {
+            return Err(BagginsError::NegativeQty(format!("qty {}", qty)));
+        }
+
+        if unit_value < zero() {
+            return Err(BagginsError::Other(format!(
+                "negative unit_value {}",
+                unit_value
+            )));
+        }
+
+        let gross = &unit_value * &qty + self.surcharge_value(&qty);
+        let discount_value = discount_fn(&gross, &qty);
+
+        if discount_value < zero() || discount_value > gross {
+            return Err(BagginsError::Other(format!(
+                "discount_value {} out of the line value {}",
+                discount_value, gross
+            )));
+        }
+
+        self.discount_handler
+            .check_discount(&discount_value, &unit_value, &qty)
+            .map_err(BagginsError::Discount)?;
+
+        if let Some(max) = max_discount_allowed {
+            if discount_value > max {
+                return Err(BagginsError::Other(format!(
+                    "discount_value {} over max_discount_allowed {}",
+                    discount_value, max
+                )));
+            }
+        }
+
+
+        self.calculate(
+            unit_value,
+            qty,
+            discount_value,
+            &mut ComputeFlags::default(),
+        )
+    }
+
+    /// same as [Calculator::compute] for a unit price that may be absent, like a free item or a
+    /// price on request. [None] is computed as a free item, with a zero unit value and no discount,
+    /// so only the surcharges, if any, are charged and taxed.
+    /// Could return [BagginsError::NegativeQty] [BagginsError::InvalidDecimalValue] [BagginsError::Other]
+    pub fn compute_optional(
+        &mut self,
+        unit_value: Option<BigDecimal>,
+        qty: BigDecimal,
+        max_discount_allowed: Option<BigDecimal>,
+    ) -> BagginsResult<Calculation> {
+        match unit_value {
+            Some(unit_value) => self.compute(unit_value, qty, max_discount_allowed),
+            None => self.compute_with(zero(), qty, |_, _| zero(), max_discount_allowed),
+        }
+    }
+
+    /// same as [Calculator::compute] but returns the values as integer minor units of the
+    /// specified scale, see [Calculation::to_minor].
+    /// Could return [BagginsError::NegativeQty] [BagginsError::InvalidDecimalValue] [BagginsError::Other]
+    pub fn compute_minor(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+        max_discount_allowed: Option<BigDecimal>,
+        scale: u32,
+    ) -> BagginsResult<CalculationMinor> {
+        self.compute(unit_value, qty, max_discount_allowed)?
+            .to_minor(scale)
+    }
+
+    /// computes a [Calculation] for each item of a bundle, given as (unit_value, qty), sharing a
+    /// single bundle discount allocated proportionally to the gross of each item. The last item
+    /// takes the allocation residue, so the allocated discounts sum exactly to `bundle_discount`.
+    /// A bundle without value allocates no discount to its items.
+    /// The registered discounts are not applied, see [DetailCalculator::compute_with].
+    /// Could return [BagginsError::NegativeQty] [BagginsError::Other]
+    pub fn compute_bundle(
+        &mut self,
+        items: &[(BigDecimal, BigDecimal)],
+        bundle_discount: BigDecimal,
+        max: Option<BigDecimal>,
+    ) -> BagginsResult<Vec<Calculation>> {
+        let total = items
+            .iter()
+            .fold(zero(), |total, (unit_value, qty)| total + unit_value * qty);
+
+        if bundle_discount < zero() || bundle_discount > total {
+            return Err(BagginsError::Other(format!(
+                "bundle_discount {} out of the bundle value {}",
+                bundle_discount, total
+            )));
+        }
+
+        let mut calcs = Vec::with_capacity(items.len());
+        let mut allocated = zero();
+
+        for (index, (unit_value, qty)) in items.iter().enumerate() {
+            let share = if index + 1 == items.len() {
+                &bundle_discount - &allocated
+            } else if total == zero() {
+                zero()
+            } else {
+                &bundle_discount * unit_value * qty / &total
+            };
+            allocated += &share;
+
+            calcs.push(self.compute_with(
+                unit_value.clone(),
+                qty.clone(),
+                |_, _| share.clone(),
+                max.clone(),
+            )?);
+        }
+
+        Ok(calcs)
+    }
+
+    /// same as [Calculator::compute] but also returns the [Warning]s found over the calculation,
+    /// which are not fatal. Useful as a soft validation of the registered discounts and taxes.
+    /// Could return [BagginsError::NegativeQty] [BagginsError::InvalidDecimalValue] [BagginsError::Other]
+    pub fn compute_checked(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+        max_discount_allowed: Option<BigDecimal>,
+    ) -> BagginsResult<(Calculation, Vec<Warning>)> {
+        let calc = self.compute(unit_value, qty, max_discount_allowed)?;
+        let mut warnings = Vec::new();
+
+        if calc.with_discount_values.tax > calc.with_discount_values.net {
+            warnings.push(Warning::TaxExceedsNet);
+        }
+
+        if &calc.with_discount_values.discount_value * BigDecimal::from(2)
+            > calc.without_discount_values.net
+        {
+            warnings.push(Warning::DiscountExceedsHalfNet);
+        }
+
+        let fractional_percent = self
+            .discount_handler
+            .discounts()
+            .iter()
+            .any(|(mode, value)| {
+                *mode == discount::Mode::Percentual && *value > zero() && *value < one()
+            });
+
+        if fractional_percent {
+            warnings.push(Warning::SuspiciousFractionalPercent);
+        }
+
+        Ok((calc, warnings))
+    }
+
+    /// returns the discount amount over the line that makes its brute the `competitor_final` price
+    /// reduced by `beat_by_pct` percent, for price matching. Zero is returned when the line already
+    /// beats the competitor without discounts. The registered discounts are not considered.
+    /// Could return [BagginsError::NegativeQty] [BagginsError::InvalidDecimalValue] [BagginsError::Tax]
+    /// and [BagginsError::Other] if not even a full discount beats the competitor
+    pub fn match_competitor(
+        &mut self,
+        my_unit: BigDecimal,
+        qty: BigDecimal,
+        competitor_final: BigDecimal,
+        beat_by_pct: BigDecimal,
+    ) -> BagginsResult<BigDecimal> {
+        if qty < zero() {
+            return Err(BagginsError::NegativeQty(format!("qty {}", qty)));
+        }
+
+        if competitor_final < zero() {
+            return Err(BagginsError::InvalidDecimalValue(format!(
+                "negative <competitor_final> {}",
+                competitor_final
+            )));
+        }
+
+        if beat_by_pct < zero() || beat_by_pct > hundred() {
+            return Err(BagginsError::InvalidDecimalValue(format!(
+                "<beat_by_pct> {} out of the range 0..=100",
+                beat_by_pct
+            )));
+        }
+
+        let target = competitor_final * (hundred() - beat_by_pct) / hundred();
+        let gross = &my_unit * &qty + self.surcharge_value(&qty);
+
+        let gross_tax = self
+            .gross_tax_handler
+            .tax_net(gross.clone(), qty.clone())
+            .map_err(BagginsError::Tax)?;
+        let net = self
+            .tax_handler
+            .un_tax(&target - gross_tax, qty)
+            .map_err(BagginsError::Tax)?;
+
+        if net < zero() {
+            return Err(BagginsError::Other(format!(
+                "no discount over the line {} reaches the target brute {}",
+                gross, target
+            )));
+        }
+
+        Ok((gross - net).max(zero()))
+    }
+
+    /// same as [Calculator::compute] but also returns the number of digits of each intermediate
+    /// value, in the order they are computed: gross net, discount value, total discount percent,
+    /// net, unit value with discounts, the taxes of the [tax::Stage::OverTaxable],
+    /// [tax::Stage::OverTax] and [tax::Stage::OverTaxIgnorable] stages, tax and brute.
+    /// The values are logged before the scale cap, the rounding, the epsilon snap and the percent
+    /// display scale are applied, so the log shows the growth they would hide. Meant to diagnose
+    /// the precision growth of divisions and chained percentages, see
+    /// [DetailCalculator::set_scale_cap].
+    /// Could return [BagginsError::NegativeQty] [BagginsError::Tax] [BagginsError::Discount] [BagginsError::Other]
+    pub fn compute_with_precision_log(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+        max_discount_allowed: Option<BigDecimal>,
+    ) -> BagginsResult<(Calculation, Vec<usize>)> {
+        self.precision_log = Some(Vec::new());
+        let calc = self.compute(unit_value, qty, max_discount_allowed);
+        let log = self.precision_log.take().unwrap_or_default();
+
+        Ok((calc?, log))
+    }
+
+    /// returns the landed cost per unit of an imported line, as in
+    /// `(net + duty + freight + tax) / qty` with the net and tax with discounts of the computed line.
+    /// Every registered tax is considered non recoverable, so the taxes the importer recovers, like
+    /// an input VAT credit, must not be registered in the calculator.
+    /// Could return [BagginsError::NegativeQty] [BagginsError::InvalidDecimalValue] [BagginsError::Other]
+    pub fn landed_cost(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+        extra: LandedCostInput,
+        max_discount_allowed: Option<BigDecimal>,
+    ) -> BagginsResult<BigDecimal> {
+        if qty == zero() {
+            return Err(BagginsError::Other(
+                "qty can't be zero to calculate the landed cost per unit".to_string(),
+            ));
+        }
+
+        for (name, value) in [("duty", &extra.duty), ("freight", &extra.freight)] {
+            if *value < zero() {
+                return Err(BagginsError::InvalidDecimalValue(format!(
+                    "negative {} {}",
+                    name, value
+                )));
+            }
+        }
+
+        let calc = self.compute(unit_value, qty.clone(), max_discount_allowed)?;
+
+        Ok((&calc.with_discount_values.net
+            + &extra.duty
+            + &extra.freight
+            + &calc.with_discount_values.tax)
+            / qty)
+    }
+
+    /// same as [Calculator::compute] but also returns the brute rounded with the [CashRounding]
+    /// for cash payments. The [Calculation] keeps the exact brute for card payments.
+    /// Could return [BagginsError::NegativeQty] [BagginsError::InvalidDecimalValue] [BagginsError::Other]
+    pub fn compute_cash(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+        max_discount_allowed: Option<BigDecimal>,
+        cash_rounding: &CashRounding,
+    ) -> BagginsResult<(Calculation, BigDecimal)> {
+        let calc = self.compute(unit_value, qty, max_discount_allowed)?;
+        let cash_brute = cash_rounding.round(&calc.with_discount_values.brute)?;
+
+        Ok((calc, cash_brute))
+    }
+
+    /// computes the line applying only the percentual discount of the specified tier instead
+    /// of the registered discounts, see [DetailCalculator::set_discount_tiers].
+    /// Could return [BagginsError::NegativeQty] [BagginsError::Other] if the tier is unknown
+    pub fn compute_for_tier(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+        tier: &str,
+        max_discount_allowed: Option<BigDecimal>,
+    ) -> BagginsResult<Calculation> {
+        let rate = match self.discount_tiers.rate(tier) {
+            Some(rate) => rate.clone(),
+            None => return Err(BagginsError::Other(format!("unknown tier {}", tier))),
+        };
+
+        self.compute_with(
+            unit_value,
+            qty,
+            |gross, _| gross * &rate / hundred(),
+            max_discount_allowed,
+        )
+    }
+
+    /// returns the smallest increase of the line net, in minor units of `scale`, that bumps the
+    /// brute rounded to `scale` by exactly one minor unit. Useful to know how much a price can
+    /// be raised before the tax inclusive price ticks up.
+    ///
+    /// Only the [tax::TaxBase::DiscountedNet] taxes follow the net, the rest of the brute is kept.
+    /// Could return [BagginsError::NegativeQty] [BagginsError::Tax], and [BagginsError::Other] if
+    /// no increase at `scale` bumps the rounded brute by exactly one minor unit
+    pub fn price_headroom(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+        scale: u32,
+    ) -> BagginsResult<BigDecimal> {
+        let scale = i64::from(scale);
+        let calc = self.compute(unit_value, qty.clone(), None)?;
+        let net = calc.with_discount_values.net;
+        let brute = calc.with_discount_values.brute;
+
+        let round = |value: &BigDecimal| value.with_scale_round(scale, RoundingMode::HalfUp);
+        let minor_unit = BigDecimal::new(1.into(), scale);
+
+        let tax = self
+            .tax_handler
+            .tax_net(net.clone(), qty.clone())
+            .map_err(BagginsError::Tax)?;
+        let untouched = &brute - &net - &tax;
+
+        let target = round(&brute) + &minor_unit;
+        let threshold = &target - &minor_unit / BigDecimal::from(2);
+
+        let needed = self
+            .tax_handler
+            .un_tax(threshold - &untouched, qty.clone())
+            .map_err(BagginsError::Tax)?;
+        let headroom = (needed - &net)
+            .with_scale_round(scale, RoundingMode::Ceiling)
+            .max(minor_unit);
+
+        let bumped_net = &net + &headroom;
+        let bumped_tax = self
+            .tax_handler
+            .tax_net(bumped_net.clone(), qty)
+            .map_err(BagginsError::Tax)?;
+
+        if round(&(bumped_net + bumped_tax + untouched)) != target {
+            return Err(BagginsError::Other(format!(
+                "no net increase at scale {} bumps the brute {} by one minor unit",
+                scale, brute
+            )));
+        }
+
+        Ok(headroom)
+    }
+
+    /// sets the first and last dates, both included, of a tax holiday, see
+    /// [tax::TaxComputer::set_tax_holiday] and [DetailCalculator::compute_on_date].
+    /// Could return [tax::TaxError::Other] if the first date is after the last one
+    pub fn set_tax_holiday(
+        &mut self,
+        range: (chrono::NaiveDate, chrono::NaiveDate),
+    ) -> Option<tax::TaxError<String>> {
+        if let Some(err) = self.tax_handler.set_tax_holiday(range) {
+            return Some(err);
+        }
+
+        self.gross_tax_handler.set_tax_holiday(range)
+    }
+
+    /// removes the tax holiday, if any
+    pub fn clear_tax_holiday(&mut self) {
+        self.tax_handler.clear_tax_holiday();
+        self.gross_tax_handler.clear_tax_holiday();
+    }
+
+    /// returns the first and last dates of the tax holiday, if any
+    pub fn tax_holiday(&self) -> Option<(chrono::NaiveDate, chrono::NaiveDate)> {
+        self.tax_handler.tax_holiday()
+    }
+
+    /// same as [Calculator::compute] for a line sold on the specified date. Within the tax
+    /// holiday no tax is calculated, otherwise the registered taxes apply as usual.
+    /// Could return [BagginsError::NegativeQty] [BagginsError::InvalidDecimalValue] [BagginsError::Other]
+    pub fn compute_on_date(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+        max_discount_allowed: Option<BigDecimal>,
+        date: chrono::NaiveDate,
+    ) -> BagginsResult<Calculation> {
+        if !self.tax_handler.is_tax_holiday(date) {
+            return self.compute(unit_value, qty, max_discount_allowed);
+        }
+
+        self.compute_without_taxes(unit_value, qty, max_discount_allowed)
+    }
+
+    /// same as [Calculator::compute] ignoring the registered taxes
+    fn compute_without_taxes(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+        max_discount_allowed: Option<BigDecimal>,
+    ) -> BagginsResult<Calculation> {
+        let tax_handler = std::mem::take(&mut self.tax_handler);
+        let gross_tax_handler = std::mem::take(&mut self.gross_tax_handler);
+
+        let calc = self.compute(unit_value, qty, max_discount_allowed);
+
+        self.tax_handler = tax_handler;
+        self.gross_tax_handler = gross_tax_handler;
+
+        calc
+    }
+
+    /// same as [Calculator::compute] but also returns the [Calculation] converted to a display
+    /// currency, with every monetary value multiplied by the exchange rate, see [Calculation::convert].
+    /// Could return [BagginsError::NegativeQty] [BagginsError::InvalidDecimalValue] if the rate
+    /// is not over zero, and [BagginsError::Other]
+    pub fn compute_dual_currency(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+        max_discount_allowed: Option<BigDecimal>,
+        rate: BigDecimal,
+    ) -> BagginsResult<(Calculation, Calculation)> {
+        if rate <= zero() {
+            return Err(BagginsError::InvalidDecimalValue(format!(
+                "exchange rate must be over zero {}",
+                rate
+            )));
+        }
+
+        let calc = self.compute(unit_value, qty, max_discount_allowed)?;
+        let converted = calc.convert(&rate);
+
+        Ok((calc, converted))
+    }
+
+    /// same as [Calculator::compute] but also computes an optional [ShippingCharge], whose taxes
+    /// are kept apart from the taxes of the line in the returned [ShippingCalculation].
+    /// Could return [BagginsError::NegativeQty] [BagginsError::Tax] [BagginsError::Other]
+    pub fn compute_with_shipping(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+        max_discount_allowed: Option<BigDecimal>,
+        shipping: Option<ShippingCharge>,
+    ) -> BagginsResult<(Calculation, Option<ShippingCalculation>)> {
+        let calc = self.compute(unit_value, qty, max_discount_allowed)?;
+
+        match shipping {
+            Some(mut shipping) => match shipping.taxes.tax(shipping.amount.clone(), one()) {
+                Ok(tax) => Ok((
+                    calc,
+                    Some(ShippingCalculation {
+                        brute: &shipping.amount + &tax,
+                        net: shipping.amount,
+                        tax,
+                    }),
+                )),
+                Err(err) => Err(BagginsError::Tax(err)),
+            },
+            None => Ok((calc, None)),
+        }
+    }
+
+    /// computes the lines of goods of an invoice with the registered discounts and taxes, and a
+    /// [ServiceCharge] taxed with its own taxes, kept apart in the returned [ServiceInvoice].
+    /// Could return [BagginsError::NegativeQty] [BagginsError::Tax] [BagginsError::Other]
+    pub fn compute_with_service(
+        &mut self,
+        lines: &[(BigDecimal, BigDecimal)],
+        mut service: ServiceCharge,
+        max_discount_allowed: Option<BigDecimal>,
+    ) -> BagginsResult<ServiceInvoice> {
+        let (lines, goods) = self.compute_goods(lines, max_discount_allowed)?;
+
+        let service_tax = service
+            .tax
+            .tax(service.amount.clone(), one())
+            .map_err(BagginsError::Tax)?;
+
+        Ok(ServiceInvoice {
+            brute: &goods.brute + &service.amount + &service_tax,
+            lines,
+            goods,
+            service_net: service.amount,
+            service_tax,
+        })
+    }
+
+    /// computes the lines of goods of an invoice with the registered discounts and taxes, and a
+    /// [Gratuity] taxed with the registered [tax::TaxBase::DiscountedNet] taxes when taxable,
+    /// kept apart in the returned [GratuityInvoice].
+    /// Could return [BagginsError::NegativeQty] [BagginsError::Tax] [BagginsError::Other]
+    pub fn compute_with_gratuity(
+        &mut self,
+        lines: &[(BigDecimal, BigDecimal)],
+        gratuity: Gratuity,
+        max_discount_allowed: Option<BigDecimal>,
+    ) -> BagginsResult<GratuityInvoice> {
+        let (lines, goods) = self.compute_goods(lines, max_discount_allowed)?;
+
+        let gratuity_tax = if gratuity.taxable {
+            self.tax_handler
+                .tax_net(gratuity.amount.clone(), one())
+                .map_err(BagginsError::Tax)?
+        } else {
+            zero()
+        };
+
+        Ok(GratuityInvoice {
+            brute: &goods.brute + &gratuity.amount + &gratuity_tax,
+            lines,
+            goods,
+            gratuity_net: gratuity.amount,
+            gratuity_tax,
+        })
+    }
+
+    /// computes the lines of goods, given as (unit_value, qty), sharing the same max discount,
+    /// like [DetailCalculator::compute_invoice]
+    fn compute_goods(
+        &mut self,
+        lines: &[(BigDecimal, BigDecimal)],
+        max_discount_allowed: Option<BigDecimal>,
+    ) -> BagginsResult<(Vec<Calculation>, InvoiceTotals)> {
+        let lines: Vec<LineInput> = lines
+            .iter()
+            .map(|(unit_value, qty)| LineInput {
+                max_discount_allowed: max_discount_allowed.clone(),
+                ..LineInput::new(unit_value.clone(), qty.clone())
+            })
+            .collect();
+
+        self.compute_invoice(&lines)
+    }
+
+    /// same as [Calculator::compute] with the unit value picked from the [PriceBreaks] by the
+    /// quantity, before the discounts and taxes are applied.
+    /// Could return [BagginsError::NegativeQty] [BagginsError::Other] if no break applies to the quantity
+    pub fn compute_with_breaks(
+        &mut self,
+        breaks: &PriceBreaks,
+        qty: BigDecimal,
+        max_discount_allowed: Option<BigDecimal>,
+    ) -> BagginsResult<Calculation> {
+        match breaks.unit_price(&qty) {
+            Some(unit_value) => self.compute(unit_value.clone(), qty, max_discount_allowed),
+            None => Err(BagginsError::Other(format!(
+                "no price break for the quantity {}",
+                qty
+            ))),
+        }
+    }
+
+    /// computes every line of a transaction and cumulates the [InvoiceTotals] in the same pass,
+    /// giving the same totals as [sum_calculations] over the returned lines.
+    /// Could return [BagginsError::NegativeQty] [BagginsError::Other]
+    pub fn compute_invoice(
+        &mut self,
+        lines: &[LineInput],
+    ) -> BagginsResult<(Vec<Calculation>, InvoiceTotals)> {
+        let mut calcs = Vec::with_capacity(lines.len());
+        let mut totals = InvoiceTotals::default();
+
+        for line in lines {
+            let calc = self.compute(
+                line.unit_value.clone(),
+                line.qty.clone(),
+                line.max_discount_allowed.clone(),
+            )?;
+            totals.add(&calc);
+            calcs.push(calc);
+        }
+
+        Ok((calcs, totals))
+    }
+
+    /// computes every line of a transaction with an order level percentual discount applied
+    /// before the line taxes, so each line is taxed over its net reduced by its share of the
+    /// order discount. The order discount is allocated with [discounts_for_exact_percent] over
+    /// the line nets after the registered discounts, rounded to the rounding scale (see
+    /// [DetailCalculator::set_rounding]), so the allocated discounts sum exactly to the
+    /// `order_pct` of the invoice.
+    /// A line whose registered discount plus its share of the order discount goes over its
+    /// `max_discount_allowed` fails instead of being clamped, so the allocations keep summing
+    /// to the `order_pct` of the invoice.
+    /// Could return [BagginsError::NegativeQty] [BagginsError::Discount] [BagginsError::Other]
+    pub fn compute_invoice_with_order_discount(
+        &mut self,
+        lines: &[LineInput],
+        order_pct: BigDecimal,
+    ) -> BagginsResult<(Vec<Calculation>, InvoiceTotals)> {
+        let mut line_discounts = Vec::with_capacity(lines.len());
+        let mut line_nets = Vec::with_capacity(lines.len());
+
+        for line in lines {
+            self.check_input_scale(&[
+                ("unit_value", Some(&line.unit_value)),
+                ("qty", Some(&line.qty)),
+                ("max_discount_allowed", line.max_discount_allowed.as_ref()),
+            ])?;
+
+            let (discount_value, _) = self
+                .discount_handler
+                .compute(
+                    line.unit_value.clone(),
+                    line.qty.clone(),
+                    line.max_discount_allowed.clone(),
+                )
+                .map_err(BagginsError::Discount)?;
+
+            let gross = &line.unit_value * &line.qty + self.surcharge_value(&line.qty);
+            line_nets.push(gross - &discount_value);
+            line_discounts.push(discount_value);
+        }
+
+        let allocations =
+            discounts_for_exact_percent(&line_nets, order_pct, self.rounding_scale as u32)?;
+
+        let mut calcs = Vec::with_capacity(lines.len());
+        let mut totals = InvoiceTotals::default();
+
+        for ((line, discount_value), allocation) in
+            lines.iter().zip(line_discounts).zip(allocations)
+        {
+            let discount_value = discount_value + allocation;
+
+            self.discount_handler
+                .check_discount(&discount_value, &line.unit_value, &line.qty)
+                .map_err(BagginsError::Discount)?;
+
+            if let Some(max) = &line.max_discount_allowed {
+                if &discount_value > max {
+                    return Err(BagginsError::Other(format!(
+                        "discount_value {} with the order discount over max_discount_allowed {}",
+                        discount_value, max
+                    )));
+                }
+            }
+
+            let calc = self.calculate(
+                line.unit_value.clone(),
+                line.qty.clone(),
+                discount_value,
+                &mut ComputeFlags::default(),
+            )?;
+            totals.add(&calc);
+            calcs.push(calc);
+        }
+
+        Ok((calcs, totals))
+    }
+
+    /// computes every line of a transaction and packages the calculations, the [tax_summary] and
+    /// the grand totals in a [Receipt], the shape a checkout needs to print or show it.
+    /// Zero rated and exempt lines are computed without the registered taxes.
+    /// Could return [BagginsError::NegativeQty] [BagginsError::Other]
+    pub fn build_receipt(&mut self, lines: &[LineInput]) -> BagginsResult<Receipt> {
+        let mut computed = Vec::with_capacity(lines.len());
+        let mut totals = InvoiceTotals::default();
+
+        for line in lines {
+            let calc = if line.tax_status == LineTaxStatus::Standard {
+                self.compute(
+                    line.unit_value.clone(),
+                    line.qty.clone(),
+                    line.max_discount_allowed.clone(),
+                )?
+            } else {
+                self.compute_without_taxes(
+                    line.unit_value.clone(),
+                    line.qty.clone(),
+                    line.max_discount_allowed.clone(),
+                )?
+            };
+            totals.add(&calc);
+            computed.push((line.tax_status, calc));
+        }
+
+        Ok(Receipt {
+            tax_summary: tax_summary(&computed),
+            lines: computed.into_iter().map(|(_, calc)| calc).collect(),
+            totals,
+        })
+    }
+
+    /// computes every line of a transaction without stopping at the first failure, returning the
+    /// calculation of each line, [None] for the failed ones, and the errors with the index of
+    /// their line. Useful to report every invalid line at once.
+    pub fn compute_batch_collect(
+        &mut self,
+        lines: &[LineInput],
+    ) -> (Vec<Option<Calculation>>, Vec<LineError>) {
+        let mut calcs = Vec::with_capacity(lines.len());
+        let mut errors = Vec::new();
+
+        for (index, line) in lines.iter().enumerate() {
+            match self.compute(
+                line.unit_value.clone(),
+                line.qty.clone(),
+                line.max_discount_allowed.clone(),
+            ) {
+                Ok(calc) => calcs.push(Some(calc)),
+                Err(err) => {
+                    calcs.push(None);
+                    errors.push((index, err));
+                }
+            }
+        }
+
+        (calcs, errors)
+    }
+
+    /// returns true if rounding the computed values to `scale` is not borderline. A value is
+    /// borderline when, rounded at `scale + 2`, it is at half a unit or more from its value
+    /// rounded at `scale`, so it sits on a tie or double rounding would give a different result.
+    /// Useful to flag lines prone to rounding disputes.
+    /// Could return [BagginsError::NegativeQty] [BagginsError::Other]
+    pub fn is_rounding_stable(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+        scale: u32,
+    ) -> BagginsResult<bool> {
+        let scale = i64::from(scale);
+        let calc = self.compute(unit_value, qty, None)?;
+        let half_unit = BigDecimal::new(5.into(), scale + 1);
+
+        let values = [
+            &calc.without_discount_values.net,
+            &calc.without_discount_values.tax,
+            &calc.without_discount_values.brute,
+            &calc.with_discount_values.net,
+            &calc.with_discount_values.tax,
+            &calc.with_discount_values.brute,
+            &calc.with_discount_values.discount_value,
+        ];
+
+        Ok(values
+            .iter()
+            .all(|value| (value.round(scale + 2) - value.round(scale)).abs() < half_unit))
+    }
+
+    /// same as [Calculator::compute] but also returns the gross profit of the line, the discounted
+    /// net minus `unit_cost * qty`. The profit is negative when the line is sold under cost.
+    /// Could return [BagginsError::NegativeQty] [BagginsError::InvalidDecimalValue] [BagginsError::Other]
+    pub fn compute_with_profit(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+        unit_cost: BigDecimal,
+        max_discount_allowed: Option<BigDecimal>,
+    ) -> BagginsResult<(Calculation, BigDecimal)> {
+        if unit_cost < zero() {
+            return Err(BagginsError::InvalidDecimalValue(format!(
+                "negative <unit_cost> {}",
+                unit_cost
+            )));
+        }
+
+        let line_cost = &unit_cost * &qty;
+        let calc = self.compute(unit_value, qty, max_discount_allowed)?;
+        let profit = &calc.with_discount_values.net - line_cost;
+
+        Ok((calc, profit))
+    }
+
+    /// same as [Calculator::compute] but trims the registered discounts so the discounted net
+    /// stays at or over `unit_cost * qty` plus the `min_margin_pct` percent over that cost.
+    /// Also returns true when the discount was trimmed to protect the margin. A line already
+    /// under the minimum margin before discounts gets no discount.
+    /// Could return [BagginsError::NegativeQty] [BagginsError::InvalidDecimalValue] [BagginsError::Other]
+    pub fn compute_margin_protected(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+        unit_cost: BigDecimal,
+        min_margin_pct: BigDecimal,
+        max_discount_allowed: Option<BigDecimal>,
+    ) -> BagginsResult<(Calculation, bool)> {
+        if unit_cost < zero() {
+            return Err(BagginsError::InvalidDecimalValue(format!(
+                "negative <unit_cost> {}",
+                unit_cost
+            )));
+        }
+
+        if min_margin_pct < zero() {
+            return Err(BagginsError::InvalidDecimalValue(format!(
+                "negative <min_margin_pct> {}",
+                min_margin_pct
+            )));
+        }
+
+        let min_net = &unit_cost * &qty * (hundred() + &min_margin_pct) / hundred();
+        let gross = &unit_value * &qty + self.surcharge_value(&qty);
+        let allowed = (gross - min_net).max(zero());
+        let requested = self.discount_handler.discount_value(&unit_value, &qty);
+
+        let capped = requested > allowed;
+        let discount_value = requested.min(allowed);
+
+        let calc = self.compute_with(
+            unit_value,
+            qty,
+            |_, _| discount_value.clone(),
+            max_discount_allowed,
+        )?;
+
+        Ok((calc, capped))
+    }
+
+    /// returns the registered taxes of a line, taxing the `net` with the [tax::TaxBase::DiscountedNet]
+    /// taxes and the `gross` with the [tax::TaxBase::Gross] taxes
+    fn taxes_by_base(
+        &self,
+        net: &BigDecimal,
+        gross: &BigDecimal,
+        qty: &BigDecimal,
+    ) -> Result<BigDecimal, tax::TaxError<String>> {
+        match self.tax_handler.tax_net(net.clone(), qty.clone()) {
+            Ok(net_tax) => match self.gross_tax_handler.tax_net(gross.clone(), qty.clone()) {
+                Ok(gross_tax) => Ok(net_tax + gross_tax),
+                Err(err) => Err(err),
+            },
+            Err(err) => Err(err),
+        }
+    }
+
+    /// returns the registered taxes of a line, like [DetailCalculator::taxes_by_base], and the same
+    /// taxes by [tax::Stage] before the tax caps, walking the stages once
+    fn tax_breakdown_by_base(
+        &self,
+        net: &BigDecimal,
+        gross: &BigDecimal,
+        qty: &BigDecimal,
+    ) -> BagginsResult<(BigDecimal, tax::TaxBreakdown)> {
+        let on_net = self.tax_handler.tax_net_by_stage(net.clone(), qty.clone());
+        let on_gross = self
+            .gross_tax_handler
+            .tax_net_by_stage(gross.clone(), qty.clone());
+
+        match (on_net, on_gross) {
+            (Ok(on_net), Ok(on_gross)) => Ok((
+                self.tax_handler.capped_total(&on_net)
+                    + self.gross_tax_handler.capped_total(&on_gross),
+                tax::TaxBreakdown {
+                    over_taxable: on_net.over_taxable + on_gross.over_taxable,
+                    over_tax: on_net.over_tax + on_gross.over_tax,
+                    over_tax_ignorable: on_net.over_tax_ignorable + on_gross.over_tax_ignorable,
+                },
+            )),
+            (Err(err), _) | (_, Err(err)) => Err(BagginsError::Tax(err)),
+        }
+    }
+
+    fn cap(&self, value: BigDecimal, flags: &mut ComputeFlags) -> BigDecimal {
+        match self.scale_cap {
+            Some(scale) => {
+                let capped = value.round(scale);
+
+                if capped != value {
+                    flags.scale_capped = true;
+                }
+
+                capped
+            }
+            None => value,
+        }
+    }
+}
+
+impl Default for DetailCalculator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Calculator for DetailCalculator {
+    fn add_discount(
+        &mut self,
+        discount: BigDecimal,
+        discount_mode: discount::Mode,
     ) -> Option<discount::DiscountError<String>> {
         self.discount_handler.add_discount(discount, discount_mode)
     }
@@ -523,22 +3630,37 @@ impl Calculator for DetailCalculator {
         brute: BigDecimal,
         qty: BigDecimal,
         max_discount_allowed: Option<BigDecimal>,
-    ) -> Result<Calculation, BagginsError<String>> {
+    ) -> BagginsResult<Calculation> {
+        self.check_input_scale(&[
+            ("brute", Some(&brute)),
+            ("qty", Some(&qty)),
+            ("max_discount_allowed", max_discount_allowed.as_ref()),
+        ])?;
+
+        if self.exact_rational {
+            let unit_value = self.rational_unit_value_from_brute(&brute, &qty)?;
+            return self.compute_unchecked(unit_value, qty, max_discount_allowed);
+        }
+
         match self.tax_handler.un_tax(brute.clone(), qty.clone()) {
             Ok(un_taxed) => match self
                 .discount_handler
-                .un_discount(un_taxed.clone(), qty.clone())
+                .un_discount(&un_taxed - self.surcharge_value(&qty), qty.clone())
             {
-                Ok(un_discounted) => self.compute(un_discounted.0, qty, max_discount_allowed),
-                Err(err) => Err(BagginsError::Other(format!(
-                    "undiscounting un_taxed {} {}",
-                    un_taxed, err
-                ))),
+                Ok(un_discounted) => {
+                    if qty == zero() {
+                        return Err(BagginsError::Other(
+                            "qty can't be zero to recover the unit value from the brute"
+                                .to_string(),
+                        ));
+                    }
+
+                    // un_discount recovers the value of the whole line, not the unit value
+                    self.compute_unchecked(un_discounted.0 / &qty, qty, max_discount_allowed)
+                }
+                Err(err) => Err(BagginsError::Discount(err)),
             },
-            Err(err) => Err(BagginsError::Other(format!(
-                "untaxing brute {} {}",
-                brute, err
-            ))),
+            Err(err) => Err(BagginsError::Tax(err)),
         }
     }
 
@@ -547,7 +3669,7 @@ impl Calculator for DetailCalculator {
         brute: f64,
         qty: f64,
         max_discount_allowed: Option<f64>,
-    ) -> Result<Calculation, BagginsError<String>> {
+    ) -> BagginsResult<Calculation> {
         
         let max_discount_allowed: Option<BigDecimal> = BigDecimal::from_f64(max_discount_allowed.unwrap_or(100.0f64));
 
@@ -563,7 +3685,7 @@ impl Calculator for DetailCalculator {
         brute: S,
         qty: S,
         max_discount_allowed: Option<S>,
-    ) -> Result<Calculation, BagginsError<String>> {
+    ) -> BagginsResult<Calculation> {
         let brute = brute.into();
         let qty = qty.into();
 
@@ -602,7 +3724,7 @@ impl Calculator for DetailCalculator {
         unit_value: S,
         qty: S,
         max_discount_allowed: Option<S>,
-    ) -> Result<Calculation, BagginsError<String>> {
+    ) -> BagginsResult<Calculation> {
         let unit_value = unit_value.into();
         let qty = qty.into();
 
@@ -641,7 +3763,7 @@ impl Calculator for DetailCalculator {
         unit_value: f64,
         qty: f64,
         max_discount_allowed: Option<f64>,
-    ) -> Result<Calculation, BagginsError<String>> {
+    ) -> BagginsResult<Calculation> {
         let max_discount_allowed: Option<BigDecimal> = BigDecimal::from_f64(max_discount_allowed.unwrap_or(100.0f64));
 
         self.compute(
@@ -656,59 +3778,14 @@ impl Calculator for DetailCalculator {
         unit_value: BigDecimal,
         qty: BigDecimal,
         max_discount_allowed: Option<BigDecimal>,
-    ) -> Result<Calculation, BagginsError<String>> {
-        match self
-            .discount_handler
-            .compute(unit_value.clone(), qty.clone(), max_discount_allowed)
-        {
-            Ok(discount) => {
-                let net = &unit_value * &qty - &discount.0;
-                let discounted_uv = &net / &qty;
-
-                match self.tax_handler.tax(discounted_uv.clone(), qty.clone()) {
-                    Ok(tax) => match self.tax_handler.tax(unit_value.clone(), qty.clone()) {
-                        Ok(tax_without_discount) => {
-                            let net_without_discount = &unit_value * &qty;
-                            let brute_without_discount =
-                                &net_without_discount + &tax_without_discount;
-                            let brute = &net + &tax;
-
-                            let calc = Calculation {
-                                without_discount_values: CalculationWithoutDiscount {
-                                    brute: brute_without_discount.clone(),
-                                    unit_value: &net_without_discount / &qty,
-                                    net: net_without_discount,
-                                    tax: tax_without_discount,
-                                },
-                                with_discount_values: CalculationWithDiscount {
-                                    discount_brute_value: &brute - &brute_without_discount,
-                                    brute,
-                                    unit_value: &net / &qty,
-                                    net,
-                                    tax,
-                                    discount_value: discount.0,
-                                    total_discount_percent: discount.1,
-                                },
-                            };
-
-                            Ok(calc)
-                        }
-                        Err(err) => Err(BagginsError::Other(format!(
-                            "calculating taxes {}",
-                            err
-                        ))),
-                    },
-                    Err(err) => Err(BagginsError::Other(format!(
-                        "calculating taxes {}",
-                        err
-                    ))),
-                }
-            }
-            Err(err) => Err(BagginsError::Other(format!(
-                "calculating discount {}",
-                err
-            ))),
-        }
+    ) -> BagginsResult<Calculation> {
+        self.check_input_scale(&[
+            ("unit_value", Some(&unit_value)),
+            ("qty", Some(&qty)),
+            ("max_discount_allowed", max_discount_allowed.as_ref()),
+        ])?;
+
+        self.compute_unchecked(unit_value, qty, max_discount_allowed)
     }
 
     fn line_tax(
@@ -0,0 +1,28 @@
+//! prelude
+//!
+//! `prelude` module re-exports the traits and types needed for the common use of the crate,
+//! so they can be imported at once.
+//!
+//! # Example
+//!
+//! ```
+//! use baggins::prelude::*;
+//! use bigdecimal::BigDecimal;
+//! use std::str::FromStr;
+//!
+//! fn line() -> BagginsResult<Calculation> {
+//!     let mut c = DetailCalculator::new();
+//!
+//!     c.try_add_discount_from_str("10.0", DiscountMode::Percentual)?;
+//!     c.try_add_tax_from_str("18.0", Stage::OverTaxable, TaxMode::Percentual)?;
+//!
+//!     c.compute_from_str("100.0", "2.0", None)
+//! }
+//!
+//! let calc = line().unwrap();
+//! assert_eq!(calc.with_discount_values().brute, BigDecimal::from_str("212.4").unwrap());
+//! ```
+//!
+pub use crate::discount::{DiscountComputer, Discounter, Mode as DiscountMode};
+pub use crate::tax::{Mode as TaxMode, Stage, Stager, TaxComputer, Taxer};
+pub use crate::{BagginsError, BagginsResult, Calculation, Calculator, DetailCalculator};
@@ -5,8 +5,10 @@
 use std::{fmt, str::FromStr};
 
 use bigdecimal::{BigDecimal, FromPrimitive};
+use chrono::NaiveDate;
+use serde::Serialize;
 
-#[derive(PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 /// The tax type
 /// A tax type could be percentual or a fixed amount, and the fixed amount tax
 /// could be by each unit or by everything being sold
@@ -37,6 +39,13 @@ pub enum Mode {
     /// If you sell apples and there is a tax which is chaged for every apple that
     /// is an amount unit tax
     AmountUnit,
+
+    /// Tax calculated as a percent value over the [Stage::OverTaxable] taxes of the line
+    /// only, not over the taxable. Only allowed for the [Stage::OverTax] stage.
+    ///
+    /// If there is a surcharge of 2% of the VAT charged for the apples, then that is
+    /// a percent of tax tax
+    PercentOfTax,
 }
 
 impl Mode {
@@ -53,11 +62,15 @@ impl Mode {
             return Some(Self::AmountUnit);
         }
 
+        if r#type == 3 {
+            return Some(Self::PercentOfTax);
+        }
+
         None
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 /// Possible errors of the tax processing
 pub enum TaxError<S: Into<String>> {
     /// a negative value is not allowed, How do you tax 10% of -10?, or the -10% of 10
@@ -105,7 +118,9 @@ impl<S: Into<String> + Clone> fmt::Display for TaxError<S> {
     }
 }
 
-#[derive(Debug, PartialEq)]
+impl<S: Into<String> + Clone + fmt::Debug> std::error::Error for TaxError<S> {}
+
+#[derive(Debug, PartialEq, Clone)]
 pub enum Stage {
     /// Taxes that are calculated directly on the value of the products
     OverTaxable,
@@ -119,6 +134,17 @@ pub enum Stage {
     OverTaxIgnorable,
 }
 
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+/// The value over which a tax is calculated in a line with discounts
+pub enum TaxBase {
+    /// the tax is calculated over the line value before discounts
+    Gross,
+
+    /// the tax is calculated over the line value after discounts. This is the default
+    #[default]
+    DiscountedNet,
+}
+
 /// Represents when a tax should be calculated.
 /// There are 3 stages in which a tax could be calculated
 ///
@@ -235,9 +261,25 @@ pub trait Stager {
     /// returns the cumulative value of the amount unit taxes of the stage
     /// could return [`BigDecimal::Zero`]
     fn amount_by_qty(&self) -> BigDecimal;
+
+    /// returns the percentual taxes of the stage applied up to a maximum assessable value,
+    /// as pairs of percent and assessable cap. They are not included in [Stager::percent]
+    fn capped_percentuals(&self) -> Vec<(BigDecimal, BigDecimal)> {
+        Vec::new()
+    }
+
+    /// returns the cumulated [Mode::PercentOfTax] taxes
+    fn percent_of_tax(&self) -> BigDecimal {
+        crate::zero()
+    }
+
+    /// returns the scale the taxes of the stage are rounded to, if any
+    fn scale(&self) -> Option<u32> {
+        None
+    }
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq)]
 /// Able to store tax data belonging to a given stage and make calculations with them
 ///
 /// # Example
@@ -278,6 +320,9 @@ pub struct TaxStage {
     percentuals: BigDecimal,
     amount_line: BigDecimal,
     amount_unit: BigDecimal,
+    capped_percentuals: Vec<(BigDecimal, BigDecimal)>,
+    percent_of_tax: BigDecimal,
+    scale: Option<u32>,
 }
 
 impl TaxStage {
@@ -295,8 +340,182 @@ impl TaxStage {
             percentuals: crate::zero(),
             amount_line: crate::zero(),
             amount_unit: crate::zero(),
+            capped_percentuals: Vec::new(),
+            percent_of_tax: crate::zero(),
+            scale: None,
         }
     }
+
+    /// returns a TaxStage with all the specified taxes accumulated by their [Mode].
+    /// Could return [TaxError::NegativeValue] for the first invalid tax found
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use baggins::tax::{Mode, Stager, TaxStage};
+    /// use bigdecimal::BigDecimal;
+    /// use std::str::FromStr;
+    ///
+    /// let stage = TaxStage::from_taxes(&[
+    ///     (Mode::Percentual, BigDecimal::from_str("18").unwrap()),
+    ///     (Mode::AmountUnit, BigDecimal::from_str("0.5").unwrap()),
+    /// ])
+    /// .unwrap();
+    ///
+    /// assert_eq!(stage.percent(), BigDecimal::from_str("18").unwrap());
+    /// ```
+    pub fn from_taxes(taxes: &[(Mode, BigDecimal)]) -> Result<TaxStage, TaxError<String>> {
+        let mut stage = Self::new();
+
+        for (mode, tax) in taxes {
+            let err = match mode {
+                Mode::Percentual => stage.add_percentual(tax.clone()),
+                Mode::AmountLine => stage.add_amount_by_line(tax.clone()),
+                Mode::AmountUnit => stage.add_amount_by_qty(tax.clone()),
+                Mode::PercentOfTax => stage.add_percent_of_tax(tax.clone()),
+            };
+
+            if let Some(err) = err {
+                return Err(err);
+            }
+        }
+
+        Ok(stage)
+    }
+
+    /// adds a percentual tax applied only up to the specified maximum assessable value of the
+    /// line, as a luxury tax on the first $10,000. The line value over the cap is not taxed by it.
+    /// Could return [TaxError::NegativeValue] boxed in an [Option]
+    pub fn add_percentual_with_assessable_cap(
+        &mut self,
+        percent: BigDecimal,
+        assessable_cap: BigDecimal,
+    ) -> Option<TaxError<String>> {
+        if percent < crate::zero() {
+            return Some(TaxError::NegativeValue(format!(
+                "negative value adding percentual tax {}",
+                percent
+            )));
+        }
+
+        if assessable_cap < crate::zero() {
+            return Some(TaxError::NegativeValue(format!(
+                "negative assessable cap {}",
+                assessable_cap
+            )));
+        }
+
+        self.capped_percentuals.push((percent, assessable_cap));
+        None
+    }
+
+    /// sets the scale the taxes of the stage are rounded to, halves to even.
+    /// `None` keeps the full precision
+    pub fn set_scale(&mut self, scale: Option<u32>) {
+        self.scale = scale;
+    }
+
+    /// returns the tax rounded to the scale of the stage, if any
+    fn round(&self, tax: BigDecimal) -> BigDecimal {
+        round_to_scale(tax, self.scale)
+    }
+
+    /// adds a [Mode::PercentOfTax] tax, calculated by [TaxComputer] over the over taxable taxes of
+    /// the line when the stage is its [Stage::OverTax] stage.
+    /// Could return [TaxError::NegativeValue] boxed in an [Option]
+    pub fn add_percent_of_tax(&mut self, percent: BigDecimal) -> Option<TaxError<String>> {
+        if percent < crate::zero() {
+            return Some(TaxError::NegativeValue(format!(
+                "negative value adding percent of tax {}",
+                percent
+            )));
+        }
+
+        self.percent_of_tax = &self.percent_of_tax + percent;
+        None
+    }
+}
+
+/// returns the tax rounded to the specified scale, if any, halves to even like the other
+/// roundings of the crate
+fn round_to_scale(tax: BigDecimal, scale: Option<u32>) -> BigDecimal {
+    match scale {
+        Some(scale) => tax.round(i64::from(scale)),
+        None => tax,
+    }
+}
+
+/// returns the taxes of the percentuals applied up to their assessable cap over the line value
+fn capped_tax(line: &BigDecimal, capped_percentuals: &[(BigDecimal, BigDecimal)]) -> BigDecimal {
+    capped_percentuals
+        .iter()
+        .fold(crate::zero(), |tax, (percent, cap)| {
+            tax + line.min(cap) * percent / crate::hundred()
+        })
+}
+
+impl TaxStage {
+    /// calculates the stage taxes from the line net instead of the unit value, before rounding
+    /// them to the stage scale.
+    /// Equivalent to [Stager::tax] with `net / qty` as taxable, but it doesn't divide,
+    /// so no precision is lost when the net can't be evenly split among the units.
+    fn tax_net(&self, net: &BigDecimal, qty: &BigDecimal) -> Result<BigDecimal, TaxError<String>> {
+        if *net < crate::zero() {
+            return Err(TaxError::NegativeValue(format!(
+                "negative net at calculating registered taxes {}",
+                net
+            )));
+        }
+
+        if *qty < crate::zero() {
+            return Err(TaxError::NegativeValue(format!(
+                "negative quantity at calculating registered taxes {}",
+                qty
+            )));
+        }
+
+        if *net == crate::zero() {
+            return Ok(crate::zero());
+        }
+
+        Ok(net * &self.percentuals / crate::hundred()
+            + &self.amount_unit * qty
+            + &self.amount_line
+            + capped_tax(net, &self.capped_percentuals))
+    }
+
+    /// calculates the stage taxes like [Stager::tax], before rounding them to the stage scale
+    fn unrounded_tax(
+        &self,
+        taxable: BigDecimal,
+        qty: BigDecimal,
+    ) -> Result<BigDecimal, TaxError<String>> {
+        if taxable < crate::zero() {
+            return Err(TaxError::NegativeValue(format!(
+                "negative taxable at calculating registered taxes{}",
+                taxable
+            )));
+        }
+
+        if qty < crate::zero() {
+            return Err(TaxError::NegativeValue(format!(
+                "negative quantity at calculating registered taxes {}",
+                qty
+            )));
+        }
+
+        if taxable == crate::zero() {
+            return Ok(crate::zero());
+        }
+
+        let capped = capped_tax(&(&taxable * &qty), &self.capped_percentuals);
+
+        Ok(
+            (&taxable * &self.percentuals / crate::hundred() + &self.amount_unit) * &qty
+                + &self.amount_line
+                + capped,
+        )
+    }
 }
 
 impl Default for TaxStage {
@@ -357,30 +576,7 @@ impl Stager for TaxStage {
         taxable: BigDecimal,
         qty: BigDecimal,
     ) -> Result<BigDecimal, TaxError<String>> {
-        if taxable < crate::zero() {
-            return Err(TaxError::NegativeValue(format!(
-                "negative taxable at calculating registered taxes{}",
-                taxable
-            )));
-        }
-
-        if qty < crate::zero() {
-            return Err(TaxError::NegativeValue(format!(
-                "negative quantity at calculating registered taxes {}",
-                qty
-            )));
-        }
-
-        if taxable == crate::zero() {
-            return Ok(crate::zero());
-        }
-
-        // println!("{} {}", taxable, self);
-
-        Ok(
-            (&taxable * &self.percentuals / crate::hundred() + &self.amount_unit) * &qty
-                + &self.amount_line,
-        )
+        self.unrounded_tax(taxable, qty).map(|tax| self.round(tax))
     }
 
     fn add_percentual_from_f64(&mut self, percent: f64) -> Option<TaxError<String>> {
@@ -414,6 +610,18 @@ impl Stager for TaxStage {
         self.amount_unit.clone()
     }
 
+    fn capped_percentuals(&self) -> Vec<(BigDecimal, BigDecimal)> {
+        self.capped_percentuals.clone()
+    }
+
+    fn percent_of_tax(&self) -> BigDecimal {
+        self.percent_of_tax.clone()
+    }
+
+    fn scale(&self) -> Option<u32> {
+        self.scale
+    }
+
     fn tax_from_str<S: Into<String>>(
         &mut self,
         taxable: S,
@@ -433,8 +641,9 @@ impl Stager for TaxStage {
 
     fn add_percentual_from_str<S: Into<String>>(&mut self, percent: S) -> Option<TaxError<String>> {
         let percent = percent.into();
+        let percent = crate::strip_percent(&percent).unwrap_or(&percent);
 
-        match BigDecimal::from_str(&percent) {
+        match BigDecimal::from_str(percent) {
             Ok(percent) => self.add_percentual(percent),
             Err(err) => Some(TaxError::InvalidDecimal(format!(
                 "invalid percent value adding percentual from str {}",
@@ -509,6 +718,48 @@ pub trait Taxer {
         tax_type: Mode,
     ) -> Option<TaxError<String>>;
 
+    /// adds a [BigDecimal] value of the specified [Mode] to the specified [Stage]
+    /// Same as [Taxer::add_tax] but returns a [Result] so it can be used with `?`
+    fn try_add_tax(
+        &mut self,
+        tax: BigDecimal,
+        stage: Stage,
+        tax_type: Mode,
+    ) -> Result<(), TaxError<String>> {
+        match self.add_tax(tax, stage, tax_type) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// adds a [f64] value of the specified [Mode] to the specified [Stage]
+    /// Same as [Taxer::add_tax_from_f64] but returns a [Result] so it can be used with `?`
+    fn try_add_tax_from_f64(
+        &mut self,
+        tax: f64,
+        stage: Stage,
+        tax_type: Mode,
+    ) -> Result<(), TaxError<String>> {
+        match self.add_tax_from_f64(tax, stage, tax_type) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// adds a [Into<String>] value of the specified [Mode] to the specified [Stage]
+    /// Same as [Taxer::add_tax_from_str] but returns a [Result] so it can be used with `?`
+    fn try_add_tax_from_str<S: Into<String>>(
+        &mut self,
+        tax: S,
+        stage: Stage,
+        tax_type: Mode,
+    ) -> Result<(), TaxError<String>> {
+        match self.add_tax_from_str(tax, stage, tax_type) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
     /// returns the calculated cummulated tax value for the specified [BigDecimal] unit_value.
     /// Could returns [TaxError::NegativeValue]
     fn tax(
@@ -585,6 +836,7 @@ pub trait Taxer {
             Mode::Percentual => Ok(&taxable * &qty * &value / crate::hundred()),
             Mode::AmountLine => Ok(&qty * &value),
             Mode::AmountUnit => Ok(value),
+            Mode::PercentOfTax => Ok(&taxable * &value / crate::hundred()),
         }
     }
 
@@ -631,15 +883,122 @@ pub trait Taxer {
     }
 }
 
+/// returns a [TaxComputer] with the taxes of two layered jurisdictions, like national and
+/// municipal taxes, merging each stage with its counterpart.
+///
+/// The merged stages keep the three stage semantics: the over tax of any jurisdiction is
+/// calculated over the net plus the over taxable taxes of both jurisdictions, and the ignorable
+/// taxes of both are calculated over the net only. When both jurisdictions set a scale for the
+/// same stage, the scale of the `national` one is kept. Dated taxes, line caps and tax holidays
+/// are not layered.
+pub fn layer(national: &TaxComputer, municipal: &TaxComputer) -> TaxComputer {
+    TaxComputer {
+        over_taxable: layer_stage(&national.over_taxable, &municipal.over_taxable),
+        over_tax: layer_stage(&national.over_tax, &municipal.over_tax),
+        over_tax_ignorable: layer_stage(
+            &national.over_tax_ignorable,
+            &municipal.over_tax_ignorable,
+        ),
+        dated: Vec::new(),
+        registered: Vec::new(),
+        reject_duplicate_taxes: false,
+        max_tax_per_line: None,
+        tax_holiday: None,
+        bases: TaxBases::default(),
+    }
+}
+
+fn layer_stage(first: &TaxStage, second: &TaxStage) -> TaxStage {
+    TaxStage {
+        percentuals: &first.percentuals + &second.percentuals,
+        amount_line: &first.amount_line + &second.amount_line,
+        amount_unit: &first.amount_unit + &second.amount_unit,
+        capped_percentuals: [
+            first.capped_percentuals.as_slice(),
+            second.capped_percentuals.as_slice(),
+        ]
+        .concat(),
+        percent_of_tax: &first.percent_of_tax + &second.percent_of_tax,
+        scale: first.scale.or(second.scale),
+    }
+}
+
+/// returns the difference between the taxes of the `to` and `from` configurations for the same
+/// taxable unit value and quantity, as in `to.tax(net, qty) - from.tax(net, qty)`.
+/// Useful to reconcile the tax of an item moved between jurisdictions.
+/// Could returns [TaxError::NegativeValue]
+pub fn tax_delta(
+    net: BigDecimal,
+    qty: BigDecimal,
+    from: &mut TaxComputer,
+    to: &mut TaxComputer,
+) -> Result<BigDecimal, TaxError<String>> {
+    match from.tax(net.clone(), qty.clone()) {
+        Ok(from_tax) => match to.tax(net, qty) {
+            Ok(to_tax) => Ok(to_tax - from_tax),
+            Err(err) => Err(err),
+        },
+        Err(err) => Err(err),
+    }
+}
+
+#[derive(Debug, Serialize, Default, Clone, PartialEq)]
+/// the taxes of a line by [Stage], see [TaxComputer::tax_net_by_stage]
+pub struct TaxBreakdown {
+    /// stores the taxes of the [Stage::OverTaxable] stage
+    pub over_taxable: BigDecimal,
+    /// stores the taxes of the [Stage::OverTax] stage
+    pub over_tax: BigDecimal,
+    /// stores the taxes of the [Stage::OverTaxIgnorable] stage
+    pub over_tax_ignorable: BigDecimal,
+}
+
+impl TaxBreakdown {
+    /// returns the taxes of all the stages
+    pub fn total(&self) -> BigDecimal {
+        &self.over_taxable + &self.over_tax + &self.over_tax_ignorable
+    }
+}
+
+#[derive(Debug, Serialize, Default, Clone, PartialEq)]
+/// the line values each [Stage] was applied to, see [TaxComputer::bases]
+pub struct TaxBases {
+    /// stores the base of the [Stage::OverTaxable] stage, the line net
+    pub over_taxable_base: BigDecimal,
+    /// stores the base of the [Stage::OverTax] stage, the line net plus the over taxable taxes
+    pub over_tax_base: BigDecimal,
+    /// stores the base of the [Stage::OverTaxIgnorable] stage, the line net
+    pub over_tax_ignorable_base: BigDecimal,
+}
+
+#[derive(Debug)]
 pub struct TaxComputer {
     over_taxable: TaxStage,
     over_tax: TaxStage,
     over_tax_ignorable: TaxStage,
+    dated: Vec<DatedTax>,
+    registered: Vec<(Stage, Mode, BigDecimal)>,
+    reject_duplicate_taxes: bool,
+    max_tax_per_line: Option<BigDecimal>,
+    tax_holiday: Option<(NaiveDate, NaiveDate)>,
+    bases: TaxBases,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// a tax registered with [TaxComputer::add_tax_dated]
+struct DatedTax {
+    value: BigDecimal,
+    stage: Stage,
+    mode: Mode,
+    effective: NaiveDate,
 }
 
 impl TaxComputer {
     /// returns a ComputerTax stage handler
     ///
+    /// Until a tax is registered it is an identity: [Taxer::tax] returns zero and [Taxer::un_tax]
+    /// returns the taxed value unchanged.
+    ///
     /// # Examples
     ///
     /// ```
@@ -654,7 +1013,724 @@ impl TaxComputer {
             over_taxable: TaxStage::default(),
             over_tax: TaxStage::default(),
             over_tax_ignorable: TaxStage::default(),
+            dated: Vec::new(),
+            registered: Vec::new(),
+            reject_duplicate_taxes: false,
+            max_tax_per_line: None,
+            tax_holiday: None,
+            bases: TaxBases::default(),
+        }
+    }
+
+    /// when enabled, [Taxer::add_tax] fails with [TaxError::Other] if a tax with the same stage,
+    /// mode and value was already added, instead of cumulating it. Catches taxes registered twice
+    /// by mistake, like adding 18% twice and taxing at 36%. Zero valued taxes are never rejected.
+    pub fn set_reject_duplicate_taxes(&mut self, reject: bool) {
+        self.reject_duplicate_taxes = reject;
+    }
+
+    /// returns true if duplicated taxes are rejected
+    pub fn reject_duplicate_taxes(&self) -> bool {
+        self.reject_duplicate_taxes
+    }
+
+    /// adds a tax to the specified [Stage] that is in effect from the `effective` date, until
+    /// another tax of the same stage and mode becomes effective. Dated taxes are only applied by
+    /// [TaxComputer::tax_on_date], the taxes added with [Taxer::add_tax] apply on any date.
+    /// Could return [TaxError::NegativeValue]
+    pub fn add_tax_dated(
+        &mut self,
+        value: BigDecimal,
+        stage: Stage,
+        mode: Mode,
+        effective: NaiveDate,
+    ) -> Option<TaxError<String>> {
+        let mut validation = TaxStage::new();
+        let err = match mode {
+            Mode::Percentual => validation.add_percentual(value.clone()),
+            Mode::AmountLine => validation.add_amount_by_line(value.clone()),
+            Mode::AmountUnit => validation.add_amount_by_qty(value.clone()),
+            Mode::PercentOfTax if stage != Stage::OverTax => {
+                return Some(percent_of_tax_stage_error(&stage))
+            }
+            Mode::PercentOfTax => validation.add_percent_of_tax(value.clone()),
+        };
+
+        if err.is_some() {
+            return err;
+        }
+
+        self.dated.push(DatedTax {
+            value,
+            stage,
+            mode,
+            effective,
+        });
+
+        None
+    }
+
+    /// adds a percentual tax to the specified [Stage] applied only up to the maximum assessable
+    /// value of the line, see [TaxStage::add_percentual_with_assessable_cap]. With [None] as cap
+    /// it is the same as adding a percentual tax with [Taxer::add_tax].
+    /// Once an assessable capped tax is registered, [Taxer::un_tax] fails with [TaxError::Other].
+    /// Could return [TaxError::NegativeValue]
+    pub fn add_tax_with_assessable_cap(
+        &mut self,
+        percent: BigDecimal,
+        stage: Stage,
+        assessable_cap: Option<BigDecimal>,
+    ) -> Option<TaxError<String>> {
+        let assessable_cap = match assessable_cap {
+            Some(assessable_cap) => assessable_cap,
+            None => return self.add_tax(percent, stage, Mode::Percentual),
+        };
+
+        let stage = match stage {
+            Stage::OverTaxable => &mut self.over_taxable,
+            Stage::OverTax => &mut self.over_tax,
+            Stage::OverTaxIgnorable => &mut self.over_tax_ignorable,
+        };
+
+        stage.add_percentual_with_assessable_cap(percent, assessable_cap)
+    }
+
+    /// sets the scale the taxes of the specified [Stage] are rounded to by [Taxer::tax] and
+    /// [TaxComputer::tax_net], halves to even, before they are added to the taxes of
+    /// the other stages. The [Stage::OverTax] stage is calculated over the rounded over taxable
+    /// taxes. [Taxer::un_tax] ignores the stage scales.
+    pub fn set_stage_scale(&mut self, stage: Stage, scale: u32) {
+        match stage {
+            Stage::OverTaxable => self.over_taxable.set_scale(Some(scale)),
+            Stage::OverTax => self.over_tax.set_scale(Some(scale)),
+            Stage::OverTaxIgnorable => self.over_tax_ignorable.set_scale(Some(scale)),
+        }
+    }
+
+    /// sets the first and last dates, both included, of a tax holiday, like a tax free weekend.
+    /// [TaxComputer::tax_on_date] calculates no tax for the dates within the range.
+    /// Could return [TaxError::Other] if the first date is after the last one
+    pub fn set_tax_holiday(&mut self, range: (NaiveDate, NaiveDate)) -> Option<TaxError<String>> {
+        if range.0 > range.1 {
+            return Some(TaxError::Other(format!(
+                "tax holiday starts {} after it ends {}",
+                range.0, range.1
+            )));
+        }
+
+        self.tax_holiday = Some(range);
+
+        None
+    }
+
+    /// removes the tax holiday, if any
+    pub fn clear_tax_holiday(&mut self) {
+        self.tax_holiday = None;
+    }
+
+    /// returns the first and last dates of the tax holiday, if any
+    pub fn tax_holiday(&self) -> Option<(NaiveDate, NaiveDate)> {
+        self.tax_holiday
+    }
+
+    /// returns true if the specified date is within the tax holiday
+    pub fn is_tax_holiday(&self, date: NaiveDate) -> bool {
+        match self.tax_holiday {
+            Some((first, last)) => first <= date && date <= last,
+            None => false,
+        }
+    }
+
+    /// returns the calculated cummulated tax value for the specified [BigDecimal] unit_value,
+    /// like [Taxer::tax], with the dated taxes in effect on the specified date.
+    /// For each stage and mode the dated tax with the latest effective date not after `date`
+    /// is used, and no tax is calculated during the tax holiday.
+    /// Could returns [TaxError::NegativeValue]
+    pub fn tax_on_date(
+        &self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+        date: NaiveDate,
+    ) -> Result<BigDecimal, TaxError<String>> {
+        if self.is_tax_holiday(date) {
+            return Ok(crate::zero());
+        }
+
+        let mut in_effect: Vec<&DatedTax> = Vec::new();
+
+        for dated in self.dated.iter().filter(|dated| dated.effective <= date) {
+            match in_effect
+                .iter_mut()
+                .find(|current| current.stage == dated.stage && current.mode == dated.mode)
+            {
+                Some(current) => {
+                    if dated.effective >= current.effective {
+                        *current = dated;
+                    }
+                }
+                None => in_effect.push(dated),
+            }
+        }
+
+        let mut taxer = TaxComputer {
+            over_taxable: self.over_taxable.clone(),
+            over_tax: self.over_tax.clone(),
+            over_tax_ignorable: self.over_tax_ignorable.clone(),
+            dated: Vec::new(),
+            registered: Vec::new(),
+            reject_duplicate_taxes: false,
+            max_tax_per_line: self.max_tax_per_line.clone(),
+            tax_holiday: None,
+            bases: TaxBases::default(),
+        };
+
+        for dated in in_effect {
+            if let Some(err) =
+                taxer.add_tax(dated.value.clone(), dated.stage.clone(), dated.mode.clone())
+            {
+                return Err(err);
+            }
+        }
+
+        taxer.tax(unit_value, qty)
+    }
+
+    /// returns the calculated cummulated tax value for the specified [BigDecimal] line net.
+    /// Gives the same result as [Taxer::tax] with `net / qty` as unit value, without the precision
+    /// loss of dividing a net that can't be evenly split among the units.
+    /// Could returns [TaxError::NegativeValue]
+    pub fn tax_net(
+        &self,
+        net: BigDecimal,
+        qty: BigDecimal,
+    ) -> Result<BigDecimal, TaxError<String>> {
+        self.uncapped_tax_net(net, qty)
+            .map(|tax| self.apply_tax_cap(tax).0)
+    }
+
+    /// returns the total of the taxes by stage limited to the max tax per line, the same as
+    /// [TaxComputer::tax_net] for the net the breakdown was calculated with
+    pub fn capped_total(&self, breakdown: &TaxBreakdown) -> BigDecimal {
+        self.apply_tax_cap(breakdown.total()).0
+    }
+
+    /// sets the max cumulated tax of a line, applied after all the stages by [Taxer::tax] and
+    /// [TaxComputer::tax_net]. `None` removes the cap. [Taxer::un_tax] ignores the cap.
+    /// Could return [TaxError::NegativeValue]
+    pub fn set_max_tax_per_line(&mut self, max: Option<BigDecimal>) -> Option<TaxError<String>> {
+        if let Some(max) = &max {
+            if *max < crate::zero() {
+                return Some(TaxError::NegativeValue(format!("max tax per line {}", max)));
+            }
+        }
+
+        self.max_tax_per_line = max;
+
+        None
+    }
+
+    /// returns the max cumulated tax of a line, if any
+    pub fn max_tax_per_line(&self) -> Option<&BigDecimal> {
+        self.max_tax_per_line.as_ref()
+    }
+
+    /// same as [Taxer::tax] but also returns true when the max tax per line trimmed the tax
+    /// Could returns [TaxError::NegativeValue]
+    pub fn tax_with_cap(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+    ) -> Result<(BigDecimal, bool), TaxError<String>> {
+        self.uncapped_tax(unit_value, qty)
+            .map(|tax| self.apply_tax_cap(tax))
+    }
+
+    /// clamps the tax to the max tax per line, returning true if it was trimmed
+    fn apply_tax_cap(&self, tax: BigDecimal) -> (BigDecimal, bool) {
+        match &self.max_tax_per_line {
+            Some(max) if tax > *max => (max.clone(), true),
+            _ => (tax, false),
+        }
+    }
+
+    fn uncapped_tax(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+    ) -> Result<BigDecimal, TaxError<String>> {
+        let (tax, bases) = self.uncapped_tax_with_bases(unit_value, qty)?;
+        self.bases = bases;
+
+        Ok(tax)
+    }
+
+    /// returns the [TaxBases] each stage was applied to by the last [Taxer::tax], so the
+    /// intermediate values of the calculation can be audited.
+    pub fn bases(&self) -> &TaxBases {
+        &self.bases
+    }
+
+    /// same as [Taxer::tax] but also returns the [TaxBases] it recorded, see [TaxComputer::bases].
+    /// Could returns [TaxError::NegativeValue]
+    pub fn tax_with_bases(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+    ) -> Result<(BigDecimal, TaxBases), TaxError<String>> {
+        let tax = self.tax(unit_value, qty)?;
+
+        Ok((tax, self.bases.clone()))
+    }
+
+    fn uncapped_tax_with_bases(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+    ) -> Result<(BigDecimal, TaxBases), TaxError<String>> {
+        let tax_over_taxable = self.over_taxable.tax(unit_value.clone(), qty.clone())?;
+        let over_tax_base = &tax_over_taxable + &unit_value * &qty;
+        let over_tax = self.over_tax.round(
+            self.over_tax.tax_net(&over_tax_base, &qty)?
+                + &tax_over_taxable * &self.over_tax.percent_of_tax / crate::hundred(),
+        );
+        let over_tax_ignorable = self
+            .over_tax_ignorable
+            .tax(unit_value.clone(), qty.clone())?;
+
+        let bases = TaxBases {
+            over_taxable_base: &unit_value * &qty,
+            over_tax_base,
+            over_tax_ignorable_base: &unit_value * &qty,
+        };
+
+        Ok((tax_over_taxable + over_tax + over_tax_ignorable, bases))
+    }
+
+    fn uncapped_tax_net(
+        &self,
+        net: BigDecimal,
+        qty: BigDecimal,
+    ) -> Result<BigDecimal, TaxError<String>> {
+        self.tax_net_by_stage(net, qty)
+            .map(|breakdown| breakdown.total())
+    }
+
+    /// returns the taxes of each [Stage] for the specified [BigDecimal] line net, like
+    /// [TaxComputer::tax_net] before the max tax per line is applied.
+    /// Could returns [TaxError::NegativeValue]
+    pub fn tax_net_by_stage(
+        &self,
+        net: BigDecimal,
+        qty: BigDecimal,
+    ) -> Result<TaxBreakdown, TaxError<String>> {
+        let over_taxable = self
+            .over_taxable
+            .round(self.over_taxable.tax_net(&net, &qty)?);
+        let over_tax = self.over_tax.round(
+            self.over_tax.tax_net(&(&over_taxable + &net), &qty)?
+                + &over_taxable * &self.over_tax.percent_of_tax / crate::hundred(),
+        );
+        let over_tax_ignorable = self
+            .over_tax_ignorable
+            .round(self.over_tax_ignorable.tax_net(&net, &qty)?);
+
+        Ok(TaxBreakdown {
+            over_taxable,
+            over_tax,
+            over_tax_ignorable,
+        })
+    }
+
+    /// returns a tuple with the tax exclusive total and the tax inclusive total
+    /// of the specified [BigDecimal] unit_value and quantity, so both prices can be shown side by side.
+    /// Could returns [TaxError::NegativeValue]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use baggins::tax::{TaxComputer, Taxer, Stage, Mode};
+    /// use bigdecimal::BigDecimal;
+    /// use std::str::FromStr;
+    ///
+    /// let mut taxer = TaxComputer::new();
+    /// let _ = taxer.add_tax_from_str("18.0", Stage::OverTaxable, Mode::Percentual);
+    ///
+    /// let (exclusive, inclusive) = taxer
+    ///     .dual_price(BigDecimal::from_str("100").unwrap(), BigDecimal::from_str("1").unwrap())
+    ///     .unwrap();
+    ///
+    /// assert_eq!(exclusive, BigDecimal::from_str("100").unwrap());
+    /// assert_eq!(inclusive, BigDecimal::from_str("118").unwrap());
+    /// ```
+    pub fn dual_price(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+    ) -> Result<(BigDecimal, BigDecimal), TaxError<String>> {
+        let exclusive = &unit_value * &qty;
+
+        match self.tax(unit_value, qty) {
+            Ok(tax) => {
+                let inclusive = &exclusive + &tax;
+                Ok((exclusive, inclusive))
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// returns the tax inclusive price of a single base unit, like the price per kg of a product
+    /// sold by weight, as the tax inclusive total of the line divided by the quantity.
+    /// Could returns [TaxError::NegativeValue] [TaxError::DivisionByZero] if qty is zero
+    pub fn inclusive_price_per_base_unit(
+        &mut self,
+        unit_value: BigDecimal,
+        qty: BigDecimal,
+    ) -> Result<BigDecimal, TaxError<String>> {
+        if qty == crate::zero() {
+            return Err(TaxError::DivisionByZero(
+                "qty can't be zero to get the price per base unit".to_string(),
+            ));
+        }
+
+        let (_, inclusive) = self.dual_price(unit_value, qty.clone())?;
+
+        Ok(inclusive / qty)
+    }
+
+    /// validates the registered taxes produce the expected effective rate, in percent, over the
+    /// `reference` unit value and quantity, within the specified tolerance in percentage points.
+    /// Useful to catch transcription errors when loading rates from external sources.
+    /// Could returns [TaxError::NegativeValue] [TaxError::DivisionByZero] if the reference line is
+    /// zero, and [TaxError::Other] if the effective rate deviates beyond the tolerance
+    pub fn assert_effective_rate(
+        &mut self,
+        reference: BigDecimal,
+        qty: BigDecimal,
+        expected_pct: BigDecimal,
+        tolerance: BigDecimal,
+    ) -> Result<(), TaxError<String>> {
+        if tolerance < crate::zero() {
+            return Err(TaxError::NegativeValue(format!("tolerance {}", tolerance)));
+        }
+
+        let line = &reference * &qty;
+
+        if line == crate::zero() {
+            return Err(TaxError::DivisionByZero(
+                "the reference line can't be zero to get the effective rate".to_string(),
+            ));
+        }
+
+        let tax = self.tax(reference, qty)?;
+        let effective = tax * crate::hundred() / line;
+
+        if (&effective - &expected_pct).abs() > tolerance {
+            return Err(TaxError::Other(format!(
+                "effective rate {}% deviates from the expected {}% beyond {}",
+                effective, expected_pct, tolerance
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// returns the line net whose tax inclusive total, rounded to `scale`, is the `target_brute`
+    /// rounded to `scale`. Useful for shelf prices that must land on a round figure.
+    ///
+    /// When the net rounded to `scale` hits the target it is returned, otherwise the exact
+    /// back solved net is returned, because no net at `scale` taxes to the target.
+    /// Could returns [TaxError::NegativeValue] [TaxError::Other] if the target can't be reached
+    pub fn net_for_round_brute(
+        &mut self,
+        target_brute: BigDecimal,
+        qty: BigDecimal,
+        scale: u32,
+    ) -> Result<BigDecimal, TaxError<String>> {
+        let scale = i64::from(scale);
+        let target = target_brute.round(scale);
+
+        if target < crate::zero() {
+            return Err(TaxError::NegativeValue(format!(
+                "target_brute {}",
+                target_brute
+            )));
+        }
+
+        let net = self.un_tax(target.clone(), qty.clone())?;
+        let rounded_net = net.round(scale);
+
+        for candidate in [rounded_net, net] {
+            let tax = self.tax_net(candidate.clone(), qty.clone())?;
+            if (&candidate + tax).round(scale) == target {
+                return Ok(candidate);
+            }
         }
+
+        Err(TaxError::Other(format!(
+            "no net taxes to the target brute {} at scale {}",
+            target, scale
+        )))
+    }
+
+    /// returns the tax exclusive line price yielding `markup_pct` percent over a tax inclusive
+    /// cost, as in wholesale to retail repricing. The cost is un taxed with [Taxer::un_tax] and the
+    /// markup is applied over the resulting net cost.
+    /// Could returns [TaxError::NegativeValue]
+    pub fn net_price_from_inclusive_cost(
+        &self,
+        inclusive_cost: BigDecimal,
+        qty: BigDecimal,
+        markup_pct: BigDecimal,
+    ) -> Result<BigDecimal, TaxError<String>> {
+        if inclusive_cost < crate::zero() {
+            return Err(TaxError::NegativeValue(format!(
+                "inclusive_cost {}",
+                inclusive_cost
+            )));
+        }
+
+        if markup_pct < crate::zero() {
+            return Err(TaxError::NegativeValue(format!(
+                "markup_pct {}",
+                markup_pct
+            )));
+        }
+
+        let net_cost = self.un_tax(inclusive_cost, qty)?;
+
+        Ok(net_cost * (crate::hundred() + markup_pct) / crate::hundred())
+    }
+
+    /// removes only the ignorable overtaxes from the specified tax inclusive `total`, returning
+    /// the total with the over taxable and over tax taxes still included.
+    /// The net is first recovered with [Taxer::un_tax] to get the ignorable overtaxes calculated over it.
+    /// Could returns [TaxError::NegativeValue]
+    pub fn base_excluding_ignorable(
+        &self,
+        total: BigDecimal,
+        qty: BigDecimal,
+    ) -> Result<BigDecimal, TaxError<String>> {
+        if total < crate::zero() {
+            return Err(TaxError::NegativeValue(format!("total {}", total)));
+        }
+
+        let net = self.un_tax(total.clone(), qty.clone())?;
+        let over_tax_ignorable = self.over_tax_ignorable.tax_net(&net, &qty)?;
+
+        Ok(total - over_tax_ignorable)
+    }
+
+    /// returns an equivalent [TaxComputer] with the registered taxes in a deterministic order,
+    /// by stage, mode and value, so computers configured in different orders can be compared
+    /// or serialized the same way. The calculated taxes don't change.
+    pub fn canonical_order(&self) -> TaxComputer {
+        let mut registered = self.registered.clone();
+        registered.sort_by(|a, b| {
+            order_key(&a.0, &a.1)
+                .cmp(&order_key(&b.0, &b.1))
+                .then_with(|| a.2.cmp(&b.2))
+        });
+
+        let mut dated = self.dated.clone();
+        dated.sort_by(|a, b| {
+            order_key(&a.stage, &a.mode)
+                .cmp(&order_key(&b.stage, &b.mode))
+                .then_with(|| a.effective.cmp(&b.effective))
+                .then_with(|| a.value.cmp(&b.value))
+        });
+
+        TaxComputer {
+            over_taxable: canonical_stage(&self.over_taxable),
+            over_tax: canonical_stage(&self.over_tax),
+            over_tax_ignorable: canonical_stage(&self.over_tax_ignorable),
+            dated,
+            registered,
+            reject_duplicate_taxes: self.reject_duplicate_taxes,
+            max_tax_per_line: self.max_tax_per_line.clone(),
+            tax_holiday: self.tax_holiday,
+            bases: self.bases.clone(),
+        }
+    }
+
+    /// returns the numerator and denominator of [Taxer::un_tax] without dividing them, so the
+    /// division can be deferred and done once with other divisions, see
+    /// [crate::DetailCalculator::set_exact_rational].
+    /// Could returns [TaxError::NegativeValue], and [TaxError::Other] if an assessable capped tax
+    /// is registered, as the capped taxes can't be removed with a single equation
+    pub fn un_tax_ratio(
+        &self,
+        taxed: BigDecimal,
+        qty: BigDecimal,
+    ) -> Result<(BigDecimal, BigDecimal), TaxError<String>> {
+        if qty < crate::zero() {
+            return Err(TaxError::NegativeValue(format!("qty {}", qty)));
+        }
+
+        let has_capped = [&self.over_taxable, &self.over_tax, &self.over_tax_ignorable]
+            .iter()
+            .any(|stage| !stage.capped_percentuals.is_empty());
+
+        if has_capped {
+            return Err(TaxError::Other(
+                "assessable capped taxes can't be removed from a taxed value".to_string(),
+            ));
+        }
+
+        let a = &self.over_taxable.percentuals / crate::hundred();
+        let b = &self.over_taxable.amount_by_qty() * &qty;
+        let c = &self.over_taxable.amount_line;
+        let d = &self.over_tax.percentuals / crate::hundred();
+        let e = &self.over_tax.amount_by_qty() * &qty;
+        let f = &self.over_tax.amount_line;
+        let k = &self.over_tax.percent_of_tax / crate::hundred();
+        let g = &self.over_tax_ignorable.percentuals / crate::hundred();
+        let h = &self.over_tax_ignorable.amount_by_qty() * &qty;
+        let i = &self.over_tax_ignorable.amount_line;
+
+        let numerator = &taxed - (b + c) * (&d + &k + crate::one()) - e - f - h - i;
+        let denominator = &a * (&d + &k) + &a + &g + &d + crate::one();
+
+        Ok((numerator, denominator))
+    }
+
+    /// bakes the registered taxes into a [CompiledTaxPlan], which calculates the same taxes as
+    /// [Taxer::tax] without traversing the stages. The plan doesn't follow the taxes added later.
+    /// Dated taxes are not compiled, as they are not applied by [Taxer::tax] either.
+    pub fn compile(&self) -> CompiledTaxPlan {
+        CompiledTaxPlan {
+            over_taxable_rate: &self.over_taxable.percentuals / crate::hundred(),
+            over_taxable_unit: self.over_taxable.amount_unit.clone(),
+            over_taxable_line: self.over_taxable.amount_line.clone(),
+            over_tax_rate: &self.over_tax.percentuals / crate::hundred(),
+            percent_of_tax_rate: &self.over_tax.percent_of_tax / crate::hundred(),
+            ignorable_rate: &self.over_tax_ignorable.percentuals / crate::hundred(),
+            over_tax_unit: self.over_tax.amount_unit.clone(),
+            over_tax_line: self.over_tax.amount_line.clone(),
+            ignorable_unit: self.over_tax_ignorable.amount_unit.clone(),
+            ignorable_line: self.over_tax_ignorable.amount_line.clone(),
+            over_taxable_capped: self.over_taxable.capped_percentuals.clone(),
+            over_tax_capped: self.over_tax.capped_percentuals.clone(),
+            ignorable_capped: self.over_tax_ignorable.capped_percentuals.clone(),
+            over_taxable_scale: self.over_taxable.scale,
+            over_tax_scale: self.over_tax.scale,
+            ignorable_scale: self.over_tax_ignorable.scale,
+            max_tax_per_line: self.max_tax_per_line.clone(),
+        }
+    }
+}
+
+/// the taxes of a [TaxComputer] flattened by [TaxComputer::compile] into the rates and amounts of
+/// a single equation, cheap to clone and to apply in high volume pricing
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompiledTaxPlan {
+    over_taxable_rate: BigDecimal,
+    over_taxable_unit: BigDecimal,
+    over_taxable_line: BigDecimal,
+    over_tax_rate: BigDecimal,
+    percent_of_tax_rate: BigDecimal,
+    ignorable_rate: BigDecimal,
+    over_tax_unit: BigDecimal,
+    over_tax_line: BigDecimal,
+    ignorable_unit: BigDecimal,
+    ignorable_line: BigDecimal,
+    over_taxable_capped: Vec<(BigDecimal, BigDecimal)>,
+    over_tax_capped: Vec<(BigDecimal, BigDecimal)>,
+    ignorable_capped: Vec<(BigDecimal, BigDecimal)>,
+    over_taxable_scale: Option<u32>,
+    over_tax_scale: Option<u32>,
+    ignorable_scale: Option<u32>,
+    max_tax_per_line: Option<BigDecimal>,
+}
+
+impl CompiledTaxPlan {
+    /// returns the same cummulated tax value as [Taxer::tax] for the specified [BigDecimal]
+    /// taxable unit value and quantity. Could returns [TaxError::NegativeValue]
+    pub fn apply(
+        &self,
+        taxable: &BigDecimal,
+        qty: &BigDecimal,
+    ) -> Result<BigDecimal, TaxError<String>> {
+        if *taxable < crate::zero() {
+            return Err(TaxError::NegativeValue(format!(
+                "negative taxable at calculating registered taxes {}",
+                taxable
+            )));
+        }
+
+        if *qty < crate::zero() {
+            return Err(TaxError::NegativeValue(format!(
+                "negative quantity at calculating registered taxes {}",
+                qty
+            )));
+        }
+
+        if *taxable == crate::zero() {
+            return Ok(crate::zero());
+        }
+
+        let line = taxable * qty;
+        let over_taxable = round_to_scale(
+            (taxable * &self.over_taxable_rate + &self.over_taxable_unit) * qty
+                + &self.over_taxable_line
+                + capped_tax(&line, &self.over_taxable_capped),
+            self.over_taxable_scale,
+        );
+        let over_tax_line = &over_taxable + &line;
+        let over_tax = round_to_scale(
+            &over_tax_line * &self.over_tax_rate
+                + &self.over_tax_unit * qty
+                + &self.over_tax_line
+                + &over_taxable * &self.percent_of_tax_rate
+                + capped_tax(&over_tax_line, &self.over_tax_capped),
+            self.over_tax_scale,
+        );
+        let over_tax_ignorable = round_to_scale(
+            (taxable * &self.ignorable_rate + &self.ignorable_unit) * qty
+                + &self.ignorable_line
+                + capped_tax(&line, &self.ignorable_capped),
+            self.ignorable_scale,
+        );
+        let tax = over_taxable + over_tax + over_tax_ignorable;
+
+        match &self.max_tax_per_line {
+            Some(max) if tax > *max => Ok(max.clone()),
+            _ => Ok(tax),
+        }
+    }
+}
+
+/// returns the position of a tax of the specified stage and mode in [TaxComputer::canonical_order]
+fn order_key(stage: &Stage, mode: &Mode) -> (u8, u8) {
+    (stage.clone() as u8, mode.clone() as u8)
+}
+
+/// returns a copy of the stage with its assessable capped taxes sorted by percent and cap
+fn canonical_stage(stage: &TaxStage) -> TaxStage {
+    let mut stage = stage.clone();
+    stage.capped_percentuals.sort();
+    stage
+}
+
+/// returns the error of a [Mode::PercentOfTax] tax added to a stage other than [Stage::OverTax]
+fn percent_of_tax_stage_error(stage: &Stage) -> TaxError<String> {
+    TaxError::Other(format!(
+        "percent of tax taxes are only allowed for the over tax stage, not {:?}",
+        stage
+    ))
+}
+
+/// compares the registered taxes and settings, leaving out the bases of the last computed line,
+/// see [TaxComputer::bases]
+impl PartialEq for TaxComputer {
+    fn eq(&self, other: &Self) -> bool {
+        self.over_taxable == other.over_taxable
+            && self.over_tax == other.over_tax
+            && self.over_tax_ignorable == other.over_tax_ignorable
+            && self.dated == other.dated
+            && self.registered == other.registered
+            && self.reject_duplicate_taxes == other.reject_duplicate_taxes
+            && self.max_tax_per_line == other.max_tax_per_line
+            && self.tax_holiday == other.tax_holiday
     }
 }
 
@@ -678,23 +1754,48 @@ impl Taxer for TaxComputer {
     }
 
     fn add_tax(&mut self, tax: BigDecimal, stage: Stage, mode: Mode) -> Option<TaxError<String>> {
-        match stage {
+        let is_duplicate =
+            self.registered
+                .iter()
+                .any(|(registered_stage, registered_mode, value)| {
+                    *registered_stage == stage && *registered_mode == mode && *value == tax
+                });
+
+        if self.reject_duplicate_taxes && is_duplicate {
+            return Some(TaxError::Other(format!(
+                "duplicated tax {} {:?} {:?}",
+                tax, stage, mode
+            )));
+        }
+
+        let registration = (stage.clone(), mode.clone(), tax.clone());
+
+        let err = match stage {
             Stage::OverTaxable => match mode {
                 Mode::Percentual => self.over_taxable.add_percentual(tax),
                 Mode::AmountLine => self.over_taxable.add_amount_by_line(tax),
                 Mode::AmountUnit => self.over_taxable.add_amount_by_qty(tax),
+                Mode::PercentOfTax => Some(percent_of_tax_stage_error(&stage)),
             },
             Stage::OverTax => match mode {
                 Mode::Percentual => self.over_tax.add_percentual(tax),
                 Mode::AmountLine => self.over_tax.add_amount_by_line(tax),
                 Mode::AmountUnit => self.over_tax.add_amount_by_qty(tax),
+                Mode::PercentOfTax => self.over_tax.add_percent_of_tax(tax),
             },
             Stage::OverTaxIgnorable => match mode {
                 Mode::Percentual => self.over_tax_ignorable.add_percentual(tax),
                 Mode::AmountLine => self.over_tax_ignorable.add_amount_by_line(tax),
                 Mode::AmountUnit => self.over_tax_ignorable.add_amount_by_qty(tax),
+                Mode::PercentOfTax => Some(percent_of_tax_stage_error(&stage)),
             },
+        };
+
+        if err.is_none() && registration.2 != crate::zero() {
+            self.registered.push(registration);
         }
+
+        err
     }
 
     fn add_tax_from_f64(
@@ -718,6 +1819,17 @@ impl Taxer for TaxComputer {
     ) -> Option<TaxError<String>> {
         let tax = tax.into();
 
+        let tax = match crate::strip_percent(&tax) {
+            Some(stripped) if tax_type == Mode::Percentual => stripped.to_string(),
+            Some(_) => {
+                return Some(TaxError::InvalidDecimal(format!(
+                    "tax {} percent symbol is only allowed for percentual taxes",
+                    tax
+                )))
+            }
+            None => tax,
+        };
+
         match BigDecimal::from_str(&tax) {
             Ok(tax) => self.add_tax(tax, stage, tax_type),
             Err(err) => Some(TaxError::InvalidDecimal(err.to_string())),
@@ -729,32 +1841,7 @@ impl Taxer for TaxComputer {
         unit_value: BigDecimal,
         qty: BigDecimal,
     ) -> Result<BigDecimal, TaxError<String>> {
-        // if unit_value < crate::zero() {
-        //     return Err(TaxError::NegativeValue(format!("unit_value {}", unit_value)))
-        // }
-
-        // if qty < crate::zero() {
-        //     return Err(TaxError::NegativeValue(format!("quantity {}", qty)))
-        // }
-
-        // let net = &unit_value * &qty;
-        match self.over_taxable.tax(unit_value.clone(), qty.clone()) {
-            Ok(tax_over_taxable) => match self
-                .over_tax
-                .tax(&tax_over_taxable + &unit_value, qty.clone())
-            {
-                Ok(over_tax) => {
-                    match self.over_tax_ignorable.tax(unit_value.clone(), qty.clone()) {
-                        Ok(over_tax_ignorable) => {
-                            Ok(&tax_over_taxable + &over_tax + &over_tax_ignorable)
-                        }
-                        Err(err) => Err(err),
-                    }
-                }
-                Err(err) => Err(err),
-            },
-            Err(err) => Err(err),
-        }
+        self.tax_with_cap(unit_value, qty).map(|(tax, _)| tax)
     }
 
     fn tax_from_f64(&mut self, unit_value: f64, qty: f64) -> Result<BigDecimal, TaxError<String>> {
@@ -789,11 +1876,12 @@ impl Taxer for TaxComputer {
 
     /// removes the calculated cummulated tax value for the specified [BigDecimal] taxed.
     /// returning the [BigDecimal] value over the cummulated taxes were calculated.
-    /// Could returns [TaxError::NegativeValue]
+    /// Could returns [TaxError::NegativeValue], and [TaxError::Other] if an assessable capped tax
+    /// is registered, see [TaxComputer::un_tax_ratio]
     ///
     /// This implementation uses the next equation to un tax the taxed value
     ///
-    /// taxed – b * d + b + e + h - c * (d + 1) - f - i  /  a * d + a + g + d + 1
+    /// (taxed – (b + c) * (d + k + 1) - e - f - h - i)  /  (a * (d + k) + a + g + d + 1)
     ///
     /// Where
     ///
@@ -810,6 +1898,8 @@ impl Taxer for TaxComputer {
     ///
     /// f = over_tax.amount_line
     ///
+    /// k = over_tax.percent_of_tax / 100
+    ///
     ///
     /// g = over_tax_ignorable.percentuals / 100
     ///
@@ -819,24 +1909,8 @@ impl Taxer for TaxComputer {
     ///
     ///
     fn un_tax(&self, taxed: BigDecimal, qty: BigDecimal) -> Result<BigDecimal, TaxError<String>> {
-        if qty < crate::zero() {
-            return Err(TaxError::NegativeValue(format!("qty {}", qty)));
-        }
-
-        let a = &self.over_taxable.percentuals / crate::hundred();
-        let b = &self.over_taxable.amount_by_qty() * &qty;
-        let c = &self.over_taxable.amount_line;
-        let d = &self.over_tax.percentuals / crate::hundred();
-        let e = &self.over_tax.amount_by_qty() * &qty;
-        let f = &self.over_tax.amount_line;
-        let g = &self.over_tax_ignorable.percentuals / crate::hundred();
-        let h = &self.over_tax_ignorable.amount_by_qty() * &qty;
-        let i = &self.over_tax_ignorable.amount_line;
-
-        let numerator = &taxed - &b * &d + b + e + h - c * (&d + crate::one()) - f - i;
-        let denominator = &a + &d + &a + &g + &d + crate::one();
-
-        Ok(numerator / denominator)
+        self.un_tax_ratio(taxed, qty)
+            .map(|(numerator, denominator)| numerator / denominator)
     }
 
     /// removes the calculated cummulated tax value for the specified [f64] taxed.
@@ -846,7 +1920,7 @@ impl Taxer for TaxComputer {
     ///
     /// This implementation uses the next equation to un tax the taxed value
     ///
-    /// taxed – b * d + b + e + h - c * (d + 1) - f - i  /  a * d + a + g + d + 1
+    /// (taxed – (b + c) * (d + k + 1) - e - f - h - i)  /  (a * (d + k) + a + g + d + 1)
     ///
     /// Where
     ///
@@ -863,6 +1937,8 @@ impl Taxer for TaxComputer {
     ///
     /// f = over_tax.amount_line
     ///
+    /// k = over_tax.percent_of_tax / 100
+    ///
     ///
     /// g = over_tax_ignorable.percentuals / 100
     ///
@@ -884,7 +1960,7 @@ impl Taxer for TaxComputer {
     ///
     /// This implementation uses the next equation to un tax the taxed value
     ///
-    /// taxed – b * d + b + e + h - c * (d + 1) - f - i  /  a * d + a + g + d + 1
+    /// (taxed – (b + c) * (d + k + 1) - e - f - h - i)  /  (a * (d + k) + a + g + d + 1)
     ///
     /// Where
     ///
@@ -901,6 +1977,8 @@ impl Taxer for TaxComputer {
     ///
     /// f = over_tax.amount_line
     ///
+    /// k = over_tax.percent_of_tax / 100
+    ///
     ///
     /// g = over_tax_ignorable.percentuals / 100
     ///
@@ -51,4 +51,2849 @@ fn test_baggins_compute() {
             panic!("{e}")
         }
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_baggins_compute_with_scale_cap() {
+    let mut c = DetailCalculator::new();
+    c.set_scale_cap(Some(4));
+    assert_eq!(c.scale_cap(), Some(4));
+
+    let err = c.add_discount_from_str("10.0", discount::Mode::AmountLine);
+    assert!(err.is_none(), "error adding amount line discount {:?}", err);
+
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let r = c
+        .compute(
+            BigDecimal::from_str("100.0").unwrap(),
+            BigDecimal::from_str("3.0").unwrap(),
+            None,
+        )
+        .unwrap();
+
+    assert_eq!(
+        r.with_discount_values().unit_value,
+        BigDecimal::from_str("96.6667").unwrap()
+    );
+    assert_eq!(r.with_discount_values().net, BigDecimal::from_str("290").unwrap());
+}
+
+#[test]
+fn test_baggins_blended_rate() {
+    let mut c18 = DetailCalculator::new();
+    let err = c18.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding 18% tax {:?}", err);
+
+    let mut c10 = DetailCalculator::new();
+    let err = c10.add_tax_from_str("10.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding 10% tax {:?}", err);
+
+    let lines = vec![
+        c18.compute_from_str("100.0", "1.0", None).unwrap(),
+        c10.compute_from_str("100.0", "3.0", None).unwrap(),
+    ];
+
+    // (18 + 30) * 100 / (100 + 300), not the plain average (18 + 10) / 2
+    assert_eq!(baggins::blended_rate(&lines), BigDecimal::from_str("12").unwrap());
+    assert_eq!(baggins::blended_rate(&[]), BigDecimal::from_str("0").unwrap());
+}
+
+#[test]
+fn test_baggins_compute_with_flags() {
+    let mut c = DetailCalculator::new();
+
+    let err = c.add_discount_from_str("50.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+
+    let (calc, flags) = c
+        .compute_with_flags(
+            BigDecimal::from_str("100.0").unwrap(),
+            BigDecimal::from_str("2.0").unwrap(),
+            Some(BigDecimal::from_str("40.0").unwrap()),
+        )
+        .unwrap();
+
+    assert!(flags.discount_capped);
+    assert!(!flags.net_clamped);
+    assert!(flags.any());
+    assert_eq!(
+        calc.with_discount_values().discount_value,
+        BigDecimal::from_str("40").unwrap()
+    );
+    assert_eq!(calc.with_discount_values().net, BigDecimal::from_str("160").unwrap());
+
+    let (_, flags) = c
+        .compute_with_flags(
+            BigDecimal::from_str("100.0").unwrap(),
+            BigDecimal::from_str("2.0").unwrap(),
+            Some(BigDecimal::from_str("1000.0").unwrap()),
+        )
+        .unwrap();
+
+    assert!(!flags.any());
+}
+
+#[test]
+fn test_baggins_compute_with_shipping() {
+    use baggins::tax::Taxer;
+    use baggins::ShippingCharge;
+
+    let mut c = DetailCalculator::new();
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding 18% tax {:?}", err);
+
+    let mut shipping_taxes = tax::TaxComputer::new();
+    let err = shipping_taxes.add_tax_from_str("5.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding 5% shipping tax {:?}", err);
+
+    let (calc, shipping) = c
+        .compute_with_shipping(
+            BigDecimal::from_str("100.0").unwrap(),
+            BigDecimal::from_str("1.0").unwrap(),
+            None,
+            Some(ShippingCharge::new(
+                BigDecimal::from_str("20.0").unwrap(),
+                shipping_taxes,
+            )),
+        )
+        .unwrap();
+
+    let shipping = shipping.unwrap();
+    assert_eq!(calc.with_discount_values().tax, BigDecimal::from_str("18").unwrap());
+    assert_eq!(shipping.tax, BigDecimal::from_str("1").unwrap());
+    assert_eq!(shipping.brute, BigDecimal::from_str("21").unwrap());
+
+    let (_, shipping) = c
+        .compute_with_shipping(
+            BigDecimal::from_str("100.0").unwrap(),
+            BigDecimal::from_str("1.0").unwrap(),
+            None,
+            None,
+        )
+        .unwrap();
+    assert!(shipping.is_none());
+}
+
+#[test]
+fn test_baggins_config_round_trip() {
+    use baggins::config::PricingConfig;
+
+    let mut c = DetailCalculator::new();
+    c.set_scale_cap(Some(10));
+
+    let err = c.add_discount_from_str("10.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+    let err = c.add_discount_from_str("1.0", discount::Mode::AmountUnit);
+    assert!(err.is_none(), "error adding amount unit discount {:?}", err);
+    let err = c.add_tax_from_str("16.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+    let err = c.add_tax_from_str("2.0", tax::Stage::OverTax, tax::Mode::AmountLine);
+    assert!(err.is_none(), "error adding amount line tax {:?}", err);
+
+    let config = PricingConfig::from(&c);
+    let mut rebuilt = DetailCalculator::from_config(&config).unwrap();
+    assert_eq!(PricingConfig::from(&rebuilt), config);
+
+    let before = c.compute_from_str("100.0", "3.0", Some("1000.0")).unwrap();
+    let after = rebuilt.compute_from_str("100.0", "3.0", Some("1000.0")).unwrap();
+
+    assert_eq!(before.to_string(), after.to_string());
+}
+
+#[test]
+fn test_baggins_amount_unit_and_amount_line_discounts() {
+    let mut c = DetailCalculator::new();
+
+    let err = c.add_discount_from_str("1.0", discount::Mode::AmountUnit);
+    assert!(err.is_none(), "error adding amount unit discount {:?}", err);
+    let err = c.add_discount_from_str("2.0", discount::Mode::AmountLine);
+    assert!(err.is_none(), "error adding amount line discount {:?}", err);
+    let err = c.add_tax_from_str("10.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let r = c.compute_from_str("100.0", "3.0", Some("1000.0")).unwrap();
+    let with = r.with_discount_values();
+    let without = r.without_discount_values();
+
+    // 100 * 3 - 1 * 3 - 2
+    assert_eq!(with.net, BigDecimal::from_str("295").unwrap());
+    assert_eq!(with.discount_value, BigDecimal::from_str("5").unwrap());
+    assert_eq!(
+        with.unit_value,
+        BigDecimal::from_str("295").unwrap() / BigDecimal::from_str("3").unwrap()
+    );
+    assert_eq!(with.tax, BigDecimal::from_str("29.5").unwrap());
+    assert_eq!(with.brute, BigDecimal::from_str("324.5").unwrap());
+    assert_eq!(with.discount_brute_value, BigDecimal::from_str("-5.5").unwrap());
+
+    assert_eq!(without.net, BigDecimal::from_str("300").unwrap());
+    assert_eq!(without.tax, BigDecimal::from_str("30").unwrap());
+    assert_eq!(without.brute, BigDecimal::from_str("330").unwrap());
+}
+
+#[test]
+fn test_baggins_over_taxable_and_over_tax_with_qty() {
+    use baggins::tax::Taxer;
+
+    let mut c = DetailCalculator::new();
+
+    let err = c.add_tax_from_str("10.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding over taxable tax {:?}", err);
+    let err = c.add_tax_from_str("5.0", tax::Stage::OverTax, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding over tax tax {:?}", err);
+
+    let r = c.compute_from_str("100.0", "3.0", None).unwrap();
+
+    // 10% of 300 is 30, 5% of 300 + 30 is 16.5
+    assert_eq!(
+        r.with_discount_values().tax,
+        BigDecimal::from_str("46.5").unwrap()
+    );
+    assert_eq!(
+        r.with_discount_values().brute,
+        BigDecimal::from_str("346.5").unwrap()
+    );
+    assert_eq!(
+        r.without_discount_values().tax,
+        BigDecimal::from_str("46.5").unwrap()
+    );
+    assert_eq!(
+        r.without_discount_values().brute,
+        BigDecimal::from_str("346.5").unwrap()
+    );
+
+    let mut taxer = tax::TaxComputer::new();
+    let err = taxer.add_tax_from_str("10.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding over taxable tax {:?}", err);
+    let err = taxer.add_tax_from_str("5.0", tax::Stage::OverTax, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding over tax tax {:?}", err);
+
+    let breakdown = taxer
+        .tax_net_by_stage(
+            BigDecimal::from_str("300").unwrap(),
+            BigDecimal::from_str("3").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(breakdown.over_tax, BigDecimal::from_str("16.5").unwrap());
+
+    let tax = taxer
+        .compile()
+        .apply(
+            &BigDecimal::from_str("100").unwrap(),
+            &BigDecimal::from_str("3").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(tax, BigDecimal::from_str("46.5").unwrap());
+}
+
+#[test]
+fn test_baggins_add_surcharge() {
+    use baggins::SurchargeMode;
+
+    let mut c = DetailCalculator::new();
+
+    let err = c.add_tax_from_str("10.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let err = c.add_surcharge(BigDecimal::from_str("5.0").unwrap(), SurchargeMode::AmountLine);
+    assert!(err.is_none(), "error adding amount line surcharge {:?}", err);
+
+    let err = c.add_surcharge(BigDecimal::from_str("-1.0").unwrap(), SurchargeMode::AmountUnit);
+    assert!(err.is_some(), "negative surcharge should not be accepted");
+
+    let r = c.compute_from_str("100.0", "2.0", None).unwrap();
+
+    assert_eq!(r.with_discount_values().net, BigDecimal::from_str("205").unwrap());
+    assert_eq!(r.with_discount_values().tax, BigDecimal::from_str("20.5").unwrap());
+    assert_eq!(r.with_discount_values().brute, BigDecimal::from_str("225.5").unwrap());
+}
+
+#[test]
+fn test_baggins_original_from_final() {
+    let mut c = DetailCalculator::new();
+
+    let err = c.add_discount_from_str("10.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+    let err = c.add_discount_from_str("1.0", discount::Mode::AmountUnit);
+    assert!(err.is_none(), "error adding amount unit discount {:?}", err);
+    let err = c.add_discount_from_str("2.0", discount::Mode::AmountLine);
+    assert!(err.is_none(), "error adding amount line discount {:?}", err);
+    let err = c.add_tax_from_str("16.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+    let err = c.add_tax_from_str("1.0", tax::Stage::OverTaxable, tax::Mode::AmountUnit);
+    assert!(err.is_none(), "error adding amount unit tax {:?}", err);
+    let err = c.add_tax_from_str("3.0", tax::Stage::OverTaxIgnorable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding ignorable tax {:?}", err);
+
+    let r = c.compute_from_str("100.0", "4.0", Some("1000.0")).unwrap();
+
+    let original = c
+        .original_from_final(
+            r.with_discount_values().brute.clone(),
+            BigDecimal::from_str("4.0").unwrap(),
+        )
+        .unwrap();
+
+    assert_eq!(original, BigDecimal::from_str("400").unwrap());
+}
+
+#[test]
+fn test_baggins_original_from_final_with_over_tax() {
+    let mut c = DetailCalculator::new();
+
+    let err = c.add_discount_from_str("10.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+    let err = c.add_tax_from_str("10.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding over taxable tax {:?}", err);
+    let err = c.add_tax_from_str("5.0", tax::Stage::OverTax, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding over tax tax {:?}", err);
+
+    let r = c.compute_from_str("100.0", "3.0", None).unwrap();
+
+    // 270 of net, 27 of over taxable taxes and 5% of 297
+    assert_eq!(
+        r.with_discount_values().brute,
+        BigDecimal::from_str("311.85").unwrap()
+    );
+
+    let original = c
+        .original_from_final(
+            r.with_discount_values().brute.clone(),
+            BigDecimal::from_str("3.0").unwrap(),
+        )
+        .unwrap();
+
+    assert_eq!(original, BigDecimal::from_str("300").unwrap());
+}
+
+#[test]
+fn test_baggins_compute_quantity_table() {
+    let mut c = DetailCalculator::new();
+
+    let err = c.add_tax_from_str("10.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let quantities = vec![
+        BigDecimal::from_str("1").unwrap(),
+        BigDecimal::from_str("10").unwrap(),
+        BigDecimal::from_str("100").unwrap(),
+    ];
+
+    let table = c
+        .compute_quantity_table(BigDecimal::from_str("10.0").unwrap(), &quantities, None)
+        .unwrap();
+
+    assert_eq!(table.len(), 3);
+
+    let expected = ["11", "110", "1100"];
+    for ((qty, calc), (expected_qty, expected_brute)) in
+        table.iter().zip(quantities.iter().zip(expected))
+    {
+        assert_eq!(qty, expected_qty);
+        assert_eq!(
+            calc.with_discount_values().brute,
+            BigDecimal::from_str(expected_brute).unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_baggins_add_tax_with_base() {
+    let mut c = DetailCalculator::new();
+
+    let err = c.add_discount_from_str("20.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+
+    let err = c.add_tax_with_base(
+        BigDecimal::from_str("10.0").unwrap(),
+        tax::Stage::OverTaxable,
+        tax::Mode::Percentual,
+        tax::TaxBase::Gross,
+    );
+    assert!(err.is_none(), "error adding gross tax {:?}", err);
+
+    let err = c.add_tax_with_base(
+        BigDecimal::from_str("5.0").unwrap(),
+        tax::Stage::OverTaxable,
+        tax::Mode::Percentual,
+        tax::TaxBase::DiscountedNet,
+    );
+    assert!(err.is_none(), "error adding discounted net tax {:?}", err);
+
+    let r = c.compute_from_str("100.0", "1.0", Some("1000.0")).unwrap();
+
+    // 10% over the gross 100 plus 5% over the discounted net 80
+    assert_eq!(
+        r.with_discount_values().net,
+        BigDecimal::from_str("80").unwrap()
+    );
+    assert_eq!(
+        r.with_discount_values().tax,
+        BigDecimal::from_str("14").unwrap()
+    );
+    // without discount both taxes are calculated over 100
+    assert_eq!(
+        r.without_discount_values().tax,
+        BigDecimal::from_str("15").unwrap()
+    );
+}
+
+#[test]
+fn test_baggins_reconcile_tax() {
+    let mut c = DetailCalculator::new();
+
+    let err = c.add_tax_from_str("10.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    // every line has a tax of 0.334, rounded to 0.33, while the invoice tax 1.002 rounds to 1.00
+    let lines = vec![
+        c.compute_from_str("3.34", "1.0", None).unwrap(),
+        c.compute_from_str("3.34", "1.0", None).unwrap(),
+        c.compute_from_str("3.34", "1.0", None).unwrap(),
+    ];
+
+    assert_eq!(
+        baggins::reconcile_tax(&lines, 2),
+        BigDecimal::from_str("-0.01").unwrap()
+    );
+}
+
+#[test]
+fn test_baggins_ffi_compute_line() {
+    use baggins::config::PricingConfig;
+    use baggins::ffi;
+
+    let mut c = DetailCalculator::new();
+    let err = c.add_discount_from_str("10.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let config = serde_json::to_string(&PricingConfig::from(&c)).unwrap();
+
+    let calc = ffi::compute_line("100.0", "1.0", Some("1000.0"), &config).unwrap();
+    let calc: serde_json::Value = serde_json::from_str(&calc).unwrap();
+
+    let net = calc["with_discount_values"]["net"].as_str().unwrap();
+    let tax = calc["with_discount_values"]["tax"].as_str().unwrap();
+    assert_eq!(
+        BigDecimal::from_str(net).unwrap(),
+        BigDecimal::from_str("90").unwrap()
+    );
+    assert_eq!(
+        BigDecimal::from_str(tax).unwrap(),
+        BigDecimal::from_str("16.2").unwrap()
+    );
+
+    assert!(ffi::compute_line("abc", "1.0", None, &config).is_err());
+    assert!(ffi::compute_line("100.0", "1.0", None, "{").is_err());
+}
+
+#[test]
+fn test_baggins_rounding_target_brute() {
+    use baggins::RoundingTarget;
+
+    let mut c = DetailCalculator::new();
+    c.set_rounding(RoundingTarget::Brute, 2);
+
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let r = c.compute_from_str("33.33", "1.0", None).unwrap();
+    let with = r.with_discount_values();
+
+    // 33.33 + 5.9994 = 39.3294
+    assert_eq!(with.brute, BigDecimal::from_str("39.33").unwrap());
+    assert_eq!(with.tax, BigDecimal::from_str("6.00").unwrap());
+    assert_eq!(with.net, BigDecimal::from_str("33.33").unwrap());
+    assert_eq!(&with.net + &with.tax, with.brute);
+
+    let without = r.without_discount_values();
+    assert_eq!(&without.net + &without.tax, without.brute);
+}
+
+#[test]
+fn test_baggins_tax_summary() {
+    use baggins::LineTaxStatus;
+
+    let mut standard = DetailCalculator::new();
+    let err = standard.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let mut untaxed = DetailCalculator::new();
+
+    let lines = vec![
+        (
+            LineTaxStatus::Standard,
+            standard.compute_from_str("100.0", "1.0", None).unwrap(),
+        ),
+        (
+            LineTaxStatus::ZeroRated,
+            untaxed.compute_from_str("50.0", "1.0", None).unwrap(),
+        ),
+        (
+            LineTaxStatus::Exempt,
+            untaxed.compute_from_str("30.0", "1.0", None).unwrap(),
+        ),
+        (
+            LineTaxStatus::Standard,
+            standard.compute_from_str("10.0", "1.0", None).unwrap(),
+        ),
+    ];
+
+    let summary = baggins::tax_summary(&lines);
+    assert_eq!(summary.len(), 3);
+
+    assert_eq!(summary[0].status, LineTaxStatus::Standard);
+    assert_eq!(summary[0].base, BigDecimal::from_str("110").unwrap());
+    assert_eq!(summary[0].tax, BigDecimal::from_str("19.8").unwrap());
+    assert_eq!(summary[0].lines, 2);
+
+    assert_eq!(summary[1].status, LineTaxStatus::ZeroRated);
+    assert_eq!(summary[1].base, BigDecimal::from_str("50").unwrap());
+    assert_eq!(summary[1].tax, BigDecimal::from_str("0").unwrap());
+
+    assert_eq!(summary[2].status, LineTaxStatus::Exempt);
+    assert_eq!(summary[2].base, BigDecimal::from_str("30").unwrap());
+    assert_eq!(summary[2].lines, 1);
+}
+
+#[test]
+fn test_baggins_compute_with_profit() {
+    let mut c = DetailCalculator::new();
+
+    let err = c.add_discount_from_str("10.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let (calc, profit) = c
+        .compute_with_profit(
+            BigDecimal::from_str("20.0").unwrap(),
+            BigDecimal::from_str("5.0").unwrap(),
+            BigDecimal::from_str("12.5").unwrap(),
+            None,
+        )
+        .unwrap();
+
+    assert_eq!(
+        calc.with_discount_values().net,
+        BigDecimal::from_str("90").unwrap()
+    );
+    assert_eq!(profit, BigDecimal::from_str("27.5").unwrap());
+
+    let result = c.compute_with_profit(
+        BigDecimal::from_str("20.0").unwrap(),
+        BigDecimal::from_str("5.0").unwrap(),
+        BigDecimal::from_str("-1.0").unwrap(),
+        None,
+    );
+    assert!(matches!(
+        result,
+        Err(baggins::BagginsError::InvalidDecimalValue(_))
+    ));
+}
+
+#[test]
+fn test_baggins_epsilon_snap() {
+    let mut c = DetailCalculator::new();
+
+    let err = c.add_tax_from_str(
+        "0.0000000001",
+        tax::Stage::OverTaxable,
+        tax::Mode::Percentual,
+    );
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let calc = c.compute_from_str("1.0", "1.0", None).unwrap();
+    assert_eq!(
+        calc.with_discount_values().tax,
+        BigDecimal::from_str("0.000000000001").unwrap()
+    );
+
+    c.set_epsilon_snap(Some(BigDecimal::from_str("0.000001").unwrap()));
+
+    let calc = c.compute_from_str("1.0", "1.0", None).unwrap();
+    assert_eq!(
+        calc.with_discount_values().tax,
+        BigDecimal::from_str("0").unwrap()
+    );
+    assert_eq!(
+        calc.without_discount_values().tax,
+        BigDecimal::from_str("0").unwrap()
+    );
+    assert_eq!(
+        calc.with_discount_values().net,
+        BigDecimal::from_str("1").unwrap()
+    );
+
+    // the brute follows the snapped tax
+    assert_eq!(
+        calc.with_discount_values().brute,
+        &calc.with_discount_values().net + &calc.with_discount_values().tax
+    );
+    assert_eq!(
+        calc.without_discount_values().brute,
+        &calc.without_discount_values().net + &calc.without_discount_values().tax
+    );
+}
+
+#[test]
+fn test_baggins_compute_from_i64() {
+    let mut c = DetailCalculator::new();
+
+    let err = c.add_discount_from_str("10.0", discount::Mode::AmountLine);
+    assert!(err.is_none(), "error adding amount line discount {:?}", err);
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let calc = c.compute_from_i64(10000, 2, 2, None).unwrap();
+    let expected = c.compute_from_str("100.00", "2", None).unwrap();
+
+    assert_eq!(
+        calc.without_discount_values().net,
+        BigDecimal::from_str("200").unwrap()
+    );
+    assert_eq!(
+        calc.with_discount_values().net,
+        BigDecimal::from_str("190").unwrap()
+    );
+    assert_eq!(
+        calc.with_discount_values().brute,
+        expected.with_discount_values().brute
+    );
+
+    let result = c.compute_from_i64(10000, 2, 2, Some(500));
+    assert!(
+        result.is_err(),
+        "a 10.00 discount is over the 5.00 max {:?}",
+        result
+    );
+}
+
+#[test]
+fn test_baggins_compute_with_clip() {
+    let mut c = DetailCalculator::new();
+
+    let err = c.add_discount_from_str("30.0", discount::Mode::AmountLine);
+    assert!(err.is_none(), "error adding amount line discount {:?}", err);
+
+    let (calc, clip) = c
+        .compute_with_clip(
+            BigDecimal::from_str("100.0").unwrap(),
+            BigDecimal::from_str("1.0").unwrap(),
+            Some(BigDecimal::from_str("20.0").unwrap()),
+        )
+        .unwrap();
+
+    assert_eq!(clip.requested, BigDecimal::from_str("30").unwrap());
+    assert_eq!(clip.applied, BigDecimal::from_str("20").unwrap());
+    assert_eq!(clip.clipped, BigDecimal::from_str("10").unwrap());
+    assert_eq!(
+        calc.with_discount_values().net,
+        BigDecimal::from_str("80").unwrap()
+    );
+}
+
+#[test]
+fn test_baggins_compute_with_flags_max_total_discount_pct() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_discount_from_str("50.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+
+    let mut config = baggins::config::PricingConfig::from(&c);
+    config.max_total_discount_pct = Some(BigDecimal::from_str("10").unwrap());
+    let mut c = DetailCalculator::from_config(&config).unwrap();
+
+    let unit_value = BigDecimal::from_str("100").unwrap();
+    let qty = BigDecimal::from_str("1").unwrap();
+
+    let result = c.compute(unit_value.clone(), qty.clone(), None);
+    assert!(matches!(
+        result,
+        Err(baggins::BagginsError::Discount(
+            discount::DiscountError::OverMaxDiscount(_)
+        ))
+    ));
+
+    let result = c.compute_with_flags(unit_value.clone(), qty.clone(), None);
+    assert!(matches!(
+        result,
+        Err(baggins::BagginsError::Discount(
+            discount::DiscountError::OverMaxDiscount(_)
+        ))
+    ));
+
+    assert!(c.compute_with_clip(unit_value, qty, None).is_err());
+}
+
+#[test]
+fn test_baggins_amount_line_discount_does_not_scale_with_qty() {
+    let mut c = DetailCalculator::new();
+
+    let err = c.add_discount_from_str("5.0", discount::Mode::AmountLine);
+    assert!(err.is_none(), "error adding amount line discount {:?}", err);
+
+    let single = c.compute_from_str("100.0", "1", None).unwrap();
+    let many = c.compute_from_str("100.0", "10", Some("1000")).unwrap();
+
+    assert_eq!(
+        single.with_discount_values().discount_value,
+        BigDecimal::from_str("5").unwrap()
+    );
+    assert_eq!(
+        many.with_discount_values().discount_value,
+        BigDecimal::from_str("5").unwrap()
+    );
+    assert_eq!(
+        single.with_discount_values().net,
+        BigDecimal::from_str("95").unwrap()
+    );
+    assert_eq!(
+        many.with_discount_values().net,
+        BigDecimal::from_str("995").unwrap()
+    );
+}
+
+#[test]
+fn test_baggins_render_invoice() {
+    let mut c = DetailCalculator::new();
+
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let lines = vec![
+        c.compute_from_str("10.0", "2.0", None).unwrap(),
+        c.compute_from_str("5.5", "1.0", None).unwrap(),
+    ];
+
+    let table = baggins::render_invoice(&lines, 2);
+
+    for header in ["NET", "DISCOUNT", "TAX", "BRUTE"] {
+        assert!(
+            table.contains(header),
+            "missing header {} in\n{}",
+            header,
+            table
+        );
+    }
+
+    let totals = table.lines().last().unwrap();
+    assert!(
+        totals.starts_with("TOTAL"),
+        "missing totals line in\n{}",
+        table
+    );
+    assert!(totals.contains("25.50"), "wrong net total in\n{}", table);
+    assert!(totals.contains("30.09"), "wrong brute total in\n{}", table);
+
+    let empty = baggins::render_invoice(&[], 2);
+    assert_eq!(
+        empty.lines().count(),
+        2,
+        "expected a header only table\n{}",
+        empty
+    );
+    assert!(empty.contains("BRUTE"));
+}
+
+#[test]
+fn test_baggins_is_rounding_stable() {
+    let mut c = DetailCalculator::new();
+
+    let stable = c
+        .is_rounding_stable(
+            BigDecimal::from_str("1.23").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+            2,
+        )
+        .unwrap();
+    assert!(stable, "1.23 should be stable at scale 2");
+
+    let stable = c
+        .is_rounding_stable(
+            BigDecimal::from_str("1.235").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+            2,
+        )
+        .unwrap();
+    assert!(!stable, "1.235 should be unstable at scale 2");
+
+    let stable = c
+        .is_rounding_stable(
+            BigDecimal::from_str("1.23496").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+            2,
+        )
+        .unwrap();
+    assert!(!stable, "1.23496 should be unstable at scale 2");
+}
+
+#[test]
+fn test_baggins_split_installments() {
+    let mut c = DetailCalculator::new();
+
+    let calc = c.compute_from_str("100.00", "1", None).unwrap();
+    let installments = baggins::split_installments(&calc, 3, 2).unwrap();
+
+    assert_eq!(installments.len(), 3);
+    assert_eq!(
+        installments[0].with_discount_values().brute,
+        BigDecimal::from_str("33.34").unwrap()
+    );
+    assert_eq!(
+        installments[1].with_discount_values().brute,
+        BigDecimal::from_str("33.33").unwrap()
+    );
+
+    let total = installments
+        .iter()
+        .fold(BigDecimal::from(0), |total, part| {
+            total + &part.with_discount_values().brute
+        });
+    assert_eq!(total, BigDecimal::from_str("100.00").unwrap());
+
+    assert!(baggins::split_installments(&calc, 0, 2).is_err());
+}
+
+#[test]
+fn test_baggins_split_installments_add_up() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding tax {:?}", err);
+
+    let calc = c.compute_from_str("1.07", "1", None).unwrap();
+    let installments = baggins::split_installments(&calc, 3, 2).unwrap();
+
+    let mut brute = BigDecimal::from(0);
+    for installment in &installments {
+        let values = installment.with_discount_values();
+        assert_eq!(&values.net + &values.tax, values.brute);
+        assert_eq!(installment.tax_breakdown().total(), values.tax);
+        brute += &values.brute;
+    }
+
+    assert_eq!(
+        installments[1].with_discount_values().net,
+        BigDecimal::from_str("0.35").unwrap()
+    );
+    assert_eq!(
+        installments[1].with_discount_values().brute,
+        BigDecimal::from_str("0.41").unwrap()
+    );
+    assert_eq!(brute, calc.with_discount_values().brute);
+}
+
+#[test]
+fn test_baggins_marginal_unit() {
+    let mut c = DetailCalculator::new();
+
+    let err = c.add_discount_from_str("15.0", discount::Mode::AmountLine);
+    assert!(err.is_none(), "error adding amount line discount {:?}", err);
+    let err = c.add_discount_from_str("10.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let mut config = baggins::config::PricingConfig::from(&c);
+    config.stacking = discount::Stacking::BestSingle;
+    let mut c = DetailCalculator::from_config(&config).unwrap();
+
+    // at qty 1 the best discount is the $15 line discount, at qty 2 the 10% ($20) takes over
+    let marginal = c
+        .marginal_unit(
+            BigDecimal::from_str("100.0").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+            None,
+        )
+        .unwrap();
+
+    assert_eq!(marginal, BigDecimal::from_str("112.1").unwrap());
+}
+
+#[test]
+fn test_baggins_strict_input_scale() {
+    let mut c = DetailCalculator::new();
+    c.set_strict_input_scale(Some(2));
+
+    let result = c.compute_from_str("100.12345", "1", None);
+    assert!(
+        matches!(result, Err(baggins::BagginsError::InvalidDecimalValue(_))),
+        "100.12345 should be rejected at scale 2 {:?}",
+        result
+    );
+
+    let result = c.compute_from_str("100.12", "1", None);
+    assert!(
+        result.is_ok(),
+        "100.12 should be accepted at scale 2 {:?}",
+        result
+    );
+
+    let result = c.compute_from_str("100.1200", "1", None);
+    assert!(
+        result.is_ok(),
+        "trailing zeros should be accepted {:?}",
+        result
+    );
+
+    let result = c.compute_from_str("100.12", "1.001", None);
+    assert!(result.is_err(), "qty 1.001 should be rejected at scale 2");
+
+    c.set_strict_input_scale(None);
+    let result = c.compute_from_str("100.12345", "1", None);
+    assert!(
+        result.is_ok(),
+        "any scale should be accepted without strict scale {:?}",
+        result
+    );
+}
+
+#[test]
+fn test_baggins_strict_input_scale_from_brute() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_tax_from_str("19.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+    c.set_strict_input_scale(Some(2));
+
+    // the recovered unit value 84.0336... is not an input, so its scale is not checked
+    let result = c.compute_from_brute(
+        BigDecimal::from_str("100.00").unwrap(),
+        BigDecimal::from_str("1").unwrap(),
+        None,
+    );
+    assert!(
+        result.is_ok(),
+        "a brute at scale 2 should be accepted {:?}",
+        result
+    );
+
+    let result = c.compute_from_brute(
+        BigDecimal::from_str("100.001").unwrap(),
+        BigDecimal::from_str("1").unwrap(),
+        None,
+    );
+    assert!(
+        result.is_err(),
+        "brute 100.001 should be rejected at scale 2"
+    );
+}
+
+#[test]
+fn test_baggins_to_ledger_entries() {
+    use baggins::LedgerAccount;
+
+    let mut c = DetailCalculator::new();
+
+    let err = c.add_discount_from_str("10.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let calc = c.compute_from_str("33.33", "3", None).unwrap();
+    let entries = c.to_ledger_entries(&calc);
+
+    assert_eq!(entries[0].account, LedgerAccount::Sales);
+    assert_eq!(entries[0].amount, BigDecimal::from_str("99.99").unwrap());
+    assert_eq!(entries[1].account, LedgerAccount::Discount);
+    assert_eq!(entries[1].amount, BigDecimal::from_str("-9.999").unwrap());
+    assert_eq!(entries[2].account, LedgerAccount::Tax);
+
+    let balance = entries
+        .iter()
+        .fold(BigDecimal::from(0), |total, entry| total + &entry.amount);
+    assert_eq!(balance, calc.with_discount_values().brute);
+}
+
+#[test]
+fn test_baggins_compute_sequenced() {
+    use baggins::discount::DiscountStep;
+
+    let mut c = DetailCalculator::new();
+
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let steps = [
+        DiscountStep::Amount(BigDecimal::from_str("10").unwrap()),
+        DiscountStep::Percent(BigDecimal::from_str("10").unwrap()),
+    ];
+
+    let calc = c
+        .compute_sequenced(
+            BigDecimal::from_str("100").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+            &steps,
+            None,
+        )
+        .unwrap();
+
+    assert_eq!(
+        calc.with_discount_values().discount_value,
+        BigDecimal::from_str("19").unwrap()
+    );
+    assert_eq!(
+        calc.with_discount_values().net,
+        BigDecimal::from_str("81").unwrap()
+    );
+    assert_eq!(
+        calc.with_discount_values().tax,
+        BigDecimal::from_str("14.58").unwrap()
+    );
+
+    let steps = [DiscountStep::Amount(BigDecimal::from_str("110").unwrap())];
+    let result = c.compute_sequenced(
+        BigDecimal::from_str("100").unwrap(),
+        BigDecimal::from_str("1").unwrap(),
+        &steps,
+        None,
+    );
+    assert!(
+        result.is_err(),
+        "a discount over the line value should fail"
+    );
+}
+
+#[test]
+fn test_baggins_affordable_net() {
+    let mut c = DetailCalculator::new();
+
+    let err = c.add_discount_from_str("10.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let qty = BigDecimal::from_str("2").unwrap();
+    let gross = c
+        .affordable_net(BigDecimal::from_str("50").unwrap(), qty.clone())
+        .unwrap();
+
+    let calc = c.compute(&gross / &qty, qty, None).unwrap();
+    assert_eq!(
+        calc.with_discount_values().brute.round(10),
+        BigDecimal::from_str("50").unwrap()
+    );
+
+    assert!(c
+        .affordable_net(
+            BigDecimal::from_str("-1").unwrap(),
+            BigDecimal::from_str("1").unwrap()
+        )
+        .is_err());
+}
+
+#[test]
+fn test_baggins_compute_with_closure() {
+    let mut custom = DetailCalculator::new();
+    let err = custom.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let mut percentual = DetailCalculator::new();
+    let err = percentual.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+    let err = percentual.add_discount_from_str("15.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+
+    let calc = custom
+        .compute_with(
+            BigDecimal::from_str("20").unwrap(),
+            BigDecimal::from_str("3").unwrap(),
+            |gross, _qty| gross * BigDecimal::from_str("0.15").unwrap(),
+            None,
+        )
+        .unwrap();
+    let expected = percentual.compute_from_str("20", "3", None).unwrap();
+
+    assert_eq!(
+        calc.with_discount_values().discount_value,
+        expected.with_discount_values().discount_value
+    );
+    assert_eq!(
+        calc.with_discount_values().net,
+        expected.with_discount_values().net
+    );
+    assert_eq!(
+        calc.with_discount_values().brute,
+        expected.with_discount_values().brute
+    );
+    assert_eq!(
+        calc.with_discount_values().total_discount_percent,
+        expected.with_discount_values().total_discount_percent
+    );
+    assert_eq!(
+        calc.with_discount_values().unit_value,
+        expected.with_discount_values().unit_value
+    );
+    assert_eq!(
+        calc.with_discount_values().tax,
+        expected.with_discount_values().tax
+    );
+
+    let result = custom.compute_with(
+        BigDecimal::from_str("20").unwrap(),
+        BigDecimal::from_str("3").unwrap(),
+        |gross, _qty| gross * BigDecimal::from(2),
+        None,
+    );
+    assert!(
+        result.is_err(),
+        "a discount over the line value should fail"
+    );
+}
+
+#[test]
+fn test_baggins_total_discount_percent_on_every_path() {
+    use baggins::discount::{DiscountStep, Discounter};
+
+    let mut c = DetailCalculator::new();
+    let err = c.add_discount_from_str("15.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+
+    let unit_value = BigDecimal::from_str("20").unwrap();
+    let qty = BigDecimal::from_str("3").unwrap();
+    let expected = BigDecimal::from_str("15").unwrap();
+
+    let calc = c.compute(unit_value.clone(), qty.clone(), None).unwrap();
+    assert_eq!(calc.with_discount_values().total_discount_percent, expected);
+
+    let (calc, _) = c
+        .compute_with_flags(unit_value.clone(), qty.clone(), None)
+        .unwrap();
+    assert_eq!(calc.with_discount_values().total_discount_percent, expected);
+
+    let calc = c
+        .compute_with(
+            unit_value.clone(),
+            qty.clone(),
+            |gross, _qty| gross * BigDecimal::from_str("0.15").unwrap(),
+            None,
+        )
+        .unwrap();
+    assert_eq!(calc.with_discount_values().total_discount_percent, expected);
+
+    let steps = [DiscountStep::Percent(BigDecimal::from_str("15").unwrap())];
+    let calc = c
+        .compute_sequenced(unit_value.clone(), qty.clone(), &steps, None)
+        .unwrap();
+    assert_eq!(calc.with_discount_values().total_discount_percent, expected);
+
+    let mut d = discount::DiscountComputer::new();
+    let err = d.add_discount_from_str("15.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+    let (_, percent) = d.compute(unit_value, qty, None).unwrap();
+    assert_eq!(percent, expected);
+}
+
+#[test]
+fn test_baggins_order_sensitivity() {
+    let mut c = DetailCalculator::new();
+
+    let err = c.add_discount_from_str("10.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let sensitivity = c
+        .order_sensitivity(
+            BigDecimal::from_str("100").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(sensitivity, BigDecimal::from_str("0").unwrap());
+
+    let err = c.add_tax_from_str("5.0", tax::Stage::OverTaxable, tax::Mode::AmountLine);
+    assert!(err.is_none(), "error adding amount line tax {:?}", err);
+
+    // discount then tax: 90 + 16.2 + 5 = 111.2, tax then discount: 123 - 12.3 = 110.7
+    let sensitivity = c
+        .order_sensitivity(
+            BigDecimal::from_str("100").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(sensitivity, BigDecimal::from_str("0.5").unwrap());
+}
+
+#[test]
+fn test_baggins_compute_minor() {
+    let mut c = DetailCalculator::new();
+
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let calc = c
+        .compute_minor(
+            BigDecimal::from_str("100").unwrap(),
+            BigDecimal::from_str("2").unwrap(),
+            None,
+            2,
+        )
+        .unwrap();
+
+    assert_eq!(calc.with_discount_values.net, 20000);
+    assert_eq!(calc.with_discount_values.tax, 3600);
+    assert_eq!(calc.with_discount_values.brute, 23600);
+    assert_eq!(calc.with_discount_values.unit_value, 10000);
+
+    assert_eq!(
+        baggins::to_minor_units(&BigDecimal::from_str("1e30").unwrap(), 2),
+        None
+    );
+}
+
+#[test]
+fn test_baggins_compute_bundle() {
+    let mut c = DetailCalculator::new();
+
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let items = [
+        (
+            BigDecimal::from_str("100").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+        ),
+        (
+            BigDecimal::from_str("25").unwrap(),
+            BigDecimal::from_str("2").unwrap(),
+        ),
+    ];
+
+    let calcs = c
+        .compute_bundle(&items, BigDecimal::from_str("30").unwrap(), None)
+        .unwrap();
+
+    assert_eq!(
+        calcs[0].with_discount_values().discount_value,
+        BigDecimal::from_str("20").unwrap()
+    );
+    assert_eq!(
+        calcs[1].with_discount_values().discount_value,
+        BigDecimal::from_str("10").unwrap()
+    );
+    assert_eq!(
+        calcs[0].with_discount_values().net,
+        BigDecimal::from_str("80").unwrap()
+    );
+    assert_eq!(
+        calcs[1].with_discount_values().tax,
+        BigDecimal::from_str("7.2").unwrap()
+    );
+
+    let items = [
+        (
+            BigDecimal::from_str("10").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+        ),
+        (
+            BigDecimal::from_str("10").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+        ),
+        (
+            BigDecimal::from_str("10").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+        ),
+    ];
+    let calcs = c
+        .compute_bundle(&items, BigDecimal::from_str("10").unwrap(), None)
+        .unwrap();
+    let allocated = calcs.iter().fold(BigDecimal::from(0), |total, calc| {
+        total + &calc.with_discount_values().discount_value
+    });
+    assert_eq!(allocated, BigDecimal::from_str("10").unwrap());
+}
+
+#[test]
+fn test_baggins_compute_bundle_without_value() {
+    let mut c = DetailCalculator::new();
+
+    let items = [
+        (
+            BigDecimal::from_str("0").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+        ),
+        (
+            BigDecimal::from_str("0").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+        ),
+    ];
+
+    let calcs = c
+        .compute_bundle(&items, BigDecimal::from_str("0").unwrap(), None)
+        .unwrap();
+
+    assert_eq!(calcs.len(), 2);
+    for calc in &calcs {
+        assert_eq!(
+            calc.with_discount_values().discount_value,
+            BigDecimal::from(0)
+        );
+        assert_eq!(calc.with_discount_values().brute, BigDecimal::from(0));
+    }
+}
+
+#[test]
+fn test_baggins_percent_display_scale() {
+    let mut c = DetailCalculator::new();
+
+    let err = c.add_discount_from_str("1.0", discount::Mode::AmountLine);
+    assert!(err.is_none(), "error adding amount line discount {:?}", err);
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    c.set_percent_display_scale(Some(2));
+
+    let calc = c
+        .compute(
+            BigDecimal::from_str("3.005").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+            None,
+        )
+        .unwrap();
+
+    // 1 of 3.005 is 33.2778...%, rounded for display while the money keeps every decimal
+    assert_eq!(
+        calc.with_discount_values().total_discount_percent,
+        BigDecimal::from_str("33.28").unwrap()
+    );
+    assert_eq!(
+        calc.with_discount_values().net,
+        BigDecimal::from_str("2.005").unwrap()
+    );
+    assert_eq!(
+        calc.with_discount_values().tax,
+        BigDecimal::from_str("0.3609").unwrap()
+    );
+    assert_eq!(
+        calc.with_discount_values().brute,
+        BigDecimal::from_str("2.3659").unwrap()
+    );
+}
+
+#[test]
+fn test_baggins_compute_dual_currency() {
+    let mut c = DetailCalculator::new();
+
+    let err = c.add_discount_from_str("10.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let rate = BigDecimal::from_str("0.9").unwrap();
+    let (base, converted) = c
+        .compute_dual_currency(
+            BigDecimal::from_str("50").unwrap(),
+            BigDecimal::from_str("2").unwrap(),
+            None,
+            rate.clone(),
+        )
+        .unwrap();
+
+    assert_eq!(
+        converted.with_discount_values().net,
+        &base.with_discount_values().net * &rate
+    );
+    assert_eq!(
+        converted.with_discount_values().net,
+        BigDecimal::from_str("81").unwrap()
+    );
+    assert_eq!(
+        converted.with_discount_values().brute,
+        BigDecimal::from_str("95.58").unwrap()
+    );
+    assert_eq!(
+        converted.with_discount_values().total_discount_percent,
+        base.with_discount_values().total_discount_percent
+    );
+
+    let result = c.compute_dual_currency(
+        BigDecimal::from_str("50").unwrap(),
+        BigDecimal::from_str("2").unwrap(),
+        None,
+        BigDecimal::from_str("0").unwrap(),
+    );
+    assert!(result.is_err(), "a zero exchange rate should fail");
+}
+
+#[test]
+fn test_baggins_is_deep_discount() {
+    let mut c = DetailCalculator::new();
+
+    let err = c.add_discount_from_str("30.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+
+    let calc = c.compute_from_str("50", "1", None).unwrap();
+
+    assert!(c.is_deep_discount(&calc, BigDecimal::from_str("25").unwrap()));
+    assert!(!c.is_deep_discount(&calc, BigDecimal::from_str("40").unwrap()));
+    assert!(!c.is_deep_discount(&calc, BigDecimal::from_str("30").unwrap()));
+}
+
+#[test]
+fn test_baggins_split_tax() {
+    let mut c = DetailCalculator::new();
+
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let calc = c.compute_from_str("100", "1", None).unwrap();
+
+    let (buyer, seller) = c
+        .split_tax(&calc, BigDecimal::from_str("50").unwrap())
+        .unwrap();
+    assert_eq!(buyer, BigDecimal::from_str("9").unwrap());
+    assert_eq!(seller, BigDecimal::from_str("9").unwrap());
+
+    let (buyer, seller) = c
+        .split_tax(&calc, BigDecimal::from_str("70").unwrap())
+        .unwrap();
+    assert_eq!(buyer, BigDecimal::from_str("12.6").unwrap());
+    assert_eq!(seller, BigDecimal::from_str("5.4").unwrap());
+    assert_eq!(&buyer + &seller, calc.with_discount_values().tax);
+
+    let calc = c.compute_from_str("0.55", "1", None).unwrap();
+    let (buyer, seller) = c
+        .split_tax(&calc, BigDecimal::from_str("50").unwrap())
+        .unwrap();
+    assert_eq!(seller, BigDecimal::from_str("0.04").unwrap());
+    assert_eq!(&buyer + &seller, calc.with_discount_values().tax);
+
+    assert!(c
+        .split_tax(&calc, BigDecimal::from_str("101").unwrap())
+        .is_err());
+}
+
+#[test]
+fn test_baggins_recompute_tax() {
+    let mut c = DetailCalculator::new();
+
+    let err = c.add_discount_from_str("10.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let prior = c.compute_from_str("50", "2", None).unwrap();
+
+    let err = c.add_tax_from_str("2.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let calc = c
+        .recompute_tax(&prior, BigDecimal::from_str("2").unwrap())
+        .unwrap();
+
+    assert_eq!(
+        calc.with_discount_values().net,
+        prior.with_discount_values().net
+    );
+    assert_eq!(
+        calc.with_discount_values().discount_value,
+        prior.with_discount_values().discount_value
+    );
+    assert_eq!(
+        calc.with_discount_values().tax,
+        BigDecimal::from_str("18").unwrap()
+    );
+    assert_eq!(
+        calc.with_discount_values().brute,
+        BigDecimal::from_str("108").unwrap()
+    );
+    assert_eq!(
+        calc.without_discount_values().tax,
+        BigDecimal::from_str("20").unwrap()
+    );
+}
+
+#[test]
+fn test_baggins_compute_for_tier() {
+    let mut tiers = discount::DiscountTiers::new();
+    for (tier, percent) in [("Bronze", "5"), ("Silver", "10"), ("Gold", "15")] {
+        let err = tiers.add_tier(tier, BigDecimal::from_str(percent).unwrap());
+        assert!(err.is_none(), "error adding tier {:?}", err);
+    }
+
+    let mut c = DetailCalculator::new();
+    c.set_discount_tiers(tiers);
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let uv = BigDecimal::from_str("50").unwrap();
+    let qty = BigDecimal::from_str("2").unwrap();
+
+    let bronze = c
+        .compute_for_tier(uv.clone(), qty.clone(), "Bronze", None)
+        .unwrap();
+    let gold = c
+        .compute_for_tier(uv.clone(), qty.clone(), "Gold", None)
+        .unwrap();
+
+    assert_eq!(
+        bronze.with_discount_values().net,
+        BigDecimal::from_str("95").unwrap()
+    );
+    assert_eq!(
+        gold.with_discount_values().net,
+        BigDecimal::from_str("85").unwrap()
+    );
+    assert_ne!(
+        bronze.with_discount_values().net,
+        gold.with_discount_values().net
+    );
+
+    assert!(c.compute_for_tier(uv, qty, "Platinum", None).is_err());
+}
+
+#[test]
+fn test_baggins_price_headroom() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let uv = BigDecimal::from_str("100.03").unwrap();
+    let qty = BigDecimal::from_str("1").unwrap();
+
+    let headroom = c.price_headroom(uv.clone(), qty.clone(), 2).unwrap();
+    assert_eq!(headroom, BigDecimal::from_str("0.01").unwrap());
+
+    let before = c.compute(uv.clone(), qty.clone(), None).unwrap();
+    let after = c.compute(&uv + &headroom, qty, None).unwrap();
+
+    assert_eq!(
+        after.with_discount_values().brute.round(2) - before.with_discount_values().brute.round(2),
+        BigDecimal::from_str("0.01").unwrap()
+    );
+}
+
+#[test]
+fn test_baggins_tax_holiday() {
+    use chrono::NaiveDate;
+
+    let mut c = DetailCalculator::new();
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let err = c.set_tax_holiday((
+        NaiveDate::from_ymd_opt(2024, 8, 3).unwrap(),
+        NaiveDate::from_ymd_opt(2024, 8, 4).unwrap(),
+    ));
+    assert!(err.is_none(), "error setting tax holiday {:?}", err);
+
+    let uv = BigDecimal::from_str("100").unwrap();
+    let qty = BigDecimal::from_str("1").unwrap();
+
+    let inside = c
+        .compute_on_date(
+            uv.clone(),
+            qty.clone(),
+            None,
+            NaiveDate::from_ymd_opt(2024, 8, 4).unwrap(),
+        )
+        .unwrap();
+    assert_eq!(inside.with_discount_values().tax, BigDecimal::from(0));
+    assert_eq!(
+        inside.with_discount_values().brute,
+        BigDecimal::from_str("100").unwrap()
+    );
+
+    let outside = c
+        .compute_on_date(
+            uv.clone(),
+            qty.clone(),
+            None,
+            NaiveDate::from_ymd_opt(2024, 8, 5).unwrap(),
+        )
+        .unwrap();
+    assert_eq!(
+        outside.with_discount_values().tax,
+        BigDecimal::from_str("18").unwrap()
+    );
+
+    // the holiday is kept by the configuration
+    let config = baggins::config::PricingConfig::from(&c);
+    assert_eq!(config.tax_holiday, c.tax_holiday());
+    let rebuilt = DetailCalculator::from_config(&config).unwrap();
+    assert!(rebuilt.config_eq(&c));
+    assert!(!rebuilt.config_eq(&DetailCalculator::new()));
+
+    c.clear_tax_holiday();
+    assert!(c.tax_holiday().is_none());
+    let inside = c
+        .compute_on_date(uv, qty, None, NaiveDate::from_ymd_opt(2024, 8, 4).unwrap())
+        .unwrap();
+    assert_eq!(
+        inside.with_discount_values().tax,
+        BigDecimal::from_str("18").unwrap()
+    );
+
+    let err = c.set_tax_holiday((
+        NaiveDate::from_ymd_opt(2024, 8, 4).unwrap(),
+        NaiveDate::from_ymd_opt(2024, 8, 3).unwrap(),
+    ));
+    assert!(
+        err.is_some(),
+        "a holiday ending before it starts should be rejected"
+    );
+    assert!(c.tax_holiday().is_none());
+}
+
+#[test]
+fn test_baggins_all_in_factor() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_discount_from_str("10.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let calc = c.compute_from_str("100", "1", None).unwrap();
+
+    // 100 - 10% = 90, + 18% = 106.2
+    assert_eq!(
+        c.all_in_factor(&calc),
+        BigDecimal::from_str("1.062").unwrap()
+    );
+}
+
+#[test]
+fn test_baggins_discounts_for_exact_percent() {
+    let nets = vec![
+        BigDecimal::from_str("10.04").unwrap(),
+        BigDecimal::from_str("10.04").unwrap(),
+    ];
+    let target = BigDecimal::from_str("12.5").unwrap();
+
+    // 12.5% of each line is 1.255, rounding each line gives 2.52 instead of the 2.51 of 20.08
+    let naive = nets.iter().fold(BigDecimal::from(0), |total, net| {
+        total + (net * &target / BigDecimal::from(100)).round(2)
+    });
+    assert_eq!(naive, BigDecimal::from_str("2.52").unwrap());
+
+    let discounts = baggins::discounts_for_exact_percent(&nets, target, 2).unwrap();
+    let total = discounts
+        .iter()
+        .fold(BigDecimal::from(0), |total, discount| total + discount);
+
+    assert_eq!(total, BigDecimal::from_str("2.51").unwrap());
+    assert_eq!(discounts[0], BigDecimal::from_str("1.25").unwrap());
+    assert_eq!(discounts[1], BigDecimal::from_str("1.26").unwrap());
+}
+
+#[test]
+fn test_baggins_compute_with_service() {
+    use baggins::tax::Taxer;
+
+    let mut c = DetailCalculator::new();
+    let err = c.add_tax_from_str("10.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let lines = vec![
+        (
+            BigDecimal::from_str("30").unwrap(),
+            BigDecimal::from_str("2").unwrap(),
+        ),
+        (
+            BigDecimal::from_str("40").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+        ),
+    ];
+
+    let mut service_tax = tax::TaxComputer::new();
+    let err = service_tax.add_tax_from_str("20.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    // a 10% service charge over the 100 of food
+    let service = baggins::ServiceCharge::new(BigDecimal::from_str("10").unwrap(), service_tax);
+
+    let invoice = c.compute_with_service(&lines, service, None).unwrap();
+
+    assert_eq!(invoice.lines.len(), 2);
+    assert_eq!(invoice.goods.net, BigDecimal::from_str("100").unwrap());
+    assert_eq!(invoice.goods.tax, BigDecimal::from_str("10").unwrap());
+    assert_eq!(invoice.service_net, BigDecimal::from_str("10").unwrap());
+    assert_eq!(invoice.service_tax, BigDecimal::from_str("2").unwrap());
+    assert_eq!(invoice.brute, BigDecimal::from_str("122").unwrap());
+}
+
+#[test]
+fn test_baggins_compute_from_brute_round_trip() {
+    let discounts = [
+        ("10.0", discount::Mode::Percentual),
+        ("5.0", discount::Mode::AmountLine),
+        ("1.5", discount::Mode::AmountUnit),
+    ];
+    let taxes = [
+        ("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual),
+        ("2.0", tax::Stage::OverTaxable, tax::Mode::AmountLine),
+        ("0.5", tax::Stage::OverTaxable, tax::Mode::AmountUnit),
+        ("3.0", tax::Stage::OverTax, tax::Mode::Percentual),
+        ("4.0", tax::Stage::OverTaxIgnorable, tax::Mode::Percentual),
+    ];
+    let epsilon = BigDecimal::from_str("0.0000000001").unwrap();
+
+    for (discount, discount_mode) in &discounts {
+        for (tax, stage, tax_mode) in &taxes {
+            for (uv, qty) in [("100", "1"), ("33.33", "3"), ("12.75", "7")] {
+                let mut c = DetailCalculator::new();
+                let err = c.add_discount_from_str(*discount, discount_mode.clone());
+                assert!(err.is_none(), "error adding discount {:?}", err);
+                let err = c.add_tax_from_str(*tax, stage.clone(), tax_mode.clone());
+                assert!(err.is_none(), "error adding tax {:?}", err);
+
+                let calc = c.compute_from_str(uv, qty, None).unwrap();
+                let recovered = c
+                    .compute_from_brute(
+                        calc.with_discount_values().brute.clone(),
+                        BigDecimal::from_str(qty).unwrap(),
+                        None,
+                    )
+                    .unwrap();
+
+                let pairs = [
+                    (
+                        &calc.with_discount_values().net,
+                        &recovered.with_discount_values().net,
+                    ),
+                    (
+                        &calc.with_discount_values().discount_value,
+                        &recovered.with_discount_values().discount_value,
+                    ),
+                    (
+                        &calc.with_discount_values().tax,
+                        &recovered.with_discount_values().tax,
+                    ),
+                    (
+                        &calc.with_discount_values().brute,
+                        &recovered.with_discount_values().brute,
+                    ),
+                    (
+                        &calc.without_discount_values().net,
+                        &recovered.without_discount_values().net,
+                    ),
+                ];
+
+                for (expected, actual) in pairs {
+                    assert!(
+                        (expected - actual).abs() < epsilon,
+                        "round trip of {} x {} with discount {} {} and tax {} {:?} {:?}: expected {} got {}",
+                        uv,
+                        qty,
+                        discount,
+                        discount_mode,
+                        tax,
+                        stage,
+                        tax_mode,
+                        expected,
+                        actual
+                    );
+                }
+            }
+        }
+    }
+
+    // the over tax stage is applied over the net plus the over taxable taxes of the whole line
+    let mut c = DetailCalculator::new();
+    let err = c.add_tax_from_str("10.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding over taxable tax {:?}", err);
+    let err = c.add_tax_from_str("5.0", tax::Stage::OverTax, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding over tax tax {:?}", err);
+
+    let recovered = c
+        .compute_from_brute(
+            BigDecimal::from_str("346.5").unwrap(),
+            BigDecimal::from_str("3").unwrap(),
+            None,
+        )
+        .unwrap();
+
+    assert_eq!(
+        recovered.with_discount_values().unit_value,
+        BigDecimal::from_str("100").unwrap()
+    );
+    assert_eq!(
+        recovered.with_discount_values().brute,
+        BigDecimal::from_str("346.5").unwrap()
+    );
+}
+
+#[test]
+fn test_baggins_compute_margin_protected() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_discount_from_str("40.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    // a cost of 70 with a 20% margin floor needs a net of at least 84, a 40% discount leaves 60
+    let (calc, capped) = c
+        .compute_margin_protected(
+            BigDecimal::from_str("100").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+            BigDecimal::from_str("70").unwrap(),
+            BigDecimal::from_str("20").unwrap(),
+            None,
+        )
+        .unwrap();
+
+    assert!(
+        capped,
+        "the discount should be trimmed to protect the margin"
+    );
+    assert_eq!(
+        calc.with_discount_values().discount_value,
+        BigDecimal::from_str("16").unwrap()
+    );
+    assert_eq!(
+        calc.with_discount_values().net,
+        BigDecimal::from_str("84").unwrap()
+    );
+
+    // a cost of 40 leaves room for the whole discount
+    let (calc, capped) = c
+        .compute_margin_protected(
+            BigDecimal::from_str("100").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+            BigDecimal::from_str("40").unwrap(),
+            BigDecimal::from_str("20").unwrap(),
+            None,
+        )
+        .unwrap();
+
+    assert!(!capped, "the discount should not be trimmed");
+    assert_eq!(
+        calc.with_discount_values().net,
+        BigDecimal::from_str("60").unwrap()
+    );
+}
+
+#[test]
+fn test_baggins_compute_with_gratuity() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_tax_from_str("10.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let lines = vec![(
+        BigDecimal::from_str("50").unwrap(),
+        BigDecimal::from_str("2").unwrap(),
+    )];
+
+    let taxable = c
+        .compute_with_gratuity(
+            &lines,
+            baggins::Gratuity::new(BigDecimal::from_str("15").unwrap(), true),
+            None,
+        )
+        .unwrap();
+
+    assert_eq!(taxable.goods.tax, BigDecimal::from_str("10").unwrap());
+    assert_eq!(taxable.gratuity_net, BigDecimal::from_str("15").unwrap());
+    assert_eq!(taxable.gratuity_tax, BigDecimal::from_str("1.5").unwrap());
+    assert_eq!(taxable.brute, BigDecimal::from_str("126.5").unwrap());
+
+    let untaxed = c
+        .compute_with_gratuity(
+            &lines,
+            baggins::Gratuity::new(BigDecimal::from_str("15").unwrap(), false),
+            None,
+        )
+        .unwrap();
+
+    assert_eq!(untaxed.gratuity_tax, BigDecimal::from(0));
+    assert_eq!(untaxed.brute, BigDecimal::from_str("125").unwrap());
+    assert_ne!(taxable.gratuity_tax, untaxed.gratuity_tax);
+}
+
+#[test]
+fn test_baggins_calculation_diff() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_discount_from_str("5.0", discount::Mode::AmountLine);
+    assert!(err.is_none(), "error adding amount line discount {:?}", err);
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let before = c.compute_from_str("100", "1", None).unwrap();
+    let after = c.compute_from_str("100", "2", None).unwrap();
+
+    let diff = before.diff(&after);
+
+    assert_eq!(diff.net, BigDecimal::from_str("100").unwrap());
+    assert_eq!(diff.tax, BigDecimal::from_str("18").unwrap());
+    assert_eq!(diff.brute, BigDecimal::from_str("118").unwrap());
+    assert_eq!(diff.discount_value, BigDecimal::from(0));
+    assert_eq!(diff.to_string(), "net +100.0, tax +18.0, brute +118.0");
+
+    let back = after.diff(&before);
+    assert_eq!(back.net, BigDecimal::from_str("-100").unwrap());
+    assert_eq!(back.to_string(), "net -100.0, tax -18.0, brute -118.0");
+}
+
+#[test]
+fn test_baggins_compute_checked() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_tax_from_str("200.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let (calc, warnings) = c
+        .compute_checked(
+            BigDecimal::from_str("100").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+            None,
+        )
+        .unwrap();
+
+    assert_eq!(
+        calc.with_discount_values().tax,
+        BigDecimal::from_str("200").unwrap()
+    );
+    assert_eq!(warnings, vec![baggins::Warning::TaxExceedsNet]);
+
+    let mut c = DetailCalculator::new();
+    let err = c.add_discount_from_str("60.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let (_, warnings) = c
+        .compute_checked(
+            BigDecimal::from_str("100").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+            None,
+        )
+        .unwrap();
+
+    assert_eq!(warnings, vec![baggins::Warning::DiscountExceedsHalfNet]);
+}
+
+#[test]
+fn test_baggins_compute_cash() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let cash = baggins::CashRounding::new(BigDecimal::from_str("0.05").unwrap());
+
+    // 100.017 * 1.18 = 118.02006
+    let (calc, cash_brute) = c
+        .compute_cash(
+            BigDecimal::from_str("100.017").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+            None,
+            &cash,
+        )
+        .unwrap();
+
+    assert_eq!(
+        calc.with_discount_values().brute.round(2),
+        BigDecimal::from_str("118.02").unwrap()
+    );
+    assert_eq!(cash_brute, BigDecimal::from_str("118.00").unwrap());
+
+    assert_eq!(
+        cash.round(&BigDecimal::from_str("118.03").unwrap())
+            .unwrap(),
+        BigDecimal::from_str("118.05").unwrap()
+    );
+    assert!(baggins::CashRounding::new(BigDecimal::from(0))
+        .round(&BigDecimal::from_str("118.03").unwrap())
+        .is_err());
+}
+
+#[test]
+fn test_baggins_compute_checked_fractional_percent() {
+    for (percent, suspicious) in [("0.1", true), ("10.0", false)] {
+        let mut c = DetailCalculator::new();
+        let err = c.add_discount_from_str(percent, discount::Mode::Percentual);
+        assert!(err.is_none(), "error adding percentual discount {:?}", err);
+
+        let (_, warnings) = c
+            .compute_checked(
+                BigDecimal::from_str("100").unwrap(),
+                BigDecimal::from_str("1").unwrap(),
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(
+            warnings.contains(&baggins::Warning::SuspiciousFractionalPercent),
+            suspicious,
+            "warnings {:?} for a {} percentual discount",
+            warnings,
+            percent
+        );
+    }
+}
+
+#[test]
+fn test_baggins_stage_totals() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_tax_from_str("10.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+    let err = c.add_tax_from_str("5.0", tax::Stage::OverTaxIgnorable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let calcs = vec![
+        c.compute_from_str("100", "1", None).unwrap(),
+        c.compute_from_str("50", "2", None).unwrap(),
+    ];
+
+    assert_eq!(
+        calcs[0].tax_breakdown().over_taxable,
+        BigDecimal::from_str("10").unwrap()
+    );
+    assert_eq!(
+        calcs[0].tax_breakdown().total(),
+        calcs[0].with_discount_values().tax
+    );
+
+    let totals = baggins::stage_totals(&calcs);
+
+    assert_eq!(totals.over_taxable, BigDecimal::from_str("20").unwrap());
+    assert_eq!(totals.over_tax, BigDecimal::from(0));
+    assert_eq!(
+        totals.over_tax_ignorable,
+        BigDecimal::from_str("10").unwrap()
+    );
+}
+
+#[test]
+fn test_baggins_tax_breakdown_rounded_and_snapped() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_tax_from_str("10.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding over taxable tax {:?}", err);
+    let err = c.add_tax_from_str("5.0", tax::Stage::OverTax, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding over tax tax {:?}", err);
+    c.set_rounding(baggins::RoundingTarget::Independent, 2);
+
+    // 1.005 + 0.55275 is 1.56 rounded, while the rounded stages add up to 1.55
+    let calc = c.compute_from_str("10.05", "1", None).unwrap();
+
+    assert_eq!(
+        calc.with_discount_values().tax,
+        BigDecimal::from_str("1.56").unwrap()
+    );
+    assert_eq!(
+        calc.tax_breakdown().total(),
+        calc.with_discount_values().tax
+    );
+    assert_eq!(
+        calc.tax_breakdown().over_tax,
+        BigDecimal::from_str("0.55").unwrap()
+    );
+
+    let mut c = DetailCalculator::new();
+    let err = c.add_tax_from_str("0.0001", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding over taxable tax {:?}", err);
+    c.set_epsilon_snap(Some(BigDecimal::from_str("0.01").unwrap()));
+
+    let calc = c.compute_from_str("1", "1", None).unwrap();
+
+    assert_eq!(calc.with_discount_values().tax, BigDecimal::from(0));
+    assert_eq!(calc.tax_breakdown().over_taxable, BigDecimal::from(0));
+}
+
+#[test]
+fn test_baggins_match_competitor() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    // beating 118 by 10% is a brute of 106.2, a net of 90 at 18%
+    let discount = c
+        .match_competitor(
+            BigDecimal::from_str("100").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+            BigDecimal::from_str("118").unwrap(),
+            BigDecimal::from_str("10").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(discount, BigDecimal::from_str("10").unwrap());
+
+    let err = c.add_discount(discount, discount::Mode::AmountLine);
+    assert!(err.is_none(), "error adding amount line discount {:?}", err);
+    let calc = c.compute_from_str("100", "1", None).unwrap();
+    assert_eq!(
+        calc.with_discount_values().brute,
+        BigDecimal::from_str("106.2").unwrap()
+    );
+
+    let discount = c
+        .match_competitor(
+            BigDecimal::from_str("100").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+            BigDecimal::from_str("200").unwrap(),
+            BigDecimal::from_str("10").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(discount, BigDecimal::from(0));
+}
+
+#[test]
+fn test_baggins_validate_monotonic_brute() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_discount_from_str("10.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let result = c.validate_monotonic_brute(BigDecimal::from_str("30").unwrap(), 20, None);
+    assert!(
+        result.is_ok(),
+        "a plain percentual discount is monotonic {:?}",
+        result
+    );
+
+    // rounding the discount to 50 makes 9 units discount 50 while 8 units discount nothing
+    let mut config = baggins::config::PricingConfig::from(&c);
+    config.discount_increment = Some(BigDecimal::from_str("50").unwrap());
+    let mut c = DetailCalculator::from_config(&config).unwrap();
+
+    let result = c.validate_monotonic_brute(BigDecimal::from_str("30").unwrap(), 20, None);
+    assert!(
+        result.is_err(),
+        "a coarse discount increment should be caught"
+    );
+    assert!(result.unwrap_err().to_string().contains("qty 9"));
+}
+
+#[test]
+fn test_baggins_explain_equation() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_discount_from_str("10", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+    let err = c.add_discount_from_str("1", discount::Mode::AmountUnit);
+    assert!(err.is_none(), "error adding amount unit discount {:?}", err);
+    let err = c.add_tax_from_str("16", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let equation = c.explain_equation(
+        BigDecimal::from_str("100").unwrap(),
+        BigDecimal::from_str("2").unwrap(),
+    );
+
+    assert_eq!(
+        equation,
+        "net = 100 × 2 − (100 × 2 × 10/100 + 1 × 2) ; tax = net × 16/100 ; brute = net + tax"
+    );
+
+    let c = DetailCalculator::new();
+    let equation = c.explain_equation(
+        BigDecimal::from_str("100").unwrap(),
+        BigDecimal::from_str("2").unwrap(),
+    );
+    assert_eq!(equation, "net = 100 × 2 ; tax = 0 ; brute = net + tax");
+}
+
+#[test]
+fn test_baggins_compute_optional() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_discount_from_str("5.0", discount::Mode::AmountLine);
+    assert!(err.is_none(), "error adding amount line discount {:?}", err);
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let free = c
+        .compute_optional(None, BigDecimal::from_str("3").unwrap(), None)
+        .unwrap();
+
+    let zero = BigDecimal::from(0);
+    assert_eq!(free.with_discount_values().net, zero);
+    assert_eq!(free.with_discount_values().tax, zero);
+    assert_eq!(free.with_discount_values().brute, zero);
+    assert_eq!(free.with_discount_values().discount_value, zero);
+    assert_eq!(free.with_discount_values().total_discount_percent, zero);
+    assert_eq!(free.without_discount_values().brute, zero);
+
+    let priced = c
+        .compute_optional(
+            Some(BigDecimal::from_str("10").unwrap()),
+            BigDecimal::from_str("3").unwrap(),
+            None,
+        )
+        .unwrap();
+    assert_eq!(
+        priced.with_discount_values().net,
+        BigDecimal::from_str("25").unwrap()
+    );
+}
+
+#[test]
+fn test_baggins_line_rounding_contribution() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_tax_from_str("10.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    // taxes of 1.006, 1.006 and 1.008 round to 3.03 line by line, 3.02 from the total
+    let calcs: Vec<baggins::Calculation> = ["10.06", "10.06", "10.08"]
+        .iter()
+        .map(|uv| c.compute_from_str(*uv, "1", None).unwrap())
+        .collect();
+
+    let contributions: Vec<BigDecimal> = calcs
+        .iter()
+        .map(|calc| c.line_rounding_contribution(calc, 2))
+        .collect();
+
+    assert_eq!(contributions[0], BigDecimal::from_str("0.004").unwrap());
+    assert_eq!(contributions[2], BigDecimal::from_str("0.002").unwrap());
+
+    let total = contributions
+        .iter()
+        .fold(BigDecimal::from(0), |total, contribution| {
+            total + contribution
+        });
+
+    assert_eq!(total, baggins::reconcile_tax(&calcs, 2));
+    assert_eq!(total, BigDecimal::from_str("0.01").unwrap());
+}
+
+#[test]
+fn test_baggins_assessable_cap_config_round_trip() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_tax_with_assessable_cap(
+        BigDecimal::from_str("5.0").unwrap(),
+        tax::Stage::OverTaxable,
+        Some(BigDecimal::from_str("100").unwrap()),
+    );
+    assert!(err.is_none(), "error adding capped tax {:?}", err);
+
+    let config = baggins::config::PricingConfig::from(&c);
+    let mut rebuilt = DetailCalculator::from_config(&config).unwrap();
+    assert_eq!(baggins::config::PricingConfig::from(&rebuilt), config);
+
+    let calc = rebuilt.compute_from_str("150", "1", None).unwrap();
+    assert_eq!(
+        calc.with_discount_values().tax,
+        BigDecimal::from_str("5").unwrap()
+    );
+}
+
+#[test]
+fn test_baggins_build_receipt() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let lines = [
+        baggins::LineInput::new(
+            BigDecimal::from_str("100").unwrap(),
+            BigDecimal::from_str("2").unwrap(),
+        ),
+        baggins::LineInput::new(
+            BigDecimal::from_str("50").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+        ),
+    ];
+
+    let receipt = c.build_receipt(&lines).unwrap();
+    assert_eq!(receipt.lines.len(), 2);
+    assert_eq!(receipt.tax_summary.len(), 1);
+    assert_eq!(receipt.totals.net, BigDecimal::from_str("250").unwrap());
+    assert_eq!(receipt.totals.tax, BigDecimal::from_str("45").unwrap());
+    assert_eq!(receipt.totals.brute, BigDecimal::from_str("295").unwrap());
+
+    let json = serde_json::to_value(&receipt).unwrap();
+    assert_eq!(json["lines"].as_array().unwrap().len(), 2);
+    assert_eq!(json["tax_summary"][0]["lines"], 2);
+    assert!(json["totals"].get("brute").is_some());
+}
+
+#[test]
+fn test_baggins_build_receipt_exempt_lines() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let mut exempt = baggins::LineInput::new(
+        BigDecimal::from_str("100").unwrap(),
+        BigDecimal::from_str("1").unwrap(),
+    );
+    exempt.tax_status = baggins::LineTaxStatus::Exempt;
+
+    let lines = [
+        baggins::LineInput::new(
+            BigDecimal::from_str("100").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+        ),
+        exempt,
+    ];
+
+    let receipt = c.build_receipt(&lines).unwrap();
+    let summary_tax = receipt
+        .tax_summary
+        .iter()
+        .fold(BigDecimal::from(0), |total, row| total + &row.tax);
+
+    assert_eq!(receipt.totals.tax, BigDecimal::from_str("18").unwrap());
+    assert_eq!(receipt.totals.tax, summary_tax);
+    assert_eq!(receipt.totals.brute, BigDecimal::from_str("218").unwrap());
+    assert_eq!(
+        receipt.lines[1].with_discount_values().tax,
+        BigDecimal::from(0)
+    );
+}
+
+#[test]
+fn test_baggins_compute_batch_collect() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding tax {:?}", err);
+
+    let lines = [
+        baggins::LineInput::new(
+            BigDecimal::from_str("100").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+        ),
+        baggins::LineInput::new(
+            BigDecimal::from_str("100").unwrap(),
+            BigDecimal::from_str("-1").unwrap(),
+        ),
+        baggins::LineInput::new(
+            BigDecimal::from_str("50").unwrap(),
+            BigDecimal::from_str("2").unwrap(),
+        ),
+    ];
+
+    let (calcs, errors) = c.compute_batch_collect(&lines);
+    assert_eq!(calcs.len(), 3);
+    assert_eq!(calcs.iter().filter(|calc| calc.is_some()).count(), 2);
+    assert!(calcs[1].is_none());
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, 1);
+}
+
+#[test]
+fn test_baggins_compute_with_breaks() {
+    let mut breaks = baggins::PriceBreaks::new();
+    let err = breaks.add_break(
+        BigDecimal::from_str("1").unwrap(),
+        BigDecimal::from_str("10").unwrap(),
+    );
+    assert!(err.is_none(), "error adding price break {:?}", err);
+    let err = breaks.add_break(
+        BigDecimal::from_str("10").unwrap(),
+        BigDecimal::from_str("9").unwrap(),
+    );
+    assert!(err.is_none(), "error adding price break {:?}", err);
+
+    let err = breaks.add_break(
+        BigDecimal::from_str("20").unwrap(),
+        BigDecimal::from_str("9.5").unwrap(),
+    );
+    assert!(
+        err.is_some(),
+        "a price growing with the quantity should be rejected"
+    );
+
+    let mut c = DetailCalculator::new();
+
+    let calc = c
+        .compute_with_breaks(&breaks, BigDecimal::from_str("9").unwrap(), None)
+        .unwrap();
+    assert_eq!(
+        calc.with_discount_values().unit_value,
+        BigDecimal::from_str("10").unwrap()
+    );
+    assert_eq!(
+        calc.with_discount_values().net,
+        BigDecimal::from_str("90").unwrap()
+    );
+
+    let calc = c
+        .compute_with_breaks(&breaks, BigDecimal::from_str("10").unwrap(), None)
+        .unwrap();
+    assert_eq!(
+        calc.with_discount_values().unit_value,
+        BigDecimal::from_str("9").unwrap()
+    );
+    assert_eq!(
+        calc.with_discount_values().net,
+        BigDecimal::from_str("90").unwrap()
+    );
+
+    let result = c.compute_with_breaks(&breaks, BigDecimal::from_str("0.5").unwrap(), None);
+    assert!(result.is_err(), "a quantity under every break should fail");
+}
+
+#[test]
+fn test_baggins_net_from_brute_and_discount() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_discount_from_str("10.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding discount {:?}", err);
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding tax {:?}", err);
+
+    let calc = c.compute_from_str("100", "2", Some("100")).unwrap();
+    let values = calc.with_discount_values();
+
+    let net = c
+        .net_from_brute_and_discount(
+            values.brute.clone(),
+            BigDecimal::from_str("2").unwrap(),
+            values.discount_value.clone(),
+        )
+        .unwrap();
+
+    assert_eq!(net, BigDecimal::from_str("200").unwrap());
+    assert_eq!(net, calc.without_discount_values().net);
+}
+
+#[test]
+fn test_baggins_compute_invoice() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_discount_from_str("5.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding discount {:?}", err);
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding tax {:?}", err);
+
+    let lines = [
+        baggins::LineInput::new(
+            BigDecimal::from_str("19.99").unwrap(),
+            BigDecimal::from_str("3").unwrap(),
+        ),
+        baggins::LineInput::new(
+            BigDecimal::from_str("7.5").unwrap(),
+            BigDecimal::from_str("4").unwrap(),
+        ),
+        baggins::LineInput::new(
+            BigDecimal::from_str("120").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+        ),
+    ];
+
+    let (calcs, totals) = c.compute_invoice(&lines).unwrap();
+    assert_eq!(calcs.len(), 3);
+    assert_eq!(totals, baggins::sum_calculations(&calcs));
+
+    let net: BigDecimal = calcs
+        .iter()
+        .map(|calc| &calc.with_discount_values().net)
+        .sum();
+    assert_eq!(totals.net, net);
+}
+
+#[test]
+fn test_baggins_compute_with_precision_log() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_discount_from_str("1", discount::Mode::AmountLine);
+    assert!(err.is_none(), "error adding discount {:?}", err);
+    // 100 / 7 percent, a division heavy tax
+    let err = c.add_tax_from_str(
+        "14.285714285714",
+        tax::Stage::OverTaxable,
+        tax::Mode::Percentual,
+    );
+    assert!(err.is_none(), "error adding tax {:?}", err);
+    let err = c.add_tax_from_str("10.0", tax::Stage::OverTax, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding tax {:?}", err);
+    c.set_scale_cap(Some(2));
+
+    let (calc, log) = c
+        .compute_with_precision_log(
+            BigDecimal::from_str("10").unwrap(),
+            BigDecimal::from_str("3").unwrap(),
+            None,
+        )
+        .unwrap();
+
+    assert_eq!(log.len(), 10);
+    assert_eq!(
+        calc.with_discount_values().net,
+        BigDecimal::from_str("29").unwrap()
+    );
+    // the calculation is capped to 2 decimals, the log keeps the growth before the cap
+    assert_eq!(
+        calc.with_discount_values().unit_value,
+        BigDecimal::from_str("9.67").unwrap()
+    );
+    // 29 / 3 can't be represented exactly, so the unit value takes the full precision
+    assert!(log[4] > 50, "unit value digits {:?}", log);
+    // 14.285714285714% of 29 is 4.14285714285706, the tax adds digits to the net
+    assert_eq!(log[5], 15, "over taxable digits {:?}", log);
+    // the over tax is a percent of the net plus the over taxable taxes, adding more digits
+    assert!(log[6] > log[5], "over tax digits {:?}", log);
+    assert!(log[8] > log[3], "tax digits {:?}", log);
+    assert!(
+        calc.with_discount_values().tax.digits() < log[8] as u64,
+        "capped tax {:?}",
+        log
+    );
+}
+
+#[test]
+fn test_baggins_exact_rational() {
+    let configure = |exact_rational: bool| {
+        let mut c = DetailCalculator::new();
+        let err = c.add_discount_from_str("7.0", discount::Mode::Percentual);
+        assert!(err.is_none(), "error adding percentual discount {:?}", err);
+        let err = c.add_discount_from_str("1.0", discount::Mode::AmountLine);
+        assert!(err.is_none(), "error adding amount line discount {:?}", err);
+        let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+        assert!(err.is_none(), "error adding percentual tax {:?}", err);
+        let err = c.add_tax_from_str("10.0", tax::Stage::OverTax, tax::Mode::Percentual);
+        assert!(err.is_none(), "error adding over tax {:?}", err);
+        c.set_scale_cap(Some(10));
+        c.set_exact_rational(exact_rational);
+        c
+    };
+
+    let brute = BigDecimal::from_str("100").unwrap();
+    let qty = BigDecimal::from_str("3").unwrap();
+
+    let mut rational = configure(true);
+    assert!(rational.exact_rational());
+    let calc = rational
+        .compute_from_brute(brute.clone(), qty.clone(), Some(BigDecimal::from(100)))
+        .unwrap();
+
+    // (100 / 1.298 + 1) × 100 / 93 / 3 rounded to the scale cap
+    let exact = BigDecimal::from_str("27.9719005252").unwrap();
+    assert_eq!(calc.without_discount_values().unit_value, exact);
+    assert_eq!(calc.without_discount_values().net, &exact * &qty);
+    // computing the recovered unit value gives back the brute at the scale cap
+    assert_eq!(calc.with_discount_values().brute.round(10), brute);
+
+    let mut naive = configure(false);
+    let naive_calc = naive
+        .compute_from_brute(brute, qty.clone(), Some(BigDecimal::from(100)))
+        .unwrap();
+
+    // each division of the naive mode expands the intermediate values
+    let (_, naive_scale) = naive_calc
+        .without_discount_values()
+        .net
+        .as_bigint_and_exponent();
+    assert!(naive_scale > 10, "naive net scale {}", naive_scale);
+    assert_ne!(naive_calc.without_discount_values().net, &exact * &qty);
+}
+
+#[test]
+fn test_baggins_exact_rational_rejects_like_naive() {
+    for exact_rational in [false, true] {
+        let mut c = DetailCalculator::new();
+        let err = c.add_tax_from_str("10.0", tax::Stage::OverTaxable, tax::Mode::AmountLine);
+        assert!(err.is_none(), "error adding amount line tax {:?}", err);
+        c.set_exact_rational(exact_rational);
+
+        // a brute lower than the amount line tax leaves a negative discounted value
+        let err = c
+            .compute_from_brute(
+                BigDecimal::from_str("5").unwrap(),
+                BigDecimal::from_str("1").unwrap(),
+                None,
+            )
+            .unwrap_err();
+
+        assert!(
+            matches!(
+                err,
+                baggins::BagginsError::Discount(discount::DiscountError::NegativeValue(_))
+            ),
+            "exact rational {} returned {}",
+            exact_rational,
+            err
+        );
+    }
+}
+
+#[test]
+fn test_baggins_reverse_from_calculation() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_discount_from_str("3.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding discount {:?}", err);
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding tax {:?}", err);
+    c.set_rounding(baggins::RoundingTarget::Independent, 2);
+
+    let original = c.compute_from_str("19.99", "3", None).unwrap();
+    let credit = c.reverse_from_calculation(&original);
+
+    assert_eq!(
+        credit.with_discount_values().tax,
+        -&original.with_discount_values().tax
+    );
+    assert_eq!(
+        credit.with_discount_values().brute,
+        -&original.with_discount_values().brute
+    );
+    assert_eq!(
+        credit.tax_breakdown().total(),
+        -original.tax_breakdown().total()
+    );
+    assert_eq!(
+        &credit.with_discount_values().net + &original.with_discount_values().net,
+        BigDecimal::from(0)
+    );
+    assert_eq!(
+        credit.with_discount_values().unit_value,
+        original.with_discount_values().unit_value
+    );
+}
+
+#[test]
+fn test_baggins_landed_cost() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_discount_from_str("10.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding discount {:?}", err);
+    let err = c.add_tax_from_str("5.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding tax {:?}", err);
+
+    // net 360, tax 18, duty 40 and freight 22 over 4 units
+    let landed = c
+        .landed_cost(
+            BigDecimal::from_str("100").unwrap(),
+            BigDecimal::from_str("4").unwrap(),
+            baggins::LandedCostInput::new(
+                BigDecimal::from_str("40").unwrap(),
+                BigDecimal::from_str("22").unwrap(),
+            ),
+            Some(BigDecimal::from_str("400").unwrap()),
+        )
+        .unwrap();
+    assert_eq!(landed, BigDecimal::from_str("110").unwrap());
+
+    let result = c.landed_cost(
+        BigDecimal::from_str("100").unwrap(),
+        BigDecimal::from_str("4").unwrap(),
+        baggins::LandedCostInput::new(
+            BigDecimal::from_str("-1").unwrap(),
+            BigDecimal::from_str("22").unwrap(),
+        ),
+        None,
+    );
+    assert!(result.is_err(), "a negative duty should be rejected");
+}
+
+#[test]
+fn test_baggins_config_eq() {
+    let mut first = DetailCalculator::new();
+    let err = first.add_discount_from_str("5.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding discount {:?}", err);
+    let err = first.add_discount_from_str("2.0", discount::Mode::AmountLine);
+    assert!(err.is_none(), "error adding discount {:?}", err);
+    let err = first.add_discount_from_str("3.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding discount {:?}", err);
+    let err = first.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding tax {:?}", err);
+    let err = first.add_tax_from_str("1.5", tax::Stage::OverTax, tax::Mode::AmountUnit);
+    assert!(err.is_none(), "error adding tax {:?}", err);
+
+    let mut second = DetailCalculator::new();
+    let err = second.add_tax_from_str("1.5", tax::Stage::OverTax, tax::Mode::AmountUnit);
+    assert!(err.is_none(), "error adding tax {:?}", err);
+    let err = second.add_discount_from_str("3.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding discount {:?}", err);
+    let err = second.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding tax {:?}", err);
+    let err = second.add_discount_from_str("2.0", discount::Mode::AmountLine);
+    assert!(err.is_none(), "error adding discount {:?}", err);
+    let err = second.add_discount_from_str("5.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding discount {:?}", err);
+
+    assert!(first.config_eq(&second));
+    assert!(second.config_eq(&first));
+
+    let err = second.add_tax_from_str("1.0", tax::Stage::OverTaxIgnorable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding tax {:?}", err);
+    assert!(!first.config_eq(&second));
+
+    let mut third = DetailCalculator::new();
+    let err = third.add_discount_from_str("8.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding discount {:?}", err);
+    let err = third.add_discount_from_str("2.0", discount::Mode::AmountLine);
+    assert!(err.is_none(), "error adding discount {:?}", err);
+    let err = third.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding tax {:?}", err);
+    let err = third.add_tax_from_str("1.5", tax::Stage::OverTax, tax::Mode::AmountUnit);
+    assert!(err.is_none(), "error adding tax {:?}", err);
+    assert!(first.config_eq(&third));
+
+    third.set_rounding(baggins::RoundingTarget::Brute, 2);
+    assert!(!first.config_eq(&third));
+}
+
+#[test]
+fn test_baggins_compute_invoice_with_order_discount() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding tax {:?}", err);
+
+    let lines = [
+        baggins::LineInput::new(
+            BigDecimal::from_str("100").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+        ),
+        baggins::LineInput::new(
+            BigDecimal::from_str("33.33").unwrap(),
+            BigDecimal::from_str("3").unwrap(),
+        ),
+    ];
+
+    let (calcs, totals) = c
+        .compute_invoice_with_order_discount(&lines, BigDecimal::from_str("10").unwrap())
+        .unwrap();
+
+    // 10% of 199.99 is 19.999, allocated as 10.00 and 9.99 plus the 0.01 residue
+    assert_eq!(
+        totals.discount_value,
+        BigDecimal::from_str("20.00").unwrap()
+    );
+    assert_eq!(
+        calcs[0].with_discount_values().discount_value,
+        BigDecimal::from_str("10").unwrap()
+    );
+    assert_eq!(
+        calcs[1].with_discount_values().discount_value,
+        BigDecimal::from_str("10").unwrap()
+    );
+    assert_eq!(
+        calcs[1].with_discount_values().net,
+        BigDecimal::from_str("89.99").unwrap()
+    );
+
+    let rate = BigDecimal::from_str("0.18").unwrap();
+    for calc in &calcs {
+        assert_eq!(
+            calc.with_discount_values().tax,
+            &calc.with_discount_values().net * &rate
+        );
+    }
+    assert_eq!(totals, baggins::sum_calculations(&calcs));
+}
+
+#[test]
+fn test_baggins_order_discount_over_max_discount_allowed() {
+    let mut c = DetailCalculator::new();
+
+    let mut capped = baggins::LineInput::new(
+        BigDecimal::from_str("100").unwrap(),
+        BigDecimal::from_str("1").unwrap(),
+    );
+    capped.max_discount_allowed = Some(BigDecimal::from_str("5").unwrap());
+
+    let lines = [
+        capped,
+        baggins::LineInput::new(
+            BigDecimal::from_str("50").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+        ),
+    ];
+
+    // the 10% order discount allocates 10 to the first line, over its max of 5
+    let result = c.compute_invoice_with_order_discount(&lines, BigDecimal::from_str("10").unwrap());
+    assert!(matches!(result, Err(baggins::BagginsError::Other(_))));
+
+    let (calcs, _) = c
+        .compute_invoice_with_order_discount(&lines, BigDecimal::from_str("5").unwrap())
+        .unwrap();
+    assert_eq!(
+        calcs[0].with_discount_values().discount_value,
+        BigDecimal::from_str("5").unwrap()
+    );
+}
+
+#[test]
+fn test_baggins_compute_keeps_handler_error_variant() {
+    let mut c = DetailCalculator::new();
+    c.add_discount_from_str("40", discount::Mode::Percentual);
+
+    let err = c
+        .compute(
+            BigDecimal::from_str("100").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+            Some(BigDecimal::from_str("10").unwrap()),
+        )
+        .unwrap_err();
+
+    assert!(matches!(
+        err,
+        baggins::BagginsError::Discount(discount::DiscountError::OverMaxDiscount(_))
+    ));
+
+    let inner = match &err {
+        baggins::BagginsError::Discount(inner) => inner.to_string(),
+        _ => unreachable!(),
+    };
+    assert_eq!(err.to_string(), inner);
+
+    let mut c = DetailCalculator::new();
+    c.add_tax_from_str("19", tax::Stage::OverTaxable, tax::Mode::Percentual);
+
+    let err = c
+        .compute_from_brute(
+            BigDecimal::from_str("119").unwrap(),
+            BigDecimal::from_str("-1").unwrap(),
+            None,
+        )
+        .unwrap_err();
+
+    assert!(matches!(err, baggins::BagginsError::Tax(_)));
+}
+
+#[test]
+fn test_baggins_from_config_keeps_handler_error_variant() {
+    let c = DetailCalculator::new();
+
+    let mut config = baggins::config::PricingConfig::from(&c);
+    config.max_total_discount_pct = Some(BigDecimal::from_str("101").unwrap());
+    assert!(matches!(
+        DetailCalculator::from_config(&config),
+        Err(baggins::BagginsError::Discount(
+            discount::DiscountError::OverMaxDiscount(_)
+        ))
+    ));
+
+    let mut config = baggins::config::PricingConfig::from(&c);
+    config.over_tax.percentual = BigDecimal::from_str("-1").unwrap();
+    assert!(matches!(
+        DetailCalculator::from_config(&config),
+        Err(baggins::BagginsError::Tax(tax::TaxError::NegativeValue(_)))
+    ));
+
+    let add = || -> baggins::BagginsResult<()> {
+        let mut c = DetailCalculator::new();
+        c.try_add_discount_from_str("-1", discount::Mode::Percentual)?;
+        Ok(())
+    };
+    assert!(matches!(
+        add(),
+        Err(baggins::BagginsError::Discount(
+            discount::DiscountError::NegativeValue(_)
+        ))
+    ));
+}
+
+#[test]
+fn test_baggins_errors_compare_with_eq() {
+    let c = DetailCalculator::new();
+
+    assert_eq!(
+        c.net_from_brute_and_discount(
+            BigDecimal::from_str("100").unwrap(),
+            BigDecimal::from_str("-1").unwrap(),
+            BigDecimal::from_str("0").unwrap(),
+        )
+        .unwrap_err(),
+        baggins::BagginsError::NegativeQty("negative qty -1".to_string())
+    );
+
+    let mut c = DetailCalculator::new();
+    c.add_discount_from_str("40", discount::Mode::Percentual);
+
+    let err = c
+        .compute(
+            BigDecimal::from_str("100").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+            Some(BigDecimal::from_str("10").unwrap()),
+        )
+        .unwrap_err();
+
+    assert_eq!(
+        err,
+        c.compute(
+            BigDecimal::from_str("100").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+            Some(BigDecimal::from_str("10").unwrap()),
+        )
+        .unwrap_err()
+    );
+    assert_ne!(err, baggins::BagginsError::Other(err.to_string()));
+}
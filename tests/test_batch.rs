@@ -0,0 +1,33 @@
+#![cfg(feature = "csv")]
+
+use baggins::{discount, tax, Calculator, DetailCalculator};
+
+#[test]
+fn test_batch_process_csv() {
+    let mut c = DetailCalculator::new();
+    let err = c.add_discount_from_str("10.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+    let err = c.add_tax_from_str("18.0", tax::Stage::OverTaxable, tax::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let input = "unit_value,qty\n100,1\n50,2\n20, 5\n";
+    let mut output = Vec::new();
+
+    c.process_csv(input.as_bytes(), &mut output).unwrap();
+
+    let output = String::from_utf8(output).unwrap();
+    let rows: Vec<&str> = output.lines().collect();
+
+    assert_eq!(
+        rows,
+        vec![
+            "unit_value,qty,net,tax,brute",
+            "100,1,90,16.2,106.2",
+            "50,2,90,16.2,106.2",
+            "20,5,90,16.2,106.2",
+        ]
+    );
+
+    let err = c.process_csv("unit_value,qty\nabc,1\n".as_bytes(), Vec::new());
+    assert!(err.is_err(), "an invalid unit value should fail");
+}
@@ -20,3 +20,384 @@ let err = c.add_tax_from_str(
 
 assert!(err.is_none(), "error adding percentual 16% tax {:?}", err);
 }
+
+fn build_discounter() -> Result<discount::DiscountComputer, discount::DiscountError<String>> {
+    use baggins::discount::Discounter;
+
+    let mut d = discount::DiscountComputer::new();
+    d.try_add_discount_from_str("10.0", discount::Mode::Percentual)?;
+    d.try_add_discount_from_f64(1.5, discount::Mode::AmountLine)?;
+    d.try_add_discount_from_str("120.0", discount::Mode::Percentual)?;
+
+    Ok(d)
+}
+
+#[test]
+fn test_try_add_discount() {
+    match build_discounter() {
+        Ok(_) => panic!("expected an over max discount error"),
+        Err(discount::DiscountError::OverMaxDiscount(_)) => {}
+        Err(e) => panic!("unexpected error {e}"),
+    }
+}
+
+#[test]
+fn test_discount_stacking() {
+    use baggins::discount::{DiscountComputer, Discounter, Stacking};
+    use bigdecimal::BigDecimal;
+    use std::str::FromStr;
+
+    let mut d = DiscountComputer::new();
+    assert_eq!(d.stacking(), Stacking::StackAll);
+
+    let err = d.add_discount_from_str("5.0", discount::Mode::AmountLine);
+    assert!(err.is_none(), "error adding amount line discount {:?}", err);
+    let err = d.add_discount_from_str("10.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+    let err = d.add_discount_from_str("2.0", discount::Mode::AmountUnit);
+    assert!(err.is_none(), "error adding amount unit discount {:?}", err);
+    assert_eq!(d.discounts().len(), 3);
+
+    let stacked = d.compute_from_str("100.0", "3.0", Some("1000.0")).unwrap();
+    assert_eq!(stacked.0, BigDecimal::from_str("41").unwrap());
+
+    d.set_stacking(Stacking::BestSingle);
+    let best = d.compute_from_str("100.0", "3.0", Some("1000.0")).unwrap();
+    assert_eq!(best.0, BigDecimal::from_str("30").unwrap());
+
+    d.set_stacking(Stacking::FirstMatch);
+    let first = d.compute_from_str("100.0", "3.0", Some("1000.0")).unwrap();
+    assert_eq!(first.0, BigDecimal::from_str("5").unwrap());
+}
+
+#[test]
+fn test_discount_is_viable() {
+    use baggins::discount::{DiscountComputer, Discounter};
+    use bigdecimal::BigDecimal;
+    use std::str::FromStr;
+
+    let mut sane = DiscountComputer::new();
+    let err = sane.add_discount_from_str("10.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+    let err = sane.add_discount_from_str("1.0", discount::Mode::AmountUnit);
+    assert!(err.is_none(), "error adding amount unit discount {:?}", err);
+
+    assert!(sane.is_viable(
+        BigDecimal::from_str("100.0").unwrap(),
+        BigDecimal::from_str("2.0").unwrap()
+    ));
+
+    let mut broken = DiscountComputer::new();
+    let err = broken.add_discount_from_str("60.0", discount::Mode::AmountLine);
+    assert!(err.is_none(), "error adding amount line discount {:?}", err);
+    let err = broken.add_discount_from_str("30.0", discount::Mode::AmountUnit);
+    assert!(err.is_none(), "error adding amount unit discount {:?}", err);
+
+    assert!(!broken.is_viable(
+        BigDecimal::from_str("100.0").unwrap(),
+        BigDecimal::from_str("2.0").unwrap()
+    ));
+}
+
+#[test]
+fn test_add_discount_with_percent_symbol() {
+    use baggins::discount::{DiscountComputer, Discounter};
+    use bigdecimal::BigDecimal;
+    use std::str::FromStr;
+
+    let mut d = DiscountComputer::new();
+
+    let err = d.add_discount_from_str("10%", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding 10% discount {:?}", err);
+    let err = d.add_discount_from_str("10 %", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding 10 % discount {:?}", err);
+
+    assert_eq!(
+        d.discounts()[0].1,
+        BigDecimal::from_str("10").unwrap(),
+        "10% should be parsed as 10"
+    );
+    assert_eq!(d.discounts()[1].1, BigDecimal::from_str("10").unwrap());
+
+    let err = d.add_discount_from_str("10%", discount::Mode::AmountLine);
+    assert!(
+        matches!(err, Some(discount::DiscountError::InvalidDecimal(_))),
+        "percent symbol should not be accepted for amount discounts"
+    );
+}
+
+#[test]
+fn test_un_discount_rejects_implausible_discountable() {
+    use baggins::discount::{DiscountComputer, Discounter};
+
+    let mut d = DiscountComputer::new();
+
+    let err = d.add_discount_from_str("60", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+    let err = d.add_discount_from_str("60", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+
+    let result = d.un_discount_from_str("40", "1");
+    assert!(
+        matches!(result, Err(discount::DiscountError::Other(_))),
+        "a cumulated 120% discount should not be removable {:?}",
+        result
+    );
+}
+
+#[test]
+fn test_amount_line_discount_does_not_scale_with_qty() {
+    use baggins::discount::{DiscountComputer, Discounter};
+    use bigdecimal::BigDecimal;
+    use std::str::FromStr;
+
+    let mut d = DiscountComputer::new();
+
+    let err = d.add_discount_from_str("5.0", discount::Mode::AmountLine);
+    assert!(err.is_none(), "error adding amount line discount {:?}", err);
+
+    for qty in ["1", "10"] {
+        let (discount_value, _) = d.compute_from_str("100", qty, Some("1000")).unwrap();
+        assert_eq!(
+            discount_value,
+            BigDecimal::from_str("5").unwrap(),
+            "amount line discount should be 5 at qty {}",
+            qty
+        );
+    }
+}
+
+#[test]
+fn test_override_unit_price() {
+    use baggins::discount::{DiscountComputer, Discounter};
+    use bigdecimal::BigDecimal;
+    use std::str::FromStr;
+
+    let mut d = DiscountComputer::new();
+
+    let err = d.add_discount_from_str("5.0", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+    let err = d.set_override_unit_price(Some(BigDecimal::from_str("80").unwrap()));
+    assert!(err.is_none(), "error setting override price {:?}", err);
+
+    let (discount_value, _) = d.compute_from_str("100", "1", None).unwrap();
+    assert_eq!(discount_value, BigDecimal::from_str("20").unwrap());
+
+    let (discount_value, _) = d.compute_from_str("100", "3", Some("1000")).unwrap();
+    assert_eq!(discount_value, BigDecimal::from_str("60").unwrap());
+
+    let result = d.compute_from_str("70", "1", None);
+    assert!(
+        matches!(result, Err(discount::DiscountError::Other(_))),
+        "an override over the unit value should fail {:?}",
+        result
+    );
+
+    let err = d.set_override_unit_price(Some(BigDecimal::from_str("-1").unwrap()));
+    assert!(matches!(
+        err,
+        Some(discount::DiscountError::NegativeValue(_))
+    ));
+
+    let err = d.set_override_unit_price(None);
+    assert!(err.is_none());
+    let (discount_value, _) = d.compute_from_str("100", "1", None).unwrap();
+    assert_eq!(discount_value, BigDecimal::from_str("5").unwrap());
+}
+
+#[test]
+fn test_discount_increment() {
+    use baggins::discount::{DiscountComputer, Discounter};
+    use bigdecimal::BigDecimal;
+    use std::str::FromStr;
+
+    let mut d = DiscountComputer::new();
+
+    let err = d.add_discount_from_str("22.26", discount::Mode::AmountLine);
+    assert!(err.is_none(), "error adding amount line discount {:?}", err);
+    let err = d.set_discount_increment(Some(BigDecimal::from_str("0.05").unwrap()));
+    assert!(err.is_none(), "error setting discount increment {:?}", err);
+
+    let (discount_value, _) = d.compute_from_str("100", "1", None).unwrap();
+    assert_eq!(discount_value, BigDecimal::from_str("22.25").unwrap());
+
+    let err = d.set_discount_increment(Some(BigDecimal::from_str("0").unwrap()));
+    assert!(err.is_some(), "a zero increment should be rejected");
+}
+
+#[test]
+fn test_max_total_discount_pct() {
+    use baggins::discount::{DiscountComputer, Discounter};
+    use bigdecimal::BigDecimal;
+    use std::str::FromStr;
+
+    let mut d = DiscountComputer::new();
+
+    let err = d.add_discount_from_str("20", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+    let err = d.set_max_total_discount_pct(Some(BigDecimal::from_str("25").unwrap()));
+    assert!(
+        err.is_none(),
+        "error setting max total discount percent {:?}",
+        err
+    );
+
+    let (discount_value, _) = d.compute_from_str("100", "1", None).unwrap();
+    assert_eq!(discount_value, BigDecimal::from_str("20").unwrap());
+
+    let err = d.add_discount_from_str("10", discount::Mode::AmountLine);
+    assert!(err.is_none(), "error adding amount line discount {:?}", err);
+
+    let result = d.compute_from_str("100", "1", None);
+    assert!(matches!(
+        result,
+        Err(discount::DiscountError::OverMaxDiscount(_))
+    ));
+
+    let err = d.set_max_total_discount_pct(Some(BigDecimal::from_str("101").unwrap()));
+    assert!(err.is_some(), "a cap over 100 should be rejected");
+}
+
+#[test]
+fn test_best_discount() {
+    use baggins::discount::{BetterOrWorse, DiscountComputer};
+    use bigdecimal::BigDecimal;
+    use std::str::FromStr;
+
+    let d = DiscountComputer::new();
+    let pct = BigDecimal::from_str("10").unwrap();
+    let amount = BigDecimal::from_str("15").unwrap();
+
+    // 10% of 200 is 20, over the 15 off
+    let discount = d
+        .best_discount(
+            BigDecimal::from_str("100").unwrap(),
+            BigDecimal::from_str("2").unwrap(),
+            pct.clone(),
+            amount.clone(),
+            BetterOrWorse::Better,
+        )
+        .unwrap();
+    assert_eq!(discount, BigDecimal::from_str("20").unwrap());
+
+    // 10% of 100 is 10, under the 15 off
+    let discount = d
+        .best_discount(
+            BigDecimal::from_str("50").unwrap(),
+            BigDecimal::from_str("2").unwrap(),
+            pct.clone(),
+            amount.clone(),
+            BetterOrWorse::Better,
+        )
+        .unwrap();
+    assert_eq!(discount, BigDecimal::from_str("15").unwrap());
+
+    let discount = d
+        .best_discount(
+            BigDecimal::from_str("50").unwrap(),
+            BigDecimal::from_str("2").unwrap(),
+            pct,
+            amount,
+            BetterOrWorse::Worse,
+        )
+        .unwrap();
+    assert_eq!(discount, BigDecimal::from_str("10").unwrap());
+}
+
+#[test]
+fn test_min_discount_pct() {
+    use baggins::discount::{DiscountComputer, Discounter};
+    use bigdecimal::BigDecimal;
+    use std::str::FromStr;
+
+    let mut d = DiscountComputer::new();
+
+    let err = d.add_discount_from_str("3", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+    let err = d.set_min_discount_pct(Some(BigDecimal::from_str("5").unwrap()));
+    assert!(
+        err.is_none(),
+        "error setting min discount percent {:?}",
+        err
+    );
+
+    let (discount_value, _) = d.compute_from_str("100", "2", Some("200")).unwrap();
+    assert_eq!(discount_value, BigDecimal::from_str("10").unwrap());
+
+    let err = d.add_discount_from_str("7", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+
+    let (discount_value, _) = d.compute_from_str("100", "2", Some("200")).unwrap();
+    assert_eq!(discount_value, BigDecimal::from_str("20").unwrap());
+}
+
+#[test]
+fn test_min_discount_pct_with_increment() {
+    use baggins::discount::{DiscountComputer, Discounter};
+    use bigdecimal::BigDecimal;
+    use std::str::FromStr;
+
+    let mut d = DiscountComputer::new();
+
+    let err = d.set_min_discount_pct(Some(BigDecimal::from_str("22.26").unwrap()));
+    assert!(
+        err.is_none(),
+        "error setting min discount percent {:?}",
+        err
+    );
+    let err = d.set_discount_increment(Some(BigDecimal::from_str("0.05").unwrap()));
+    assert!(err.is_none(), "error setting discount increment {:?}", err);
+
+    // the nearest step of the 22.26 floor is 22.25, under the floor, so it is rounded up
+    let (discount_value, _) = d.compute_from_str("100", "1", Some("100")).unwrap();
+    assert_eq!(discount_value, BigDecimal::from_str("22.30").unwrap());
+
+    // a discount over the floor is still rounded to the nearest step
+    let err = d.add_discount_from_str("30.01", discount::Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual discount {:?}", err);
+    let (discount_value, _) = d.compute_from_str("100", "1", Some("100")).unwrap();
+    assert_eq!(discount_value, BigDecimal::from_str("30").unwrap());
+}
+
+#[test]
+fn test_discount_error_is_std_error() {
+    use std::error::Error;
+
+    let errors = [
+        discount::DiscountError::NegativeValue("negative".to_string()),
+        discount::DiscountError::OverMaxDiscount("over max".to_string()),
+        discount::DiscountError::InvalidDecimal("invalid decimal".to_string()),
+        discount::DiscountError::InvalidDiscountMode("invalid mode".to_string()),
+        discount::DiscountError::Other("other".to_string()),
+    ];
+
+    for err in &errors {
+        let err: &dyn Error = err;
+        assert!(err.source().is_none());
+        assert!(!err.to_string().is_empty());
+    }
+
+    let boxed: Box<dyn Error> =
+        Box::new(discount::DiscountError::OverMaxDiscount("120%".to_string()));
+    assert!(boxed.to_string().contains("120%"));
+}
+
+#[test]
+fn test_discount_errors_compare_with_eq() {
+    use bigdecimal::BigDecimal;
+    use discount::{DiscountComputer, DiscountError, Discounter, Mode};
+    use std::str::FromStr;
+
+    let mut d = DiscountComputer::new();
+
+    assert_eq!(
+        d.add_discount(BigDecimal::from_str("-5").unwrap(), Mode::Percentual),
+        Some(DiscountError::NegativeValue(
+            "negative discount -5".to_string()
+        ))
+    );
+    assert_ne!(
+        DiscountError::NegativeValue("a".to_string()),
+        DiscountError::Other("a".to_string())
+    );
+}
@@ -1,6 +1,6 @@
 use std::str::FromStr;
 
-use baggins::tax::{Mode, Stage, TaxComputer, Taxer};
+use baggins::tax::{Mode, Stage, Stager, TaxComputer, TaxStage, Taxer};
 use bigdecimal::BigDecimal;
 
 #[test]
@@ -59,3 +59,834 @@ fn test_tax_computer_calculate_over_taxable_f64() {
         }
     }
 }
+
+#[test]
+fn test_tax_computer_dual_price() {
+    let mut taxer = TaxComputer::new();
+
+    let err = taxer.add_tax_from_str("18.0", Stage::OverTaxable, Mode::Percentual);
+    assert!(err.is_none(), "error adding 18% tax {:?}", err);
+
+    let (exclusive, inclusive) = taxer
+        .dual_price(
+            BigDecimal::from_str("100.0").unwrap(),
+            BigDecimal::from_str("1.0").unwrap(),
+        )
+        .unwrap();
+
+    assert_eq!(exclusive, BigDecimal::from_str("100").unwrap());
+    assert_eq!(inclusive, BigDecimal::from_str("118").unwrap());
+}
+
+#[test]
+fn test_tax_computer_try_add_tax() -> Result<(), baggins::tax::TaxError<String>> {
+    let mut taxer = TaxComputer::new();
+
+    taxer.try_add_tax_from_str("18.0", Stage::OverTaxable, Mode::Percentual)?;
+    taxer.try_add_tax_from_f64(0.5, Stage::OverTaxable, Mode::AmountUnit)?;
+    taxer.try_add_tax(BigDecimal::from_str("1.0").unwrap(), Stage::OverTax, Mode::AmountLine)?;
+
+    assert!(taxer
+        .try_add_tax_from_str("-1.0", Stage::OverTaxable, Mode::Percentual)
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_tax_stage_from_taxes() {
+    let stage = TaxStage::from_taxes(&[
+        (Mode::Percentual, BigDecimal::from_str("18.0").unwrap()),
+        (Mode::AmountUnit, BigDecimal::from_str("0.5").unwrap()),
+        (Mode::Percentual, BigDecimal::from_str("2.0").unwrap()),
+    ])
+    .unwrap();
+
+    assert_eq!(stage.percent(), BigDecimal::from_str("20").unwrap());
+    assert_eq!(stage.amount_by_qty(), BigDecimal::from_str("0.5").unwrap());
+    assert_eq!(stage.amount_line(), BigDecimal::from_str("0").unwrap());
+
+    let err = TaxStage::from_taxes(&[(Mode::AmountLine, BigDecimal::from_str("-1.0").unwrap())]);
+    assert!(err.is_err(), "negative tax should not be accepted");
+}
+
+#[test]
+fn test_tax_delta() {
+    let mut from = TaxComputer::new();
+    let err = from.add_tax_from_str("18.0", Stage::OverTaxable, Mode::Percentual);
+    assert!(err.is_none(), "error adding 18% tax {:?}", err);
+
+    let mut to = TaxComputer::new();
+    let err = to.add_tax_from_str("10.0", Stage::OverTaxable, Mode::Percentual);
+    assert!(err.is_none(), "error adding 10% tax {:?}", err);
+
+    let delta = baggins::tax::tax_delta(
+        BigDecimal::from_str("100.0").unwrap(),
+        BigDecimal::from_str("2.0").unwrap(),
+        &mut from,
+        &mut to,
+    )
+    .unwrap();
+
+    assert_eq!(delta, BigDecimal::from_str("-16").unwrap());
+}
+
+#[test]
+fn test_tax_computer_add_tax_with_percent_symbol() {
+    let mut taxer = TaxComputer::new();
+
+    let err = taxer.add_tax_from_str("10%", Stage::OverTaxable, Mode::Percentual);
+    assert!(err.is_none(), "error adding 10% tax {:?}", err);
+    let err = taxer.add_tax_from_str("10 %", Stage::OverTaxable, Mode::Percentual);
+    assert!(err.is_none(), "error adding 10 % tax {:?}", err);
+
+    assert_eq!(taxer.over_taxables().percent(), BigDecimal::from_str("20").unwrap());
+
+    let err = taxer.add_tax_from_str("10%", Stage::OverTaxable, Mode::AmountUnit);
+    assert!(err.is_some(), "percent symbol should not be accepted for amount taxes");
+}
+
+#[test]
+fn test_tax_computer_net_for_round_brute() {
+    let mut taxer = TaxComputer::new();
+
+    let err = taxer.add_tax_from_str("18.0", Stage::OverTaxable, Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let net = taxer
+        .net_for_round_brute(
+            BigDecimal::from_str("2.00").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+            2,
+        )
+        .unwrap();
+
+    let tax = taxer
+        .tax_net(net.clone(), BigDecimal::from_str("1").unwrap())
+        .unwrap();
+    assert_eq!(
+        (&net + &tax).round(2),
+        BigDecimal::from_str("2.00").unwrap()
+    );
+
+    let net = taxer
+        .net_for_round_brute(
+            BigDecimal::from_str("11.80").unwrap(),
+            BigDecimal::from_str("2").unwrap(),
+            2,
+        )
+        .unwrap();
+
+    assert_eq!(net, BigDecimal::from_str("10.00").unwrap());
+}
+
+#[test]
+fn test_tax_layer() {
+    let mut national = TaxComputer::new();
+    let err = national.add_tax_from_str("18.0", Stage::OverTaxable, Mode::Percentual);
+    assert!(err.is_none(), "error adding national tax {:?}", err);
+
+    let mut municipal = TaxComputer::new();
+    let err = municipal.add_tax_from_str("2.0", Stage::OverTax, Mode::Percentual);
+    assert!(err.is_none(), "error adding municipal tax {:?}", err);
+
+    let mut layered = baggins::tax::layer(&national, &municipal);
+
+    assert_eq!(
+        layered.over_taxables().percent(),
+        BigDecimal::from_str("18").unwrap()
+    );
+    assert_eq!(
+        layered.over_taxes().percent(),
+        BigDecimal::from_str("2").unwrap()
+    );
+
+    let tax = layered
+        .tax(
+            BigDecimal::from_str("100.0").unwrap(),
+            BigDecimal::from_str("1.0").unwrap(),
+        )
+        .unwrap();
+
+    assert_eq!(tax, BigDecimal::from_str("20.36").unwrap());
+}
+
+#[test]
+fn test_tax_computer_tax_on_date() {
+    use chrono::NaiveDate;
+
+    let mut taxer = TaxComputer::new();
+
+    let err = taxer.add_tax_dated(
+        BigDecimal::from_str("18").unwrap(),
+        Stage::OverTaxable,
+        Mode::Percentual,
+        NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
+    );
+    assert!(err.is_none(), "error adding dated tax {:?}", err);
+    let err = taxer.add_tax_dated(
+        BigDecimal::from_str("19").unwrap(),
+        Stage::OverTaxable,
+        Mode::Percentual,
+        NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+    );
+    assert!(err.is_none(), "error adding dated tax {:?}", err);
+
+    let unit_value = BigDecimal::from_str("100").unwrap();
+    let qty = BigDecimal::from_str("1").unwrap();
+
+    let tax = taxer
+        .tax_on_date(
+            unit_value.clone(),
+            qty.clone(),
+            NaiveDate::from_ymd_opt(2021, 6, 1).unwrap(),
+        )
+        .unwrap();
+    assert_eq!(tax, BigDecimal::from_str("18").unwrap());
+
+    let tax = taxer
+        .tax_on_date(
+            unit_value.clone(),
+            qty.clone(),
+            NaiveDate::from_ymd_opt(2025, 6, 1).unwrap(),
+        )
+        .unwrap();
+    assert_eq!(tax, BigDecimal::from_str("19").unwrap());
+
+    let tax = taxer
+        .tax_on_date(
+            unit_value,
+            qty,
+            NaiveDate::from_ymd_opt(2019, 6, 1).unwrap(),
+        )
+        .unwrap();
+    assert_eq!(tax, BigDecimal::from_str("0").unwrap());
+
+    let err = taxer.add_tax_dated(
+        BigDecimal::from_str("-1").unwrap(),
+        Stage::OverTaxable,
+        Mode::Percentual,
+        NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+    );
+    assert!(err.is_some(), "negative dated tax should fail");
+}
+
+#[test]
+fn test_tax_computer_reject_duplicate_taxes() {
+    let mut taxer = TaxComputer::new();
+    taxer.set_reject_duplicate_taxes(true);
+
+    let err = taxer.add_tax_from_str("18", Stage::OverTaxable, Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let err = taxer.add_tax_from_str("18.0", Stage::OverTaxable, Mode::Percentual);
+    assert!(err.is_some(), "a duplicated tax should be rejected");
+
+    let err = taxer.add_tax_from_str("18", Stage::OverTax, Mode::Percentual);
+    assert!(
+        err.is_none(),
+        "same value in another stage is not a duplicate {:?}",
+        err
+    );
+
+    assert_eq!(
+        taxer.over_taxables().percent(),
+        BigDecimal::from_str("18").unwrap()
+    );
+
+    taxer.set_reject_duplicate_taxes(false);
+    let err = taxer.add_tax_from_str("18", Stage::OverTaxable, Mode::Percentual);
+    assert!(
+        err.is_none(),
+        "duplicates are cumulated when not rejected {:?}",
+        err
+    );
+    assert_eq!(
+        taxer.over_taxables().percent(),
+        BigDecimal::from_str("36").unwrap()
+    );
+}
+
+#[test]
+fn test_tax_computer_inclusive_price_per_base_unit() {
+    let mut taxer = TaxComputer::new();
+
+    let err = taxer.add_tax_from_str("18.0", Stage::OverTaxable, Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    let per_kg = taxer
+        .inclusive_price_per_base_unit(
+            BigDecimal::from_str("4").unwrap(),
+            BigDecimal::from_str("2.5").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(per_kg, BigDecimal::from_str("4.72").unwrap());
+
+    let result = taxer.inclusive_price_per_base_unit(
+        BigDecimal::from_str("4").unwrap(),
+        BigDecimal::from_str("0").unwrap(),
+    );
+    assert!(matches!(
+        result,
+        Err(baggins::tax::TaxError::DivisionByZero(_))
+    ));
+}
+
+#[test]
+fn test_tax_computer_assert_effective_rate() {
+    let mut taxer = TaxComputer::new();
+
+    let err = taxer.add_tax_from_str("18.0", Stage::OverTaxable, Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+    let err = taxer.add_tax_from_str("2.0", Stage::OverTax, Mode::Percentual);
+    assert!(err.is_none(), "error adding over tax {:?}", err);
+
+    let result = taxer.assert_effective_rate(
+        BigDecimal::from_str("100").unwrap(),
+        BigDecimal::from_str("1").unwrap(),
+        BigDecimal::from_str("20.36").unwrap(),
+        BigDecimal::from_str("0.01").unwrap(),
+    );
+    assert!(result.is_ok(), "expected rate should match {:?}", result);
+
+    let result = taxer.assert_effective_rate(
+        BigDecimal::from_str("100").unwrap(),
+        BigDecimal::from_str("1").unwrap(),
+        BigDecimal::from_str("20").unwrap(),
+        BigDecimal::from_str("0.1").unwrap(),
+    );
+    assert!(
+        matches!(result, Err(baggins::tax::TaxError::Other(_))),
+        "expected rate should not match {:?}",
+        result
+    );
+}
+
+#[test]
+fn test_tax_computer_max_tax_per_line() {
+    let mut taxer = TaxComputer::new();
+
+    let err = taxer.add_tax_from_str("18.0", Stage::OverTaxable, Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+    let err = taxer.set_max_tax_per_line(Some(BigDecimal::from_str("10").unwrap()));
+    assert!(err.is_none(), "error setting max tax per line {:?}", err);
+
+    let (tax, capped) = taxer
+        .tax_with_cap(
+            BigDecimal::from_str("100").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(tax, BigDecimal::from_str("10").unwrap());
+    assert!(capped, "the cap should trim an 18 tax");
+
+    let (tax, capped) = taxer
+        .tax_with_cap(
+            BigDecimal::from_str("50").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(tax, BigDecimal::from_str("9").unwrap());
+    assert!(!capped, "the cap should not trim a 9 tax");
+
+    let tax = taxer
+        .tax_net(
+            BigDecimal::from_str("200").unwrap(),
+            BigDecimal::from_str("2").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(tax, BigDecimal::from_str("10").unwrap());
+}
+
+#[test]
+fn test_tax_computer_base_excluding_ignorable() {
+    let mut taxer = TaxComputer::new();
+
+    let err = taxer.add_tax_from_str("10.0", Stage::OverTaxable, Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+    let err = taxer.add_tax_from_str("5.0", Stage::OverTaxIgnorable, Mode::Percentual);
+    assert!(err.is_none(), "error adding ignorable tax {:?}", err);
+    let err = taxer.add_tax_from_str("2.0", Stage::OverTaxIgnorable, Mode::AmountLine);
+    assert!(err.is_none(), "error adding ignorable tax {:?}", err);
+
+    // net 200, over taxable 20, ignorable 10 + 2
+    let base = taxer
+        .base_excluding_ignorable(
+            BigDecimal::from_str("232").unwrap(),
+            BigDecimal::from_str("2").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(base, BigDecimal::from_str("220").unwrap());
+
+    let net = taxer
+        .un_tax(
+            BigDecimal::from_str("232").unwrap(),
+            BigDecimal::from_str("2").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(net, BigDecimal::from_str("200").unwrap());
+}
+
+#[test]
+fn test_tax_computer_net_price_from_inclusive_cost() {
+    let mut taxer = TaxComputer::new();
+
+    let err = taxer.add_tax_from_str("18.0", Stage::OverTaxable, Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+
+    // 118 with 18% included is a net cost of 100, plus a 30% markup
+    let price = taxer
+        .net_price_from_inclusive_cost(
+            BigDecimal::from_str("118").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+            BigDecimal::from_str("30").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(price, BigDecimal::from_str("130").unwrap());
+
+    let err = taxer.net_price_from_inclusive_cost(
+        BigDecimal::from_str("118").unwrap(),
+        BigDecimal::from_str("1").unwrap(),
+        BigDecimal::from_str("-30").unwrap(),
+    );
+    assert!(err.is_err(), "a negative markup should fail");
+}
+
+#[test]
+fn test_tax_computer_compile() {
+    let mut taxer = TaxComputer::new();
+
+    let taxes = [
+        ("18.0", Stage::OverTaxable, Mode::Percentual),
+        ("0.5", Stage::OverTaxable, Mode::AmountUnit),
+        ("2.0", Stage::OverTaxable, Mode::AmountLine),
+        ("3.0", Stage::OverTax, Mode::Percentual),
+        ("0.25", Stage::OverTax, Mode::AmountUnit),
+        ("1.0", Stage::OverTax, Mode::AmountLine),
+        ("4.0", Stage::OverTaxIgnorable, Mode::Percentual),
+        ("0.1", Stage::OverTaxIgnorable, Mode::AmountUnit),
+        ("1.5", Stage::OverTaxIgnorable, Mode::AmountLine),
+    ];
+
+    for (value, stage, mode) in taxes {
+        let err = taxer.add_tax_from_str(value, stage, mode);
+        assert!(err.is_none(), "error adding tax {:?}", err);
+    }
+
+    let plan = taxer.compile();
+
+    for (uv, qty) in [
+        ("100", "1"),
+        ("33.33", "3"),
+        ("0", "5"),
+        ("12.75", "0"),
+        ("0.01", "1000"),
+    ] {
+        let uv = BigDecimal::from_str(uv).unwrap();
+        let qty = BigDecimal::from_str(qty).unwrap();
+
+        let expected = taxer.tax(uv.clone(), qty.clone()).unwrap();
+        let compiled = plan.apply(&uv, &qty).unwrap();
+
+        assert_eq!(compiled, expected, "compiled plan for {} x {}", uv, qty);
+    }
+
+    assert!(plan
+        .apply(
+            &BigDecimal::from_str("-1").unwrap(),
+            &BigDecimal::from_str("1").unwrap()
+        )
+        .is_err());
+}
+
+#[test]
+fn test_tax_computer_assessable_cap() {
+    let mut taxer = TaxComputer::new();
+
+    let err = taxer.add_tax_from_str("10.0", Stage::OverTaxable, Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+    let err = taxer.add_tax_with_assessable_cap(
+        BigDecimal::from_str("5.0").unwrap(),
+        Stage::OverTaxable,
+        Some(BigDecimal::from_str("10000").unwrap()),
+    );
+    assert!(err.is_none(), "error adding capped tax {:?}", err);
+
+    // under the cap both taxes apply to the whole line
+    let tax = taxer
+        .tax(
+            BigDecimal::from_str("2000").unwrap(),
+            BigDecimal::from_str("2").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(tax, BigDecimal::from_str("600").unwrap());
+
+    // over the cap the 5% only applies to the first 10000
+    let tax = taxer
+        .tax(
+            BigDecimal::from_str("15000").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(tax, BigDecimal::from_str("2000").unwrap());
+
+    let tax = taxer
+        .tax_net(
+            BigDecimal::from_str("15000").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(tax, BigDecimal::from_str("2000").unwrap());
+
+    let plan = taxer.compile();
+    let tax = plan
+        .apply(
+            &BigDecimal::from_str("15000").unwrap(),
+            &BigDecimal::from_str("1").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(tax, BigDecimal::from_str("2000").unwrap());
+}
+
+#[test]
+fn test_tax_computer_assessable_cap_un_tax() {
+    let mut taxer = TaxComputer::new();
+
+    let err = taxer.add_tax_with_assessable_cap(
+        BigDecimal::from_str("5.0").unwrap(),
+        Stage::OverTaxable,
+        Some(BigDecimal::from_str("10000").unwrap()),
+    );
+    assert!(err.is_none(), "error adding capped tax {:?}", err);
+
+    // the capped tax can't be removed with the un tax equation
+    let result = taxer.un_tax(
+        BigDecimal::from_str("15500").unwrap(),
+        BigDecimal::from_str("1").unwrap(),
+    );
+    assert!(
+        matches!(result, Err(baggins::tax::TaxError::Other(_))),
+        "{:?}",
+        result
+    );
+    assert!(taxer
+        .un_tax_ratio(
+            BigDecimal::from_str("15500").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+        )
+        .is_err());
+}
+
+#[test]
+fn test_tax_computer_percent_of_tax() {
+    let mut taxer = TaxComputer::new();
+
+    let err = taxer.add_tax_from_str("18.0", Stage::OverTaxable, Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+    let err = taxer.add_tax_from_str("2.0", Stage::OverTax, Mode::PercentOfTax);
+    assert!(err.is_none(), "error adding percent of tax {:?}", err);
+
+    let err = taxer.add_tax_from_str("2.0", Stage::OverTaxable, Mode::PercentOfTax);
+    assert!(
+        err.is_some(),
+        "percent of tax must be rejected out of the over tax stage"
+    );
+
+    let vat = BigDecimal::from_str("180").unwrap();
+    let surcharge = &vat * BigDecimal::from_str("0.02").unwrap();
+
+    let tax = taxer
+        .tax(
+            BigDecimal::from_str("500").unwrap(),
+            BigDecimal::from_str("2").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(tax, &vat + &surcharge);
+
+    let breakdown = taxer
+        .tax_net_by_stage(
+            BigDecimal::from_str("1000").unwrap(),
+            BigDecimal::from_str("2").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(breakdown.over_taxable, vat);
+    assert_eq!(breakdown.over_tax, surcharge);
+
+    let plan = taxer.compile();
+    let tax = plan
+        .apply(
+            &BigDecimal::from_str("500").unwrap(),
+            &BigDecimal::from_str("2").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(tax, &vat + &surcharge);
+
+    let net = taxer
+        .un_tax(
+            BigDecimal::from_str("1183.6").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(net, BigDecimal::from_str("1000").unwrap());
+}
+
+#[test]
+fn test_tax_computer_tax_with_bases() {
+    let mut taxer = TaxComputer::new();
+
+    let err = taxer.add_tax_from_str("18.0", Stage::OverTaxable, Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+    let err = taxer.add_tax_from_str("10.0", Stage::OverTax, Mode::Percentual);
+    assert!(err.is_none(), "error adding over tax {:?}", err);
+    let err = taxer.add_tax_from_str("5.0", Stage::OverTaxIgnorable, Mode::Percentual);
+    assert!(err.is_none(), "error adding ignorable tax {:?}", err);
+
+    let net = BigDecimal::from_str("1000").unwrap();
+    let (tax, bases) = taxer
+        .tax_with_bases(net.clone(), BigDecimal::from_str("1").unwrap())
+        .unwrap();
+
+    let over_taxable_tax = BigDecimal::from_str("180").unwrap();
+    assert_eq!(bases.over_taxable_base, net);
+    assert_eq!(bases.over_tax_base, &net + &over_taxable_tax);
+    assert_eq!(bases.over_tax_ignorable_base, net);
+    assert_eq!(tax, BigDecimal::from_str("348").unwrap());
+}
+
+#[test]
+fn test_tax_computer_bases_with_qty() {
+    let mut taxer = TaxComputer::new();
+
+    let err = taxer.add_tax_from_str("10.0", Stage::OverTaxable, Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+    let err = taxer.add_tax_from_str("5.0", Stage::OverTax, Mode::Percentual);
+    assert!(err.is_none(), "error adding over tax {:?}", err);
+
+    let tax = taxer
+        .tax(
+            BigDecimal::from_str("100").unwrap(),
+            BigDecimal::from_str("3").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(tax, BigDecimal::from_str("46.5").unwrap());
+
+    // the over tax base is the net of the line plus its over taxable taxes, 300 + 30
+    let bases = taxer.bases();
+    assert_eq!(
+        bases.over_taxable_base,
+        BigDecimal::from_str("300").unwrap()
+    );
+    assert_eq!(bases.over_tax_base, BigDecimal::from_str("330").unwrap());
+    assert_eq!(
+        bases.over_tax_ignorable_base,
+        BigDecimal::from_str("300").unwrap()
+    );
+}
+
+#[test]
+fn test_tax_computer_order_independence() {
+    let taxes = [
+        ("18.0", Stage::OverTaxable, Mode::Percentual),
+        ("1.5", Stage::OverTaxable, Mode::AmountUnit),
+        ("10.0", Stage::OverTax, Mode::Percentual),
+        ("2.0", Stage::OverTax, Mode::AmountLine),
+        ("5.0", Stage::OverTaxIgnorable, Mode::Percentual),
+        ("0.75", Stage::OverTaxIgnorable, Mode::AmountUnit),
+    ];
+
+    let permutations: [[usize; 6]; 4] = [
+        [0, 1, 2, 3, 4, 5],
+        [5, 4, 3, 2, 1, 0],
+        [2, 0, 4, 1, 5, 3],
+        [3, 5, 1, 4, 0, 2],
+    ];
+
+    let mut results = Vec::new();
+    let mut plans = Vec::new();
+    let mut taxers = Vec::new();
+
+    for permutation in permutations {
+        let mut taxer = TaxComputer::new();
+
+        for index in permutation {
+            let (value, stage, mode) = &taxes[index];
+            let err = taxer.add_tax_from_str(*value, stage.clone(), mode.clone());
+            assert!(err.is_none(), "error adding tax {:?}", err);
+        }
+        let err = taxer.add_tax_with_assessable_cap(
+            BigDecimal::from_str(if permutation[0] % 2 == 0 { "3" } else { "4" }).unwrap(),
+            Stage::OverTaxable,
+            Some(BigDecimal::from_str("500").unwrap()),
+        );
+        assert!(err.is_none(), "error adding capped tax {:?}", err);
+        let err = taxer.add_tax_with_assessable_cap(
+            BigDecimal::from_str(if permutation[0] % 2 == 0 { "4" } else { "3" }).unwrap(),
+            Stage::OverTaxable,
+            Some(BigDecimal::from_str("500").unwrap()),
+        );
+        assert!(err.is_none(), "error adding capped tax {:?}", err);
+
+        results.push(
+            taxer
+                .tax(
+                    BigDecimal::from_str("123.45").unwrap(),
+                    BigDecimal::from_str("7").unwrap(),
+                )
+                .unwrap(),
+        );
+        plans.push(taxer.canonical_order().compile());
+        taxers.push(taxer);
+    }
+
+    assert!(
+        results.iter().all(|tax| *tax == results[0]),
+        "{:?}",
+        results
+    );
+    assert!(plans.iter().all(|plan| *plan == plans[0]));
+
+    // the registered order differs, the canonical order doesn't
+    assert_ne!(taxers[0], taxers[1]);
+    for taxer in &taxers {
+        assert_eq!(taxer.canonical_order(), taxers[0].canonical_order());
+    }
+
+    // the bases of the last computed line are left out of the comparison
+    taxers[0]
+        .tax(
+            BigDecimal::from_str("10").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(taxers[0].canonical_order(), taxers[1].canonical_order());
+}
+
+#[test]
+fn test_tax_computer_empty_is_identity() {
+    let mut taxer = TaxComputer::new();
+
+    let tax = taxer
+        .tax(
+            BigDecimal::from_str("100").unwrap(),
+            BigDecimal::from_str("2").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(tax, BigDecimal::from_str("0").unwrap());
+
+    let tax = taxer
+        .tax_net(
+            BigDecimal::from_str("200").unwrap(),
+            BigDecimal::from_str("2").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(tax, BigDecimal::from_str("0").unwrap());
+
+    let net = taxer
+        .un_tax(
+            BigDecimal::from_str("200").unwrap(),
+            BigDecimal::from_str("2").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(net, BigDecimal::from_str("200").unwrap());
+
+    let net = TaxComputer::default()
+        .un_tax(
+            BigDecimal::from_str("0").unwrap(),
+            BigDecimal::from_str("0").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(net, BigDecimal::from_str("0").unwrap());
+}
+
+#[test]
+fn test_tax_computer_stage_scale() {
+    let mut taxer = TaxComputer::new();
+
+    let err = taxer.add_tax_from_str("18.0", Stage::OverTaxable, Mode::Percentual);
+    assert!(err.is_none(), "error adding percentual tax {:?}", err);
+    let err = taxer.add_tax_from_str("7.0", Stage::OverTax, Mode::Percentual);
+    assert!(err.is_none(), "error adding over tax {:?}", err);
+    taxer.set_stage_scale(Stage::OverTaxable, 2);
+    taxer.set_stage_scale(Stage::OverTax, 0);
+
+    // 18% of 10.37 is 1.8666, rounded to 1.87. 7% of 10.37 + 1.87 is 0.8568, rounded to 1
+    let breakdown = taxer
+        .tax_net_by_stage(
+            BigDecimal::from_str("10.37").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(
+        breakdown.over_taxable,
+        BigDecimal::from_str("1.87").unwrap()
+    );
+    assert_eq!(breakdown.over_tax, BigDecimal::from_str("1").unwrap());
+
+    let tax = taxer
+        .tax(
+            BigDecimal::from_str("10.37").unwrap(),
+            BigDecimal::from_str("1").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(tax, BigDecimal::from_str("2.87").unwrap());
+
+    // 18% of 20.74 is 3.7332, rounded to 3.73. 7% of 20.74 + 3.73 is 1.7129, rounded to 2
+    let tax = taxer
+        .tax(
+            BigDecimal::from_str("10.37").unwrap(),
+            BigDecimal::from_str("2").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(tax, BigDecimal::from_str("5.73").unwrap());
+
+    let plan = taxer.compile();
+    let tax = plan
+        .apply(
+            &BigDecimal::from_str("10.37").unwrap(),
+            &BigDecimal::from_str("2").unwrap(),
+        )
+        .unwrap();
+    assert_eq!(tax, BigDecimal::from_str("5.73").unwrap());
+}
+
+#[test]
+fn test_tax_error_is_std_error() {
+    use baggins::tax::TaxError;
+    use std::error::Error;
+
+    let errors = [
+        TaxError::NegativeValue("negative".to_string()),
+        TaxError::OverMaxDiscount("over max".to_string()),
+        TaxError::InvalidDecimal("invalid decimal".to_string()),
+        TaxError::InvalidDiscountMode("invalid mode".to_string()),
+        TaxError::DivisionByZero("division".to_string()),
+        TaxError::Other("other".to_string()),
+    ];
+
+    for err in &errors {
+        let err: &dyn Error = err;
+        assert!(err.source().is_none());
+        assert!(!err.to_string().is_empty());
+    }
+
+    let boxed: Box<dyn Error> = Box::new(TaxError::NegativeValue("qty -1".to_string()));
+    assert!(boxed.to_string().contains("qty -1"));
+}
+
+#[test]
+fn test_tax_errors_compare_with_eq() {
+    use baggins::tax::TaxError;
+
+    let mut tax_calculator = TaxComputer::default();
+
+    assert_eq!(
+        tax_calculator.add_tax(
+            BigDecimal::from_str("-18").unwrap(),
+            Stage::OverTaxable,
+            Mode::Percentual
+        ),
+        Some(TaxError::NegativeValue(
+            "negative value adding percentual tax -18".to_string()
+        ))
+    );
+}